@@ -0,0 +1,141 @@
+// Generators of the trees the benches run on. Each bench only uses some of
+// them.
+#![allow(dead_code)]
+
+use puan_eval::puan_core::{
+    bic_or_bound, BicOrBound, BinaryInequalityConstraint, Bound, CoefRelation, LinearBoundedTree,
+};
+
+// A small xorshift generator, so that the trees are the same between runs
+// without depending on a random number crate
+pub struct Random(pub u64);
+
+impl Random {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn next(&mut self, below: usize) -> usize {
+        (self.next_u64() % below as u64) as usize
+    }
+}
+
+// Generates a layered tree with `leaves` Bound nodes and `bics` BIC nodes.
+// Every BIC relates to between 2 and 6 nodes created before it, so the tree
+// has no cycles.
+pub fn generate(leaves: usize, bics: usize) -> LinearBoundedTree {
+    let mut random = Random(0x2545f4914f6cdd1d);
+    let mut next = move || random.next_u64();
+
+    let mut tree = LinearBoundedTree::default();
+    for i in 0..leaves {
+        let lower = (next() % 4 == 0) as i64;
+        tree.nodes.insert(format!("leaf{}", i), BicOrBound {
+            part: Some(bic_or_bound::Part::Bound(Bound { lower, upper: 1 })),
+        });
+    }
+    for i in 0..bics {
+        let available = leaves + i;
+        let relations = (0..2 + next() % 5)
+            .map(|_| {
+                let child = (next() % available as u64) as usize;
+                CoefRelation {
+                    id: if child < leaves { format!("leaf{}", child) } else { format!("bic{}", child - leaves) },
+                    coefficient: (next() % 7) as i64 - 3,
+                }
+            })
+            .collect();
+        tree.nodes.insert(format!("bic{}", i), BicOrBound {
+            part: Some(bic_or_bound::Part::Bic(BinaryInequalityConstraint { relations })),
+        });
+    }
+    tree
+}
+
+pub fn bound(lower: i64, upper: i64) -> BicOrBound {
+    BicOrBound { part: Some(bic_or_bound::Part::Bound(Bound { lower, upper })) }
+}
+
+pub fn bic(relations: Vec<(String, i64)>) -> BicOrBound {
+    let relations = relations.into_iter()
+        .map(|(id, coefficient)| CoefRelation { id, coefficient })
+        .collect();
+    BicOrBound { part: Some(bic_or_bound::Part::Bic(BinaryInequalityConstraint { relations })) }
+}
+
+// Adds a root BIC that is 1 if all rules are 1, and the constant node "one"
+pub fn add_root(tree: &mut LinearBoundedTree, rules: Vec<String>) {
+    let count = rules.len() as i64;
+    let mut relations: Vec<(String, i64)> = rules.into_iter().map(|rule| (rule, 1)).collect();
+    relations.push(("one".to_string(), -count));
+    tree.nodes.insert("one".to_string(), bound(1, 1));
+    tree.nodes.insert("root".to_string(), bic(relations));
+}
+
+// Generates a product configuration model: options in groups of five of
+// which exactly one must be picked, and rules between random options of
+// different groups saying that one option requires or excludes another
+pub fn configurator(groups: usize, rules: usize) -> LinearBoundedTree {
+    let mut random = Random(0x2545f4914f6cdd1d);
+    let mut tree = LinearBoundedTree::default();
+    let mut all: Vec<String> = Vec::new();
+    for group in 0..groups {
+        let options: Vec<String> = (0..5).map(|option| format!("g{}o{}", group, option)).collect();
+        for option in options.iter() {
+            tree.nodes.insert(option.to_string(), bound(0, 1));
+        }
+        let mut at_least: Vec<(String, i64)> = options.iter().map(|option| (option.to_string(), 1)).collect();
+        at_least.push(("one".to_string(), -1));
+        let mut at_most: Vec<(String, i64)> = options.iter().map(|option| (option.to_string(), -1)).collect();
+        at_most.push(("one".to_string(), 1));
+        tree.nodes.insert(format!("g{}min", group), bic(at_least));
+        tree.nodes.insert(format!("g{}max", group), bic(at_most));
+        all.push(format!("g{}min", group));
+        all.push(format!("g{}max", group));
+    }
+    for rule in 0..rules {
+        let a = format!("g{}o{}", random.next(groups), random.next(5));
+        let b = format!("g{}o{}", random.next(groups), random.next(5));
+        let relations = if random.next(2) == 0 {
+            vec![(a, -1), (b, 1)]
+        } else {
+            vec![(a, -1), (b, -1), ("one".to_string(), 1)]
+        };
+        tree.nodes.insert(format!("rule{}", rule), bic(relations));
+        all.push(format!("rule{}", rule));
+    }
+    add_root(&mut tree, all);
+    tree
+}
+
+// Generates a random 3-SAT formula, with one BIC per clause
+pub fn three_sat(variables: usize, clauses: usize) -> LinearBoundedTree {
+    let mut random = Random(0x9e3779b97f4a7c15);
+    let mut tree = LinearBoundedTree::default();
+    for variable in 0..variables {
+        tree.nodes.insert(format!("x{}", variable), bound(0, 1));
+    }
+    let mut all: Vec<String> = Vec::new();
+    for clause in 0..clauses {
+        let mut negated = 0;
+        let mut relations: Vec<(String, i64)> = (0..3)
+            .map(|_| {
+                let id = format!("x{}", random.next(variables));
+                if random.next(2) == 0 {
+                    (id, 1)
+                } else {
+                    negated += 1;
+                    (id, -1)
+                }
+            })
+            .collect();
+        relations.push(("one".to_string(), negated - 1));
+        tree.nodes.insert(format!("c{}", clause), bic(relations));
+        all.push(format!("c{}", clause));
+    }
+    add_root(&mut tree, all);
+    tree
+}
@@ -0,0 +1,140 @@
+use std::collections::VecDeque;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use puan_eval::bitsliced::{BitSliced, LANES};
+use puan_eval::compiled::{compile, ParallelOptions};
+use puan_eval::program;
+use puan_eval::propagate::{propagate, propagate_bounds, propagate_in_place};
+use puan_eval::puan_core::{bic_or_bound, BicOrBound, Bound, LinearBoundedTree};
+
+mod common;
+
+use common::generate;
+
+// The queue based propagation that was used before trees were compiled, kept
+// here as a baseline. It looks up every relation by its string id. Note that
+// it never revisits a BIC that was popped before its children were
+// propagated, so on deep trees it does less work than `propagate`.
+fn propagate_legacy(tree: &LinearBoundedTree) -> LinearBoundedTree {
+    let mut _tree = tree.clone();
+    let mut queue: VecDeque<String> = tree.nodes.keys().cloned().collect();
+
+    while let Some(current) = queue.pop_front() {
+        let Some(bic_or_bound::Part::Bic(bic)) = &_tree.nodes[&current].part else {
+            continue;
+        };
+        let mut new_lower_bound: i64 = 0;
+        let mut new_upper_bound: i64 = 0;
+        let mut relations_taken_into_account: usize = 0;
+        for child in bic.relations.iter() {
+            match _tree.nodes.get(&child.id).and_then(|node| node.part.as_ref()) {
+                Some(bic_or_bound::Part::Bic(_)) => {
+                    queue.push_back(child.id.to_string());
+                    break;
+                },
+                Some(bic_or_bound::Part::Bound(bound)) => {
+                    if child.coefficient < 0 {
+                        new_lower_bound += bound.upper * child.coefficient;
+                        new_upper_bound += bound.lower * child.coefficient;
+                    } else {
+                        new_lower_bound += bound.lower * child.coefficient;
+                        new_upper_bound += bound.upper * child.coefficient;
+                    }
+                    relations_taken_into_account += 1;
+                },
+                None => break,
+            }
+        }
+        if relations_taken_into_account == bic.relations.len() {
+            _tree.nodes.insert(current, BicOrBound {
+                part: Some(bic_or_bound::Part::Bound(Bound {
+                    lower: (new_lower_bound >= 0) as i64,
+                    upper: (new_upper_bound >= 0) as i64,
+                })),
+            });
+        }
+    }
+
+    _tree
+}
+
+fn bench_propagate(c: &mut Criterion) {
+    let tree = generate(40_000, 80_000);
+    let compiled = compile(&tree).unwrap();
+
+    let mut group = c.benchmark_group("propagate_120k");
+    group.sample_size(10);
+    group.bench_function("legacy", |b| b.iter(|| propagate_legacy(&tree)));
+    group.bench_function("propagate", |b| b.iter(|| propagate(&tree).unwrap()));
+    group.bench_function("propagate_in_place", |b| {
+        b.iter_batched_ref(
+            || tree.clone(),
+            |tree| propagate_in_place(tree).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("propagate_bounds", |b| b.iter(|| propagate_bounds(&tree).unwrap()));
+    group.bench_function("compile", |b| b.iter(|| compile(&tree).unwrap()));
+    group.bench_function("evaluate", |b| {
+        b.iter_batched_ref(
+            || compiled.initial_values(),
+            |values| compiled.evaluate(values),
+            BatchSize::LargeInput,
+        )
+    });
+    let program = program::compile(&tree).unwrap();
+    group.bench_function("program", |b| {
+        b.iter_batched_ref(
+            || program.memory(),
+            |memory| program.execute(memory),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("evaluate_parallel", |b| {
+        let options = ParallelOptions { threshold: 0, ..Default::default() };
+        b.iter_batched_ref(
+            || compiled.initial_values(),
+            |values| compiled.evaluate_parallel(values, &options),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+// Evaluates the same 64 assignments of the free leaves one at a time and
+// bit-sliced. The assignments set the words of the free leaves directly, so
+// only evaluation is measured.
+fn bench_bitsliced(c: &mut Criterion) {
+    let tree = generate(40_000, 80_000);
+    let sliced = BitSliced::new(&tree).unwrap();
+    let compiled = sliced.compiled();
+
+    let mut lanes = sliced.initial_lanes();
+    for (i, node) in sliced.free().iter().enumerate() {
+        lanes[*node as usize] = (i as u64).wrapping_mul(0x9e3779b97f4a7c15);
+    }
+
+    let mut group = c.benchmark_group("assignments_64");
+    group.sample_size(10);
+    group.bench_function("evaluate", |b| {
+        b.iter(|| {
+            for lane in 0..LANES {
+                let mut values = compiled.initial_values();
+                for node in sliced.free().iter() {
+                    let value = (lanes[*node as usize] >> lane & 1) as i64;
+                    values.lower[*node as usize] = value;
+                    values.upper[*node as usize] = value;
+                }
+                compiled.evaluate(&mut values);
+            }
+        })
+    });
+    group.bench_function("bitsliced", |b| {
+        b.iter_batched_ref(|| lanes.clone(), |lanes| sliced.evaluate(lanes), BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_propagate, bench_bitsliced);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+use std::path::Path;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use puan_eval::io::read_file;
+use puan_eval::puan_core::LinearBoundedTree;
+use puan_eval::solve::{solve, SolveOptions, Status};
+
+mod common;
+
+use common::{configurator, three_sat};
+
+// Returns every model in `benches/models`, by file name, sorted
+fn models() -> Vec<(String, LinearBoundedTree)> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("benches").join("models");
+    let mut models: Vec<(String, LinearBoundedTree)> = std::fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            (name, read_file(&path).unwrap())
+        })
+        .collect();
+    models.sort_by(|a, b| a.0.cmp(&b.0));
+    models
+}
+
+fn bench_solve(c: &mut Criterion) {
+    let mut corpus = models();
+    corpus.push(("configurator_50k".to_string(), configurator(10_000, 5_000)));
+    corpus.push(("three_sat_200".to_string(), three_sat(200, 852)));
+    corpus.push(("three_sat_50k".to_string(), three_sat(50_000, 150_000)));
+    // Every model has a single node that no other node relates to, its root
+    let options = SolveOptions {
+        roots: Vec::new(),
+        time_limit: Some(Duration::from_secs(60)),
+    };
+
+    let mut group = c.benchmark_group("solve");
+    group.sample_size(10);
+    for (name, tree) in corpus.iter() {
+        let solution = solve(tree, &options).unwrap();
+        assert_ne!(solution.status, Status::Unknown, "{} was not solved in time", name);
+        group.bench_function(name.as_str(), |b| b.iter(|| solve(tree, &options).unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_solve);
+criterion_main!(benches);
@@ -10,9 +10,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .out_dir("src")
         .compile(
             &[
-                "puan_core.proto"
+                "puan_core.proto",
+                "puan_analysis.proto",
             ],
-            &["puan-proto/v1"], // Add the directory containing your proto files
+            &["puan-proto/v1", "proto/v1"], // Add the directory containing your proto files
         )?;
     Ok(())
 }
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::cdcl::Solver;
+use crate::error::LbtError;
+use crate::puan_core::LinearBoundedTree;
+use crate::solve::Status;
+
+// Options for `backbone`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackboneOptions {
+    // The nodes that must be 1. If empty, every node that no other node
+    // relates to must be 1.
+    pub roots: Vec<String>,
+    // How long to search, or no limit if None
+    pub time_limit: Option<Duration>,
+}
+
+// The result of `backbone`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backbone {
+    // The value of every leaf that has the same value in all assignments
+    // making the roots 1, leaving out leaves that are fixed in the tree
+    pub leaves: BTreeMap<String, i64>,
+    // Whether every leaf was decided. If the time limit was reached first,
+    // the leaves found so far are still fixed, but others may be too.
+    pub complete: bool,
+}
+
+// The nodes found to have the same value in every assignment satisfying the
+// constraints of a solver, see `fixed`
+pub(crate) struct Fixed {
+    pub values: Vec<(u32, bool)>,
+    pub complete: bool,
+}
+
+// Finds which of a list of nodes have the same value in every assignment
+// that satisfies the constraints of a solver.
+//
+// Nodes fixed by propagation alone are taken first. The remaining nodes are
+// candidates with the value they have in a first assignment found. Each
+// candidate is then searched with the other value assumed, preferring the
+// other value of every remaining candidate too: if that is Sat, every
+// candidate whose value differs in the new assignment is dropped, and
+// if it is Unsat, the candidate is fixed and required, which makes the
+// following searches easier. The other values are preferred once up front,
+// and after each search again only for the candidates the search assigned
+// their own value, since the solver saves the values it unassigns.
+//
+// # Arguments
+//
+// * `solver` - The solver with the constraints, e.g. the roots, added
+// * `nodes` - The nodes to decide, none of which may be fixed in the tree
+// * `deadline` - When to stop searching, or None to search until done
+//
+// # Returns
+//
+// The fixed nodes, or None if no assignment satisfies the constraints
+pub(crate) fn fixed(solver: &mut Solver, nodes: &[u32], deadline: Option<Instant>) -> Option<Fixed> {
+    solver.add_nodes(nodes);
+    if !solver.propagate_first_level() {
+        return None;
+    }
+    let mut values: Vec<(u32, bool)> = Vec::new();
+    let mut undecided: Vec<u32> = Vec::new();
+    for node in nodes.iter() {
+        match solver.value(*node) {
+            Some(value) => values.push((*node, value == 1)),
+            None => undecided.push(*node),
+        }
+    }
+
+    match solver.solve(&[], deadline) {
+        Status::Sat => {},
+        Status::Unsat => return None,
+        Status::Unknown => return Some(Fixed { values, complete: false }),
+    }
+    let mut candidates: Vec<(u32, bool)> = undecided.into_iter()
+        .map(|node| (node, solver.value(node) == Some(1)))
+        .collect();
+    // Trying the other value of every candidate first lets a single
+    // assignment drop many of them at once
+    let flipped: Vec<(u32, bool)> = candidates.iter().map(|(node, value)| (*node, !value)).collect();
+    solver.prefer(&flipped);
+    while let Some((node, value)) = candidates.pop() {
+        match solver.solve(&[(node, !value)], deadline) {
+            Status::Sat => candidates.retain(|(other, value)| solver.value(*other) == Some(*value as i64)),
+            Status::Unsat => {
+                values.push((node, value));
+                solver.add_clause(&[(node, value)]);
+            },
+            Status::Unknown => return Some(Fixed { values, complete: false }),
+        }
+        let changed: Vec<(u32, bool)> = candidates.iter()
+            .filter(|(other, value)| solver.phase(*other) == *value)
+            .map(|(other, value)| (*other, !value))
+            .collect();
+        solver.prefer(&changed);
+    }
+    Some(Fixed { values, complete: true })
+}
+
+// Finds the leaves of a tree that have the same value in every assignment of
+// its leaves that makes all roots 1, i.e. its backbone.
+//
+// Unlike `propagate`, which only fixes what follows from the bounds of the
+// children of each BIC, this also finds leaves whose other value rules out
+// all assignments further away, e.g. an option that is only excluded by two
+// rules together. See `fixed` for how.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to search. Leaves must either be fixed,
+//   i.e. have equal lower and upper bounds, or have the bound [0, 1].
+// * `options` - The roots and the time limit
+//
+// # Returns
+//
+// The backbone, None if no assignment makes all roots 1, or an error if a
+// node has no part, a root or child does not exist in the tree, or a leaf is
+// neither fixed nor [0, 1]
+pub fn backbone(tree: &LinearBoundedTree, options: &BackboneOptions) -> Result<Option<Backbone>, LbtError> {
+    let deadline = options.time_limit.map(|limit| Instant::now() + limit);
+    let mut solver = Solver::new(tree)?;
+    let roots: Vec<u32> = if options.roots.is_empty() {
+        solver.parentless()
+    } else {
+        options.roots.iter().map(|root| solver.node(root)).collect::<Result<_, _>>()?
+    };
+    solver.require(&roots);
+
+    // Leaves the roots do not depend on can take either value, so only the
+    // leaves the roots depend on are decided
+    let leaves: Vec<u32> = dependencies(&solver, &roots);
+    let Some(fixed) = fixed(&mut solver, &leaves, deadline) else {
+        return Ok(None);
+    };
+    let compiled = solver.compiled();
+    Ok(Some(Backbone {
+        leaves: fixed.values.into_iter()
+            .map(|(node, value)| (compiled.id(node).to_string(), value as i64))
+            .collect(),
+        complete: fixed.complete,
+    }))
+}
+
+// Returns the leaves that are not fixed in the tree and that any of the
+// roots depends on, in node order
+fn dependencies(solver: &Solver, roots: &[u32]) -> Vec<u32> {
+    let compiled = solver.compiled();
+    let mut needed = vec![false; compiled.len()];
+    let mut stack: Vec<u32> = roots.to_vec();
+    while let Some(node) = stack.pop() {
+        if !needed[node as usize] {
+            needed[node as usize] = true;
+            stack.extend(compiled.relations(node).0.iter());
+        }
+    }
+    (0..compiled.len() as u32)
+        .filter(|node| needed[*node as usize] && !compiled.is_bic[*node as usize] && solver.constant(*node).is_none())
+        .collect()
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
+use crate::compiled::{bic_bound, compile, CompiledTree, Values};
+use crate::error::LbtError;
+use crate::puan_core::{Bound, LinearBoundedTree};
+
+// A tree compiled once for evaluating many leaf assignments against it, e.g.
+// the same model under thousands of different selections. Only the bounds of
+// a fixed set of roots are reported for each assignment, and only the BICs
+// the roots depend on are propagated.
+#[derive(Debug, Clone)]
+pub struct Batch {
+    compiled: CompiledTree,
+    // The node index of every id
+    index: HashMap<String, u32>,
+    roots: Vec<u32>,
+    // The BICs the roots depend on, in evaluation order
+    order: Vec<u32>,
+    // Whether each node has a bound after evaluation
+    resolved: Vec<bool>,
+    initial: Values,
+}
+
+impl Batch {
+    // Compiles a tree for batch evaluation.
+    //
+    // # Arguments
+    //
+    // * `tree` - The LinearBoundedTree to evaluate
+    // * `roots` - The ids of the nodes to report the bounds of. If empty, all
+    //   nodes that no other node relates to are used, sorted by id.
+    //
+    // # Returns
+    //
+    // The compiled batch, or an error if a node has no part or a root does
+    // not exist in the tree
+    pub fn new(tree: &LinearBoundedTree, roots: &[String]) -> Result<Self, LbtError> {
+        let compiled = compile(tree)?;
+        let index: HashMap<String, u32> = (0..compiled.len() as u32)
+            .map(|node| (compiled.id(node).to_string(), node))
+            .collect();
+
+        let roots: Vec<u32> = if roots.is_empty() {
+            let mut has_parent = vec![false; compiled.len()];
+            for child in compiled.children.iter() {
+                has_parent[*child as usize] = true;
+            }
+            let mut roots: Vec<u32> = (0..compiled.len() as u32)
+                .filter(|node| !has_parent[*node as usize])
+                .collect();
+            roots.sort_unstable_by_key(|node| compiled.id(*node));
+            roots
+        } else {
+            roots.iter()
+                .map(|root| index.get(root).copied().ok_or_else(|| LbtError::UnknownNode(root.to_string())))
+                .collect::<Result<_, _>>()?
+        };
+
+        // Mark everything reachable from the roots, so that BICs no root
+        // depends on are never propagated
+        let mut needed = vec![false; compiled.len()];
+        let mut stack: Vec<u32> = roots.clone();
+        while let Some(node) = stack.pop() {
+            if needed[node as usize] {
+                continue;
+            }
+            needed[node as usize] = true;
+            stack.extend(compiled.relations(node).0.iter().filter(|child| !needed[**child as usize]));
+        }
+        let order: Vec<u32> = compiled.order.iter()
+            .copied()
+            .filter(|node| needed[*node as usize])
+            .collect();
+
+        Ok(Batch {
+            resolved: compiled.resolved(),
+            initial: compiled.initial_values(),
+            compiled,
+            index,
+            roots,
+            order,
+        })
+    }
+
+    // Returns the ids of the roots, in the order their bounds are returned
+    pub fn roots(&self) -> impl Iterator<Item = &str> {
+        self.roots.iter().map(|root| self.compiled.id(*root))
+    }
+
+    // Evaluates a single assignment.
+    //
+    // # Arguments
+    //
+    // * `assignment` - Bounds that replace the bounds of the Bound nodes with
+    //   the same id. Nodes that are not assigned keep the bound of the tree.
+    //
+    // # Returns
+    //
+    // The bound of every root, in the order of `roots`, or None for roots
+    // that cannot be propagated. An error is returned if an assigned id does
+    // not exist or is a BIC.
+    pub fn evaluate(&self, assignment: &HashMap<String, Bound>) -> Result<Vec<Option<Bound>>, LbtError> {
+        let mut values = self.initial.clone();
+        self.evaluate_with(&mut values, assignment)
+    }
+
+    // Evaluates many assignments, spread over the global rayon thread pool.
+    // Each worker thread reuses the same values between assignments.
+    //
+    // # Arguments
+    //
+    // * `assignments` - The assignments to evaluate, see `evaluate`
+    //
+    // # Returns
+    //
+    // The root bounds of each assignment, in the order of `assignments`, or
+    // the error of the first assignment that is not valid
+    pub fn evaluate_all(&self, assignments: &[HashMap<String, Bound>]) -> Result<Vec<Vec<Option<Bound>>>, LbtError> {
+        let results: Vec<Result<Vec<Option<Bound>>, LbtError>> = assignments.par_iter()
+            .map_init(|| self.initial.clone(), |values, assignment| self.evaluate_with(values, assignment))
+            .collect();
+        results.into_iter().collect()
+    }
+
+    // Evaluates an assignment on values that hold the initial values of all
+    // Bound nodes, and restores the assigned nodes afterwards so the values
+    // can be used for the next assignment
+    fn evaluate_with(&self, values: &mut Values, assignment: &HashMap<String, Bound>) -> Result<Vec<Option<Bound>>, LbtError> {
+        // Check the whole assignment before changing any value, so the values
+        // are left as they were on error
+        let assigned: Vec<(u32, &Bound)> = assignment.iter()
+            .map(|(id, bound)| match self.index.get(id) {
+                Some(node) if self.compiled.is_bic[*node as usize] => Err(LbtError::Malformed(
+                    format!("node {} is a BIC, only Bound nodes can be assigned", id)
+                )),
+                Some(node) => Ok((*node, bound)),
+                None => Err(LbtError::UnknownNode(id.to_string())),
+            })
+            .collect::<Result<_, _>>()?;
+        for (node, bound) in assigned.iter() {
+            values.lower[*node as usize] = bound.lower;
+            values.upper[*node as usize] = bound.upper;
+        }
+
+        for node in self.order.iter() {
+            let (children, coefficients) = self.compiled.relations(*node);
+            let (lower, upper) = bic_bound(children, coefficients, values);
+            values.lower[*node as usize] = lower;
+            values.upper[*node as usize] = upper;
+        }
+        let bounds = self.roots.iter()
+            .map(|root| {
+                let root = *root as usize;
+                self.resolved[root].then(|| Bound { lower: values.lower[root], upper: values.upper[root] })
+            })
+            .collect();
+
+        for (node, _) in assigned.iter() {
+            values.lower[*node as usize] = self.initial.lower[*node as usize];
+            values.upper[*node as usize] = self.initial.upper[*node as usize];
+        }
+        Ok(bounds)
+    }
+}
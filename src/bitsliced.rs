@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use crate::compiled::{compile, CompiledTree};
+use crate::error::LbtError;
+use crate::puan_core::LinearBoundedTree;
+
+// The number of assignments evaluated at once, one per bit of a lane
+pub const LANES: usize = 64;
+
+// A compiled 0/1 tree prepared for bit-sliced evaluation of up to 64 fixed
+// assignments at once.
+//
+// Every node gets one u64 word, where bit `j` is the value of the node under
+// assignment `j`. A BIC is evaluated by summing its relations for all 64
+// assignments in parallel: the sum is kept as a two's complement number of
+// `widths[node]` bits, stored as one word per bit ("bit planes"), and each
+// relation is added to it with a word-level ripple-carry adder. The BIC is 1
+// in every lane where the sign bit of the sum is 0.
+#[derive(Debug, Clone)]
+pub struct BitSliced {
+    compiled: CompiledTree,
+    // The node index of every id
+    index: HashMap<String, u32>,
+    // The number of bits needed to hold any sum of each BIC, 0 for Bounds
+    widths: Vec<u32>,
+    // The word of each node before any assignment is applied. Leaves fixed
+    // to 1 have all bits set, all other nodes have none.
+    initial: Vec<u64>,
+    // The leaves that are not fixed, i.e. that have the bound [0, 1]
+    free: Vec<u32>,
+    // Whether each node has a value after evaluation
+    resolved: Vec<bool>,
+}
+
+impl BitSliced {
+    // Compiles a tree for bit-sliced evaluation.
+    //
+    // # Arguments
+    //
+    // * `tree` - The LinearBoundedTree to evaluate. Every Bound must be
+    //   [0, 0], [0, 1] or [1, 1].
+    //
+    // # Returns
+    //
+    // The compiled tree, or an error if a node has no part or a Bound is not 0/1
+    pub fn new(tree: &LinearBoundedTree) -> Result<Self, LbtError> {
+        let compiled = compile(tree)?;
+        let n = compiled.len();
+
+        let mut widths: Vec<u32> = vec![0; n];
+        let mut initial: Vec<u64> = vec![0; n];
+        let mut free: Vec<u32> = Vec::new();
+        for node in 0..n {
+            if compiled.is_bic[node] {
+                // Every partial sum lies between the sum of the negative and
+                // the sum of the positive coefficients, so the sum always
+                // fits in enough bits for the larger of the two, plus a sign
+                let (_, coefficients) = compiled.relations(node as u32);
+                let positive: u128 = coefficients.iter().filter(|c| **c > 0).map(|c| c.unsigned_abs() as u128).sum();
+                let negative: u128 = coefficients.iter().filter(|c| **c < 0).map(|c| c.unsigned_abs() as u128).sum();
+                let magnitude = positive.max(negative.saturating_sub(1));
+                widths[node] = 1 + (128 - magnitude.leading_zeros());
+                continue;
+            }
+            match (compiled.lower[node], compiled.upper[node]) {
+                (0, 0) => {},
+                (1, 1) => initial[node] = u64::MAX,
+                (0, 1) => free.push(node as u32),
+                (lower, upper) => return Err(LbtError::Malformed(format!(
+                    "node {} has the bound [{}, {}], bit-sliced evaluation needs 0/1 bounds",
+                    compiled.id(node as u32), lower, upper,
+                ))),
+            }
+        }
+
+        Ok(BitSliced {
+            resolved: compiled.resolved(),
+            index: (0..n as u32).map(|node| (compiled.id(node).to_string(), node)).collect(),
+            compiled,
+            widths,
+            initial,
+            free,
+        })
+    }
+
+    // Returns the compiled tree
+    pub fn compiled(&self) -> &CompiledTree {
+        &self.compiled
+    }
+
+    // Returns the node index of an id, if it is part of the tree
+    pub fn node(&self, id: &str) -> Option<u32> {
+        self.index.get(id).copied()
+    }
+
+    // Returns the leaves that are not fixed, which every assignment must set.
+    // Setting the words of these leaves directly, e.g. to the bits of a
+    // counter, is the fastest way to test all their combinations.
+    pub fn free(&self) -> &[u32] {
+        &self.free
+    }
+
+    // Returns the words of all nodes with the fixed leaves set and every
+    // other node 0 in all lanes
+    pub fn initial_lanes(&self) -> Vec<u64> {
+        self.initial.clone()
+    }
+
+    // Packs up to 64 assignments into one word per node, with assignment `j`
+    // in bit `j`. Lanes without an assignment have all free leaves set to 0.
+    //
+    // # Arguments
+    //
+    // * `assignments` - The value of leaves by id. Every free leaf must be
+    //   assigned, while fixed leaves may only be assigned their own value.
+    //
+    // # Returns
+    //
+    // The words of all nodes, or an error if there are more than 64
+    // assignments or an assignment is not valid
+    pub fn pack(&self, assignments: &[HashMap<String, bool>]) -> Result<Vec<u64>, LbtError> {
+        if assignments.len() > LANES {
+            return Err(LbtError::Malformed(format!(
+                "{} assignments given, at most {} can be evaluated at once", assignments.len(), LANES,
+            )));
+        }
+
+        let mut lanes = self.initial_lanes();
+        for (lane, assignment) in assignments.iter().enumerate() {
+            let mut assigned: usize = 0;
+            for (id, value) in assignment.iter() {
+                let node = self.node(id).ok_or_else(|| LbtError::UnknownNode(id.to_string()))? as usize;
+                if self.compiled.is_bic[node] {
+                    return Err(LbtError::Malformed(format!("node {} is a BIC, only Bound nodes can be assigned", id)));
+                }
+                if self.compiled.lower[node] == self.compiled.upper[node] {
+                    if (self.compiled.lower[node] == 1) != *value {
+                        return Err(LbtError::Malformed(format!(
+                            "node {} is fixed to {} and cannot be assigned {}", id, self.compiled.lower[node], *value as i64,
+                        )));
+                    }
+                    continue;
+                }
+                assigned += 1;
+                if *value {
+                    lanes[node] |= 1 << lane;
+                }
+            }
+            if assigned != self.free.len() {
+                let missing = self.free.iter()
+                    .map(|node| self.compiled.id(*node))
+                    .find(|id| !assignment.contains_key(*id))
+                    .unwrap_or_default();
+                return Err(LbtError::Malformed(format!("node {} is not assigned in assignment {}", missing, lane)));
+            }
+        }
+        Ok(lanes)
+    }
+
+    // Evaluates all BICs that can be propagated, in all 64 lanes at once. The
+    // words of Bound nodes are read as they are, BICs that cannot be
+    // propagated are left unchanged.
+    //
+    // # Arguments
+    //
+    // * `lanes` - The words of all nodes, e.g. from `pack`
+    pub fn evaluate(&self, lanes: &mut [u64]) {
+        let mut planes: Vec<u64> = Vec::new();
+        for node in self.compiled.order.iter() {
+            let width = self.widths[*node as usize] as usize;
+            planes.clear();
+            planes.resize(width, 0);
+
+            let (children, coefficients) = self.compiled.relations(*node);
+            for (child, coefficient) in children.iter().zip(coefficients.iter()) {
+                let word = lanes[*child as usize];
+                if word == 0 {
+                    continue;
+                }
+                // Add or subtract the magnitude of the coefficient in the lanes
+                // where the child is 1. The loop stops as soon as no bits of
+                // the magnitude and no carry are left.
+                let magnitude = coefficient.unsigned_abs();
+                let mut carry: u64 = 0;
+                for (bit, plane) in planes.iter_mut().enumerate() {
+                    let rest = magnitude.checked_shr(bit as u32).unwrap_or(0);
+                    if rest == 0 && carry == 0 {
+                        break;
+                    }
+                    let addend = if rest & 1 == 1 { word } else { 0 };
+                    let sum = *plane ^ addend ^ carry;
+                    carry = if *coefficient < 0 {
+                        (!*plane & addend) | (!(*plane ^ addend) & carry)
+                    } else {
+                        (*plane & addend) | (carry & (*plane ^ addend))
+                    };
+                    *plane = sum;
+                }
+            }
+            // The sum is at least 0 in every lane where the sign bit is clear
+            lanes[*node as usize] = !planes[width - 1];
+        }
+    }
+
+    // Returns the value of a node in one lane after evaluation, or None if
+    // the id is not part of the tree or is a BIC that cannot be propagated
+    pub fn value(&self, lanes: &[u64], id: &str, lane: usize) -> Option<bool> {
+        let node = self.node(id)? as usize;
+        self.resolved[node].then(|| lanes[node] >> lane & 1 == 1)
+    }
+}
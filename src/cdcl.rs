@@ -0,0 +1,1055 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
+
+use crate::compiled::{compile, CompiledTree};
+use crate::error::LbtError;
+use crate::graph::relations;
+use crate::puan_core::LinearBoundedTree;
+use crate::solve::{SolveStats, Status};
+
+// Marks a variable without a reason, i.e. a decision or an assumption
+const NO_REASON: u32 = u32::MAX;
+// Marks a variable that is not in the heap
+const NOT_IN_HEAP: u32 = u32::MAX;
+// The number of conflicts in the first restart, which is multiplied by the
+// Luby sequence for all following restarts
+const RESTART_BASE: u64 = 64;
+// The number of learned constraints kept before the least active half is
+// removed, growing by 10% every time it is reached
+const LEARNED_LIMIT: usize = 2_000;
+const VARIABLE_DECAY: f64 = 0.95;
+const CONSTRAINT_DECAY: f64 = 0.999;
+// Why the constraints of BICs can be built without error once a tree is
+// compiled by `compile_searchable`
+pub(crate) const CHECKED: &str = "the constraints of every BIC were checked when the tree was compiled";
+
+// A pseudo-Boolean constraint `sum(coefficients[i] * literals[i]) >= degree`,
+// with positive coefficients sorted from largest to smallest, no larger than
+// the degree. A literal is a node and the value it must have, with literal
+// `2 * node` being true if the node is 1 and `2 * node + 1` if it is 0.
+// Clauses are constraints with all coefficients and the degree 1.
+struct Constraint {
+    literals: Vec<u32>,
+    coefficients: Vec<i64>,
+    degree: i64,
+    // The sum of all coefficients
+    total: i64,
+    learned: bool,
+    activity: f64,
+}
+
+// The literals, coefficients and degree of a constraint, as returned by
+// `normalise`
+pub(crate) type Normalised = (Vec<u32>, Vec<i64>, i64);
+
+// A conflict driven clause learning (CDCL) search over the BICs of a tree.
+//
+// Every BIC `b` with relations `c_i * x_i` becomes two pseudo-Boolean
+// constraints over its node and the nodes it relates to, which together say
+// `b = 1 if and only if sum(c_i * x_i) >= 0`:
+//
+// * `sum(c_i * x_i) + M * (1 - b) >= 0`, where `M` is the smallest value
+//   that makes the constraint hold whenever `b` is 0
+// * `-sum(c_i * x_i) - 1 + M' * b >= 0`, where `M'` is the smallest value
+//   that makes the constraint hold whenever `b` is 1
+//
+// Relations to the same node are summed and nodes with a fixed bound are
+// folded into the constant, so the constraints hold exactly for every
+// assignment rather than for the interval bounds `propagate` works with.
+// The sums are taken in 128 bits, and trees whose constraints do not fit in
+// 64 bits once summed are rejected when the solver is created.
+// Only BICs that are needed, i.e. that a root or an added constraint depends
+// on, become constraints.
+//
+// A constraint propagates a literal once the coefficient of the literal is
+// larger than the slack, the sum of the coefficients of all literals that
+// are not false minus the degree. When a constraint is violated, the
+// conflict is explained as a clause: every literal propagated by a
+// constraint is implied by the subset of its false literals that already
+// made the slack too small. These clauses are resolved until a single
+// literal of the current decision level is left (the first unique
+// implication point), and the result is learned as a new constraint. The
+// search then jumps back to the second highest level in the learned clause,
+// where the clause propagates, rather than just undoing the last decision.
+//
+// Decisions pick the variable with the highest activity, which is increased
+// for every variable taking part in a conflict and decays over time (VSIDS),
+// and give it the value it last had, initially 0. The search restarts from
+// the levels of the assumptions after a number of conflicts following the
+// Luby sequence, keeping everything learned, and drops the least active
+// learned constraints when there are too many of them.
+pub struct Solver {
+    compiled: CompiledTree,
+    index: HashMap<String, u32>,
+    // Whether each node is a variable of the search, i.e. not a fixed Bound
+    // and needed by a root or an added constraint
+    active: Vec<bool>,
+    constraints: Vec<Constraint>,
+    // The sum of the coefficients of the literals of each constraint that
+    // have not been found false by propagation yet
+    possible: Vec<i64>,
+    // For each literal, the constraints it is part of and its coefficient
+    occurrences: Vec<Vec<(u32, i64)>>,
+    // The value of each node, or -1 if it is not assigned
+    values: Vec<i8>,
+    levels: Vec<u32>,
+    reasons: Vec<u32>,
+    // The position of each assigned node on the trail
+    positions: Vec<u32>,
+    // The value each node had when it was last unassigned
+    phases: Vec<bool>,
+    // The literals made true, in order, and where each decision level starts
+    trail: Vec<u32>,
+    trail_limits: Vec<u32>,
+    // The first literal of the trail whose consequences are not propagated yet
+    head: usize,
+    activity: Vec<f64>,
+    variable_increment: f64,
+    constraint_increment: f64,
+    heap: Heap,
+    seen: Vec<bool>,
+    learned_limit: usize,
+    // Whether the constraints cannot be satisfied at all
+    inconsistent: bool,
+    // The assumptions decided on the first levels, one per level, which the
+    // next search keeps as long as it starts with the same assumptions
+    assumed: Vec<(u32, bool)>,
+    // The assumptions that could not all hold in the last search, see `core`
+    core: Vec<(u32, bool)>,
+    // Returns true once searches should stop, see `interrupt_when`
+    interrupted: Option<Box<dyn Fn() -> bool + Send>>,
+    stats: SolveStats,
+}
+
+impl Solver {
+    // Compiles a tree for searching. No node is required to be 1 yet, see
+    // `require`.
+    //
+    // # Arguments
+    //
+    // * `tree` - The LinearBoundedTree to search. Leaves must either be
+    //   fixed, i.e. have equal lower and upper bounds, or have the bound
+    //   [0, 1].
+    //
+    // # Returns
+    //
+    // The solver, or an error if a node has no part, a child does not exist
+    // in the tree, a leaf is neither fixed nor [0, 1], or the constraints of
+    // a BIC do not fit in 64 bits
+    pub fn new(tree: &LinearBoundedTree) -> Result<Self, LbtError> {
+        let compiled = compile_searchable(tree)?;
+        let n = compiled.len();
+        // A Bound with a lower bound above its upper bound can never be
+        // satisfied
+        let inconsistent = (0..n).any(|node| compiled.lower[node] > compiled.upper[node]);
+
+        Ok(Solver {
+            index: (0..n as u32).map(|node| (compiled.id(node).to_string(), node)).collect(),
+            compiled,
+            active: vec![false; n],
+            constraints: Vec::new(),
+            possible: Vec::new(),
+            occurrences: vec![Vec::new(); 2 * n],
+            values: vec![-1; n],
+            levels: vec![0; n],
+            reasons: vec![NO_REASON; n],
+            positions: vec![0; n],
+            phases: vec![false; n],
+            trail: Vec::new(),
+            trail_limits: Vec::new(),
+            head: 0,
+            activity: vec![0.0; n],
+            variable_increment: 1.0,
+            constraint_increment: 1.0,
+            heap: Heap::new(n),
+            seen: vec![false; n],
+            learned_limit: LEARNED_LIMIT,
+            inconsistent,
+            assumed: Vec::new(),
+            core: Vec::new(),
+            interrupted: None,
+            stats: SolveStats::default(),
+        })
+    }
+
+    // Makes every search stop with the status Unknown once a function returns
+    // true, e.g. when the client waiting for the result is gone. It is called
+    // about as often as the deadline is checked.
+    pub fn interrupt_when(&mut self, interrupted: impl Fn() -> bool + Send + 'static) {
+        self.interrupted = Some(Box::new(interrupted));
+    }
+
+    // Returns the compiled tree the solver searches
+    pub fn compiled(&self) -> &CompiledTree {
+        &self.compiled
+    }
+
+    // Returns the node index of an id, or an error if it is not in the tree
+    pub fn node(&self, id: &str) -> Result<u32, LbtError> {
+        self.index.get(id).copied().ok_or_else(|| LbtError::UnknownNode(id.to_string()))
+    }
+
+    // Returns the nodes that no other node relates to, in node order
+    pub fn parentless(&self) -> Vec<u32> {
+        parentless(&self.compiled)
+    }
+
+    // Returns the work done by all searches so far
+    pub fn stats(&self) -> SolveStats {
+        self.stats
+    }
+
+    // Returns the fixed value of a node, if it is a Bound with equal lower
+    // and upper bounds
+    pub fn constant(&self, node: u32) -> Option<i64> {
+        if node as usize >= self.compiled.len() {
+            return None;
+        }
+        constant(&self.compiled, node)
+    }
+
+    // Returns the assumptions of the last search that cannot all hold
+    // together, if it was Unsat. Every other assumption can be dropped
+    // without making the search Sat. Empty if the constraints cannot be
+    // satisfied under any assumptions.
+    pub fn core(&self) -> &[(u32, bool)] {
+        &self.core
+    }
+
+    // Adds a node to the search that is not part of the tree, e.g. to count
+    // how many literals of a constraint are true, and returns it. It is
+    // only constrained by the constraints added over it.
+    pub fn add_variable(&mut self) -> u32 {
+        let node = self.values.len() as u32;
+        self.active.push(true);
+        self.occurrences.push(Vec::new());
+        self.occurrences.push(Vec::new());
+        self.values.push(-1);
+        self.levels.push(0);
+        self.reasons.push(NO_REASON);
+        self.positions.push(0);
+        self.phases.push(false);
+        self.activity.push(0.0);
+        self.seen.push(false);
+        self.heap.positions.push(NOT_IN_HEAP);
+        self.heap.insert(node, &self.activity);
+        node
+    }
+
+    // Returns the value of a node in the last assignment found, or None if
+    // the node is neither fixed nor needed by any constraint
+    pub fn value(&self, node: u32) -> Option<i64> {
+        match self.values[node as usize] {
+            -1 => self.constant(node),
+            value => Some(value as i64),
+        }
+    }
+
+    // Makes the next search decide the given nodes before all others, trying
+    // the given values first rather than the values they last had. This
+    // undoes the last assignment found, since unassigning a node saves its
+    // value.
+    pub fn prefer(&mut self, preferred: &[(u32, bool)]) {
+        self.backjump(0);
+        // Every unassigned node is in the heap after backjumping, so its top
+        // has the highest activity of any node that can be decided
+        let highest = self.heap.top().map_or(0.0, |node| self.activity[node as usize]);
+        for (node, value) in preferred.iter() {
+            self.phases[*node as usize] = *value;
+            self.activity[*node as usize] = highest;
+            self.bump(*node);
+        }
+    }
+
+    // Returns the value a node is tried with first the next time it is
+    // decided: its value if it is assigned, since unassigning a node saves
+    // its value, and otherwise the value saved or preferred last
+    pub fn phase(&self, node: u32) -> bool {
+        match self.values[node as usize] {
+            -1 => self.phases[node as usize],
+            value => value == 1,
+        }
+    }
+
+    // Requires every node of a list to be 1
+    pub fn require(&mut self, nodes: &[u32]) {
+        for node in nodes.iter() {
+            self.add_clause(&[(*node, true)]);
+        }
+    }
+
+    // Requires at least one node of a list to have the given value, e.g. to
+    // exclude an assignment that was already found
+    pub fn add_clause(&mut self, literals: &[(u32, bool)]) {
+        let mut degree = 1;
+        let terms: Vec<(u32, i64)> = literals.iter()
+            .map(|(node, value)| if *value {
+                (*node, 1)
+            } else {
+                // 1 - x >= ... is -x >= ... - 1
+                degree -= 1;
+                (*node, -1)
+            })
+            .collect();
+        self.add_linear(&terms, degree).expect("clauses fit in 64 bits");
+    }
+
+    // Adds nodes and the BICs they depend on to the search without
+    // constraining them, so that `value` and `phase` can be asked for them
+    pub fn add_nodes(&mut self, nodes: &[u32]) {
+        if nodes.iter().any(|node| !self.active[*node as usize] && self.constant(*node).is_none()) {
+            self.backjump(0);
+        }
+        for node in nodes.iter() {
+            self.activate(*node);
+        }
+    }
+
+    // Requires `sum(coefficient * node) >= degree`. The BICs the nodes depend
+    // on are added to the search if they are not part of it yet.
+    //
+    // # Arguments
+    //
+    // * `terms` - The node and coefficient of every term of the sum
+    // * `degree` - The smallest value the sum may take
+    //
+    // # Returns
+    //
+    // Nothing, or an error if the constraint does not fit in 64 bits once
+    // the fixed nodes are folded into the degree. Nothing is added on error.
+    pub fn add_linear(&mut self, terms: &[(u32, i64)], degree: i64) -> Result<(), LbtError> {
+        // The sums are taken in 128 bits, see `normalise`
+        let mut degree = degree as i128;
+        let mut merged: BTreeMap<u32, i128> = BTreeMap::new();
+        for (node, coefficient) in terms.iter() {
+            match self.constant(*node) {
+                Some(value) => degree -= *coefficient as i128 * value as i128,
+                None => *merged.entry(*node).or_insert(0) += *coefficient as i128,
+            }
+        }
+        let constraint = normalise(merged, degree)?;
+
+        // The constraint is added below the lowest level any of its nodes is
+        // assigned on, where it cannot be violated and everything it
+        // propagates holds, so that the levels of the assumptions of the
+        // last search before it can be kept. Adding the constraints of new
+        // BICs needs the first level.
+        let inactive = terms.iter().any(|(node, _)| !self.active[*node as usize] && self.constant(*node).is_none());
+        let lowest = terms.iter()
+            .filter(|(node, _)| self.values[*node as usize] != -1)
+            .map(|(node, _)| self.levels[*node as usize])
+            .min();
+        match lowest {
+            _ if inactive => self.backjump(0),
+            Some(level) => self.backjump(level.saturating_sub(1)),
+            None => {},
+        }
+        for (node, _) in terms.iter() {
+            self.activate(*node);
+        }
+        if let Some(constraint) = constraint {
+            self.add_constraint(constraint, false);
+        }
+        Ok(())
+    }
+
+    // Propagates the constraints on the first level without deciding any
+    // node. Afterwards `value` gives the value a node has in every
+    // assignment satisfying the constraints, if propagation alone finds it.
+    //
+    // # Returns
+    //
+    // False if propagation runs into a contradiction, i.e. no assignment
+    // satisfies the constraints, true otherwise
+    pub fn propagate_first_level(&mut self) -> bool {
+        self.backjump(0);
+        if !self.inconsistent && self.propagate().is_some() {
+            self.stats.conflicts += 1;
+            self.inconsistent = true;
+        }
+        !self.inconsistent
+    }
+
+    // Makes a node and everything it depends on part of the search, adding
+    // the constraints of every BIC that was not part of it yet
+    fn activate(&mut self, node: u32) {
+        let mut stack = vec![node];
+        let mut bics: Vec<u32> = Vec::new();
+        while let Some(node) = stack.pop() {
+            if self.active[node as usize] || self.constant(node).is_some() {
+                continue;
+            }
+            self.active[node as usize] = true;
+            self.heap.insert(node, &self.activity);
+            if self.compiled.is_bic[node as usize] {
+                bics.push(node);
+                stack.extend(self.compiled.relations(node).0.iter());
+            }
+        }
+
+        for bic in bics {
+            for (terms, degree) in bic_constraints(&self.compiled, bic) {
+                if let Some(constraint) = normalise(terms, degree).expect(CHECKED) {
+                    self.add_constraint(constraint, false);
+                }
+            }
+        }
+    }
+
+    // Adds a constraint over literals of active nodes, as returned by
+    // `normalise`, and propagates it on the current level
+    fn add_constraint(&mut self, (literals, coefficients, degree): Normalised, learned: bool) {
+        let index = self.constraints.len() as u32;
+        for (literal, coefficient) in literals.iter().zip(coefficients.iter()) {
+            self.occurrences[*literal as usize].push((index, *coefficient));
+        }
+        let constraint = Constraint {
+            total: coefficients.iter().sum(),
+            literals,
+            coefficients,
+            degree,
+            learned,
+            activity: 0.0,
+        };
+        self.possible.push(self.propagated_possible(&constraint));
+        self.constraints.push(constraint);
+        if learned {
+            self.stats.learned += 1;
+        }
+
+        if self.check(index) && self.trail_limits.is_empty() {
+            self.inconsistent = true;
+        }
+    }
+
+    // Returns the sum of the coefficients of a constraint minus those of the
+    // literals already found false by propagation
+    fn propagated_possible(&self, constraint: &Constraint) -> i64 {
+        constraint.total - constraint.literals.iter()
+            .zip(constraint.coefficients.iter())
+            .filter(|(literal, _)| {
+                self.literal_value(**literal) == Some(false)
+                    && (self.positions[(**literal >> 1) as usize] as usize) < self.head
+            })
+            .map(|(_, coefficient)| *coefficient)
+            .sum::<i64>()
+    }
+
+    fn literal_value(&self, literal: u32) -> Option<bool> {
+        match self.values[(literal >> 1) as usize] {
+            -1 => None,
+            value => Some((value == 1) == (literal & 1 == 0)),
+        }
+    }
+
+    fn level(&self) -> u32 {
+        self.trail_limits.len() as u32
+    }
+
+    fn assign(&mut self, literal: u32, reason: u32) {
+        let node = (literal >> 1) as usize;
+        self.values[node] = (literal & 1 == 0) as i8;
+        self.levels[node] = self.level();
+        self.reasons[node] = reason;
+        self.positions[node] = self.trail.len() as u32;
+        self.trail.push(literal);
+    }
+
+    // Propagates the literals of a constraint whose coefficient is larger
+    // than its slack. Returns true if the constraint is violated.
+    fn check(&mut self, index: u32) -> bool {
+        let slack = self.possible[index as usize] - self.constraints[index as usize].degree;
+        if slack < 0 {
+            return true;
+        }
+        let constraint = &self.constraints[index as usize];
+        if constraint.coefficients.first().is_none_or(|largest| *largest <= slack) {
+            return false;
+        }
+        let implied: Vec<u32> = constraint.literals.iter()
+            .zip(constraint.coefficients.iter())
+            .take_while(|(_, coefficient)| **coefficient > slack)
+            .map(|(literal, _)| *literal)
+            .filter(|literal| self.values[(literal >> 1) as usize] == -1)
+            .collect();
+        for literal in implied {
+            self.stats.propagations += 1;
+            self.assign(literal, index);
+        }
+        false
+    }
+
+    // Propagates all literals on the trail. Returns the violated constraint
+    // if there is one.
+    fn propagate(&mut self) -> Option<u32> {
+        while self.head < self.trail.len() {
+            let falsified = (self.trail[self.head] ^ 1) as usize;
+            self.head += 1;
+            let occurrences = std::mem::take(&mut self.occurrences[falsified]);
+            for (index, coefficient) in occurrences.iter() {
+                self.possible[*index as usize] -= coefficient;
+            }
+            let conflict = occurrences.iter().map(|(index, _)| *index).find(|index| self.check(*index));
+            self.occurrences[falsified] = occurrences;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    // Undoes all assignments made after a decision level
+    fn backjump(&mut self, level: u32) {
+        if self.level() <= level {
+            return;
+        }
+        let start = self.trail_limits[level as usize] as usize;
+        for position in (start..self.trail.len()).rev() {
+            let literal = self.trail[position];
+            if position < self.head {
+                for (index, coefficient) in self.occurrences[(literal ^ 1) as usize].iter() {
+                    self.possible[*index as usize] += coefficient;
+                }
+            }
+            let node = (literal >> 1) as usize;
+            self.phases[node] = self.values[node] == 1;
+            self.values[node] = -1;
+            self.reasons[node] = NO_REASON;
+            self.heap.insert(node as u32, &self.activity);
+        }
+        self.trail.truncate(start);
+        self.trail_limits.truncate(level as usize);
+        self.assumed.truncate(level as usize);
+        self.head = self.head.min(start);
+    }
+
+    // Returns the false literals of a constraint that explain why it implied
+    // a literal, or why it is violated if no literal is given. Only literals
+    // that were false before the implied literal was assigned are used, the
+    // largest first, until they alone leave too little slack.
+    fn explain(&mut self, index: u32, implied: Option<u32>) -> Vec<u32> {
+        let constraint = &mut self.constraints[index as usize];
+        if constraint.learned {
+            constraint.activity += self.constraint_increment;
+        }
+        let constraint = &self.constraints[index as usize];
+        let (before, implied_coefficient) = match implied {
+            Some(literal) => {
+                let position = constraint.literals.iter().position(|other| *other == literal).unwrap();
+                (self.positions[(literal >> 1) as usize], constraint.coefficients[position])
+            },
+            None => (u32::MAX, 0),
+        };
+
+        // The false literals must sum to more than this for the slack to be
+        // too small
+        let target = constraint.total - constraint.degree - implied_coefficient;
+        let mut sum: i64 = 0;
+        let mut explanation: Vec<u32> = Vec::new();
+        for (literal, coefficient) in constraint.literals.iter().zip(constraint.coefficients.iter()) {
+            if sum > target {
+                break;
+            }
+            if Some(*literal) != implied
+                && self.literal_value(*literal) == Some(false)
+                && self.positions[(literal >> 1) as usize] < before
+            {
+                explanation.push(*literal);
+                sum += coefficient;
+            }
+        }
+        explanation
+    }
+
+    // Resolves a conflict into a learned clause, with the literal of the
+    // current level first, and returns it with the level to jump back to
+    fn analyse(&mut self, conflict: u32) -> (Vec<u32>, u32) {
+        let mut learned: Vec<u32> = vec![0];
+        let mut pending = 0;
+        let mut position = self.trail.len();
+        let mut reason = self.explain(conflict, None);
+        let asserting = loop {
+            for literal in reason.iter() {
+                let node = (literal >> 1) as usize;
+                if self.seen[node] || self.levels[node] == 0 {
+                    continue;
+                }
+                self.seen[node] = true;
+                self.bump(node as u32);
+                if self.levels[node] == self.level() {
+                    pending += 1;
+                } else {
+                    learned.push(*literal);
+                }
+            }
+
+            // The next literal of the current level taking part in the conflict
+            loop {
+                position -= 1;
+                if self.seen[(self.trail[position] >> 1) as usize] {
+                    break;
+                }
+            }
+            let literal = self.trail[position];
+            let node = (literal >> 1) as usize;
+            self.seen[node] = false;
+            pending -= 1;
+            if pending == 0 {
+                break literal;
+            }
+            reason = self.explain(self.reasons[node], Some(literal));
+        };
+        learned[0] = asserting ^ 1;
+        for literal in learned[1..].iter() {
+            self.seen[(literal >> 1) as usize] = false;
+        }
+
+        // The literal assigned last, other than the asserting one, decides
+        // the level to jump back to
+        let mut level = 0;
+        if learned.len() > 1 {
+            let highest = (1..learned.len())
+                .max_by_key(|position| self.levels[(learned[*position] >> 1) as usize])
+                .unwrap();
+            learned.swap(1, highest);
+            level = self.levels[(learned[1] >> 1) as usize];
+        }
+        (learned, level)
+    }
+
+    // Returns the assumptions that imply a false assumption is false,
+    // including the false assumption itself, by following the reasons of
+    // the literals back to the decisions they came from. Only assumptions
+    // are decided on the levels this is called on.
+    fn failed(&mut self, assumption: (u32, bool)) -> Vec<(u32, bool)> {
+        let mut core = vec![assumption];
+        let (node, _) = assumption;
+        if self.levels[node as usize] == 0 {
+            return core;
+        }
+        self.seen[node as usize] = true;
+        let mut pending = 1;
+        let mut position = self.trail.len();
+        while pending > 0 {
+            position -= 1;
+            let literal = self.trail[position];
+            let node = (literal >> 1) as usize;
+            if !self.seen[node] {
+                continue;
+            }
+            self.seen[node] = false;
+            pending -= 1;
+            if self.reasons[node] == NO_REASON {
+                core.push((node as u32, literal & 1 == 0));
+                continue;
+            }
+            for reason in self.explain(self.reasons[node], Some(literal)) {
+                let other = (reason >> 1) as usize;
+                if self.levels[other] > 0 && !self.seen[other] {
+                    self.seen[other] = true;
+                    pending += 1;
+                }
+            }
+        }
+        core
+    }
+
+    fn bump(&mut self, node: u32) {
+        self.activity[node as usize] += self.variable_increment;
+        if self.activity[node as usize] > 1e100 {
+            for activity in self.activity.iter_mut() {
+                *activity *= 1e-100;
+            }
+            self.variable_increment *= 1e-100;
+        }
+        self.heap.increase(node, &self.activity);
+    }
+
+    // Removes the least active half of the learned constraints with more
+    // than two literals. Must be called on the first decision level, where no
+    // constraint is the reason of an assignment that can be analysed.
+    fn reduce(&mut self) {
+        let mut learned: Vec<usize> = (0..self.constraints.len())
+            .filter(|index| self.constraints[*index].learned && self.constraints[*index].literals.len() > 2)
+            .collect();
+        learned.sort_by(|a, b| self.constraints[*a].activity.total_cmp(&self.constraints[*b].activity));
+        let mut removed = vec![false; self.constraints.len()];
+        for index in learned[..learned.len() / 2].iter() {
+            removed[*index] = true;
+        }
+
+        let mut kept = 0;
+        self.constraints.retain(|_| {
+            kept += 1;
+            !removed[kept - 1]
+        });
+        for occurrences in self.occurrences.iter_mut() {
+            occurrences.clear();
+        }
+        for (index, constraint) in self.constraints.iter().enumerate() {
+            for (literal, coefficient) in constraint.literals.iter().zip(constraint.coefficients.iter()) {
+                self.occurrences[*literal as usize].push((index as u32, *coefficient));
+            }
+        }
+        self.possible = self.constraints.iter().map(|constraint| self.propagated_possible(constraint)).collect();
+        for reason in self.reasons.iter_mut() {
+            *reason = NO_REASON;
+        }
+    }
+
+    // Searches for an assignment of all active nodes satisfying all
+    // constraints, with the assumed nodes having the given values. The
+    // assignment can be read with `value` if the status is Sat. Everything
+    // learned is kept for the next search.
+    //
+    // # Arguments
+    //
+    // * `assumptions` - Nodes and the value they must have in this search only
+    // * `deadline` - When to give up, or None to search until done
+    //
+    // # Returns
+    //
+    // Sat, Unsat if no assignment satisfies the constraints under the
+    // assumptions, or Unknown if the deadline was reached or the search was
+    // interrupted
+    pub fn solve(&mut self, assumptions: &[(u32, bool)], deadline: Option<Instant>) -> Status {
+        // The levels of the assumptions the last search started with too are
+        // kept, so that searching again with a few assumptions changed at the
+        // end does not decide all of them again
+        let shared = self.assumed.iter()
+            .zip(assumptions.iter())
+            .take_while(|(assumed, assumption)| assumed == assumption)
+            .count();
+        // Nodes stay active once they are, so only the assumptions after the
+        // shared ones can add constraints, which needs the first level
+        if assumptions[shared..].iter().any(|(node, _)| !self.active[*node as usize]) {
+            self.backjump(0);
+            for (node, _) in assumptions.iter() {
+                self.activate(*node);
+            }
+        } else {
+            self.backjump(shared as u32);
+        }
+        self.core.clear();
+        let mut restart = 0;
+        let mut conflicts: u64 = 0;
+        let mut limit = RESTART_BASE * luby(restart);
+        let mut steps: u64 = 0;
+
+        loop {
+            if self.inconsistent {
+                return Status::Unsat;
+            }
+            if let Some(conflict) = self.propagate() {
+                self.stats.conflicts += 1;
+                conflicts += 1;
+                if self.level() == 0 {
+                    self.inconsistent = true;
+                    return Status::Unsat;
+                }
+                let (learned, level) = self.analyse(conflict);
+                self.backjump(level);
+                let terms: BTreeMap<u32, i128> = learned.iter()
+                    .map(|literal| (literal >> 1, if literal & 1 == 0 { 1 } else { -1 }))
+                    .collect();
+                let negated = learned.iter().filter(|literal| *literal & 1 == 1).count() as i128;
+                if let Some(constraint) = normalise(terms, 1 - negated).expect("clauses fit in 64 bits") {
+                    self.add_constraint(constraint, true);
+                }
+                self.variable_increment /= VARIABLE_DECAY;
+                self.constraint_increment /= CONSTRAINT_DECAY;
+                continue;
+            }
+
+            // The clock is read at the start of every search, so that many
+            // short searches in a row still see the deadline
+            if steps.is_multiple_of(256)
+                && (deadline.is_some_and(|deadline| Instant::now() >= deadline)
+                    || self.interrupted.as_ref().is_some_and(|interrupted| interrupted()))
+            {
+                self.backjump(0);
+                return Status::Unknown;
+            }
+            steps += 1;
+            if conflicts >= limit {
+                self.stats.restarts += 1;
+                restart += 1;
+                conflicts = 0;
+                limit = RESTART_BASE * luby(restart);
+                // Restarts keep the levels of the assumptions, unless learned
+                // constraints are removed, which needs the first level
+                if self.stats.learned as usize > self.learned_limit {
+                    self.backjump(0);
+                    self.reduce();
+                    self.learned_limit += self.learned_limit / 10;
+                } else {
+                    self.backjump(self.assumed.len() as u32);
+                }
+                continue;
+            }
+
+            let literal = if let Some((node, value)) = assumptions.get(self.level() as usize) {
+                let literal = 2 * node + !value as u32;
+                match self.literal_value(literal) {
+                    Some(false) => {
+                        self.core = self.failed((*node, *value));
+                        return Status::Unsat;
+                    },
+                    // An empty level keeps levels and assumptions aligned
+                    Some(true) => {
+                        self.assumed.push((*node, *value));
+                        None
+                    },
+                    None => {
+                        self.assumed.push((*node, *value));
+                        Some(literal)
+                    },
+                }
+            } else {
+                let Some(node) = self.next_decision() else {
+                    return Status::Sat;
+                };
+                self.stats.decisions += 1;
+                Some(2 * node + !self.phases[node as usize] as u32)
+            };
+            self.trail_limits.push(self.trail.len() as u32);
+            if let Some(literal) = literal {
+                self.assign(literal, NO_REASON);
+            }
+        }
+    }
+
+    // Returns the unassigned active node with the highest activity
+    fn next_decision(&mut self) -> Option<u32> {
+        while let Some(node) = self.heap.pop(&self.activity) {
+            if self.values[node as usize] == -1 {
+                return Some(node);
+            }
+        }
+        None
+    }
+}
+
+// Compiles a tree after checking that it can be searched, i.e. that every
+// child is part of the tree, every leaf is either fixed or [0, 1] and the
+// constraints of every BIC fit in 64 bits, see `bic_constraints`
+pub(crate) fn compile_searchable(tree: &LinearBoundedTree) -> Result<CompiledTree, LbtError> {
+    for (id, node) in tree.nodes.iter() {
+        for relation in relations(id, node)? {
+            if !tree.nodes.contains_key(&relation.id) {
+                return Err(LbtError::UnknownNode(relation.id.to_string()));
+            }
+        }
+    }
+    let compiled = compile(tree)?;
+    for node in 0..compiled.len() {
+        let (lower, upper) = (compiled.lower[node], compiled.upper[node]);
+        if !compiled.is_bic[node] && lower < upper && (lower, upper) != (0, 1) {
+            return Err(LbtError::Malformed(format!(
+                "leaf {} has the bound [{}, {}], only fixed and [0, 1] leaves can be searched",
+                compiled.id(node as u32), lower, upper,
+            )));
+        }
+    }
+    // Leaves are checked first, since the constraints of a BIC only fit if
+    // its children are fixed or [0, 1]
+    for bic in (0..compiled.len() as u32).filter(|node| compiled.is_bic[*node as usize]) {
+        for (terms, degree) in bic_constraints(&compiled, bic) {
+            normalise(terms, degree).map_err(|_| too_large(&compiled, bic))?;
+        }
+    }
+    Ok(compiled)
+}
+
+// Returns the nodes that no other node relates to, in node order
+pub(crate) fn parentless(compiled: &CompiledTree) -> Vec<u32> {
+    let mut has_parent = vec![false; compiled.len()];
+    for child in compiled.children.iter() {
+        has_parent[*child as usize] = true;
+    }
+    (0..compiled.len() as u32).filter(|node| !has_parent[*node as usize]).collect()
+}
+
+// Returns the fixed value of a node, if it is a Bound with equal lower and
+// upper bounds
+pub(crate) fn constant(compiled: &CompiledTree, node: u32) -> Option<i64> {
+    let node = node as usize;
+    (!compiled.is_bic[node] && compiled.lower[node] == compiled.upper[node]).then_some(compiled.lower[node])
+}
+
+// Returns the two constraints `sum(coefficient * node) >= degree` that
+// together make a BIC 1 if and only if the sum of its relations is at least
+// 0, see `Solver`. Children with a fixed bound are folded into the degree.
+// The sums are taken in 128 bits, so that relations that cancel out, like
+// `M * x - M * x` for a large `M`, are summed exactly. Whether the
+// constraints fit in 64 bits is only known once they are normalised.
+pub(crate) fn bic_constraints(compiled: &CompiledTree, bic: u32) -> [(BTreeMap<u32, i128>, i128); 2] {
+    let (children, coefficients) = compiled.relations(bic);
+    let mut constant_sum: i128 = 0;
+    let mut sum: BTreeMap<u32, i128> = BTreeMap::new();
+    for (child, coefficient) in children.iter().zip(coefficients.iter()) {
+        match constant(compiled, *child) {
+            Some(value) => constant_sum += *coefficient as i128 * value as i128,
+            None => *sum.entry(*child).or_insert(0) += *coefficient as i128,
+        }
+    }
+    let minimum = constant_sum + sum.values().map(|coefficient| (*coefficient).min(0)).sum::<i128>();
+    let maximum = constant_sum + sum.values().map(|coefficient| (*coefficient).max(0)).sum::<i128>();
+
+    // sum + M * (1 - b) >= 0, i.e. sum - M * b >= -constant - M
+    let big = (-minimum).max(0);
+    let mut at_least = sum.clone();
+    *at_least.entry(bic).or_insert(0) -= big;
+
+    // -sum - 1 + M' * b >= 0, i.e. -sum + M' * b >= constant + 1
+    let big_below = (maximum + 1).max(0);
+    let mut below: BTreeMap<u32, i128> = sum.into_iter().map(|(child, coefficient)| (child, -coefficient)).collect();
+    *below.entry(bic).or_insert(0) += big_below;
+
+    [(at_least, -constant_sum - big), (below, constant_sum + 1)]
+}
+
+// The error for a BIC whose constraints do not fit in 64 bits
+fn too_large(compiled: &CompiledTree, bic: u32) -> LbtError {
+    LbtError::Malformed(format!("the relations of {} do not fit in 64 bits", compiled.id(bic)))
+}
+
+// Rewrites `sum(coefficient * node) >= degree` into a constraint over
+// literals with positive coefficients, sorted from largest to smallest and
+// no larger than the degree. The constraint is given in 128 bits and
+// returned in 64.
+//
+// # Returns
+//
+// The literals, coefficients and degree, None if the constraint always
+// holds, or an error if the degree or the sum of the coefficients does not
+// fit in 64 bits
+pub(crate) fn normalise(terms: BTreeMap<u32, i128>, degree: i128) -> Result<Option<Normalised>, LbtError> {
+    let mut degree = degree;
+    let mut literals: Vec<(i128, u32)> = Vec::with_capacity(terms.len());
+    for (node, coefficient) in terms.into_iter() {
+        if coefficient > 0 {
+            literals.push((coefficient, 2 * node));
+        } else if coefficient < 0 {
+            // c * x is c - c * (1 - x), where 1 - x is the negated literal
+            degree -= coefficient;
+            literals.push((-coefficient, 2 * node + 1));
+        }
+    }
+    if degree <= 0 {
+        return Ok(None);
+    }
+    // No literal can contribute more than the degree
+    for (coefficient, _) in literals.iter_mut() {
+        *coefficient = (*coefficient).min(degree);
+    }
+    if degree > i64::MAX as i128 || literals.iter().map(|(coefficient, _)| *coefficient).sum::<i128>() > i64::MAX as i128 {
+        return Err(LbtError::Malformed("the constraint does not fit in 64 bits".to_string()));
+    }
+    literals.sort_unstable_by_key(|(coefficient, _)| Reverse(*coefficient));
+    Ok(Some((
+        literals.iter().map(|(_, literal)| *literal).collect(),
+        literals.iter().map(|(coefficient, _)| *coefficient as i64).collect(),
+        degree as i64,
+    )))
+}
+
+// Returns the i-th element of the Luby sequence 1, 1, 2, 1, 1, 2, 4, 1, ...
+fn luby(mut i: u64) -> u64 {
+    let mut size: u64 = 1;
+    let mut exponent: u32 = 0;
+    while size < i + 1 {
+        exponent += 1;
+        size = 2 * size + 1;
+    }
+    while size - 1 != i {
+        size = (size - 1) / 2;
+        exponent -= 1;
+        i %= size;
+    }
+    1 << exponent
+}
+
+// A binary max-heap of nodes ordered by activity, which knows where each
+// node is so its position can be updated when the activity increases
+struct Heap {
+    nodes: Vec<u32>,
+    positions: Vec<u32>,
+}
+
+impl Heap {
+    fn new(n: usize) -> Self {
+        Heap { nodes: Vec::new(), positions: vec![NOT_IN_HEAP; n] }
+    }
+
+    fn insert(&mut self, node: u32, activity: &[f64]) {
+        if self.positions[node as usize] != NOT_IN_HEAP {
+            return;
+        }
+        self.positions[node as usize] = self.nodes.len() as u32;
+        self.nodes.push(node);
+        self.up(self.nodes.len() - 1, activity);
+    }
+
+    fn increase(&mut self, node: u32, activity: &[f64]) {
+        let position = self.positions[node as usize];
+        if position != NOT_IN_HEAP {
+            self.up(position as usize, activity);
+        }
+    }
+
+    fn top(&self) -> Option<u32> {
+        self.nodes.first().copied()
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<u32> {
+        let top = *self.nodes.first()?;
+        let last = self.nodes.pop().unwrap();
+        self.positions[top as usize] = NOT_IN_HEAP;
+        if !self.nodes.is_empty() {
+            self.nodes[0] = last;
+            self.positions[last as usize] = 0;
+            self.down(0, activity);
+        }
+        Some(top)
+    }
+
+    fn up(&mut self, mut position: usize, activity: &[f64]) {
+        let node = self.nodes[position];
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if activity[self.nodes[parent] as usize] >= activity[node as usize] {
+                break;
+            }
+            self.nodes[position] = self.nodes[parent];
+            self.positions[self.nodes[position] as usize] = position as u32;
+            position = parent;
+        }
+        self.nodes[position] = node;
+        self.positions[node as usize] = position as u32;
+    }
+
+    fn down(&mut self, mut position: usize, activity: &[f64]) {
+        let node = self.nodes[position];
+        loop {
+            let mut child = 2 * position + 1;
+            if child >= self.nodes.len() {
+                break;
+            }
+            if child + 1 < self.nodes.len()
+                && activity[self.nodes[child + 1] as usize] > activity[self.nodes[child] as usize]
+            {
+                child += 1;
+            }
+            if activity[self.nodes[child] as usize] <= activity[node as usize] {
+                break;
+            }
+            self.nodes[position] = self.nodes[child];
+            self.positions[self.nodes[position] as usize] = position as u32;
+            position = child;
+        }
+        self.nodes[position] = node;
+        self.positions[node as usize] = position as u32;
+    }
+}
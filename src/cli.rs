@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+
+use serde_json::json;
+
+use puan_eval::count::{count, CountOptions};
+use puan_eval::diff::{bound_changes_json, diff, diff_propagated};
+use puan_eval::dedup::dedup;
+use puan_eval::health::{health, HealthOptions};
+use puan_eval::io::{read_file, write_file};
+use puan_eval::model::write_model;
+use puan_eval::puan_core::Bound;
+use puan_eval::solve::{solve, SolveOptions, Status};
+
+const USAGE: &str = "\
+Usage: lbt <command> [arguments]
+
+Trees are read as protobuf text format from files ending with .txtpb,
+.textproto or .pbtxt, and as binary protobuf from all other files.
+
+Commands:
+  diff <before> <after> [--json] [--propagate] [--assign <id>=<lower>[:<upper>]]...
+      Prints the nodes that were added, removed or changed between two trees.
+      With --propagate, both trees are also propagated under the same leaf
+      assignment and the nodes whose resulting bound differs are printed.
+  compile <tree> <model>
+      Compiles a tree into a model file that can be memory mapped and
+      evaluated without decoding it.
+  dedup <tree> <output>
+      Merges structurally identical nodes and writes the smaller tree. Every
+      merged id is printed together with the id it was merged into.
+  solve <tree> [--root <id>]... [--time-limit <milliseconds>]
+      Searches for values of the [0, 1] leaves that make all roots 1, by
+      default every node that no other node relates to. Prints sat and the
+      value of every leaf, unsat, or unknown if the time limit was reached.
+  count <tree> [--root <id>]... [--project <id>]... [--time-limit <milliseconds>]
+      Prints the exact number of values of the [0, 1] leaves that make all
+      roots 1, or unknown if the time limit was reached. With --project,
+      values that only differ in other leaves are counted once.
+  health <tree> [--root <id>]... [--time-limit <milliseconds>]
+      Prints every node that is 0 whenever all roots are 1 (dead) and every
+      node that is 1 whenever they are (mandatory), each with the mandatory
+      BICs that together force its value, or ? if the time limit was reached
+      before they were found.
+";
+
+// Splits the arguments of a command into positional arguments, flags and
+// repeatable options with values, e.g. `--assign a=1`
+struct Arguments {
+    positional: Vec<String>,
+    flags: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Arguments {
+    fn parse(args: &[String], with_value: &[&str]) -> Result<Self, String> {
+        let mut parsed = Arguments { positional: Vec::new(), flags: Vec::new(), options: Vec::new() };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                if with_value.contains(&name) {
+                    let value = iter.next().ok_or_else(|| format!("--{} needs a value", name))?;
+                    parsed.options.push((name.to_string(), value.to_string()));
+                } else {
+                    parsed.flags.push(name.to_string());
+                }
+            } else {
+                parsed.positional.push(arg.to_string());
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.options.iter()
+            .filter(move |(option, _)| option == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Parses an assignment on the form `id=lower:upper` or `id=value`
+fn parse_assignment(assignment: &str) -> Result<(String, Bound), String> {
+    let (id, value) = assignment.rsplit_once('=')
+        .ok_or_else(|| format!("assignment {} must be on the form <id>=<lower>[:<upper>]", assignment))?;
+    let parse = |number: &str| number.parse::<i64>()
+        .map_err(|error| format!("invalid number {} in assignment {}: {}", number, assignment, error));
+    let bound = match value.split_once(':') {
+        Some((lower, upper)) => Bound { lower: parse(lower)?, upper: parse(upper)? },
+        None => Bound { lower: parse(value)?, upper: parse(value)? },
+    };
+    Ok((id.to_string(), bound))
+}
+
+// Parses the last --time-limit in milliseconds, if any
+fn time_limit(args: &Arguments) -> Result<Option<Duration>, String> {
+    args.values("time-limit")
+        .last()
+        .map(|millis| millis.parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|error| format!("invalid time limit {}: {}", millis, error)))
+        .transpose()
+}
+
+fn run_diff(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &["assign"])?;
+    let [before, after] = args.positional.as_slice() else {
+        return Err("diff needs exactly two files".to_string());
+    };
+    let before = read_file(Path::new(before)).map_err(|error| error.to_string())?;
+    let after = read_file(Path::new(after)).map_err(|error| error.to_string())?;
+
+    let assignment: HashMap<String, Bound> = args.values("assign")
+        .map(parse_assignment)
+        .collect::<Result<_, _>>()?;
+    let structure = diff(&before, &after);
+    let propagation = if args.flag("propagate") {
+        Some(diff_propagated(&before, &after, &assignment).map_err(|error| error.to_string())?)
+    } else {
+        None
+    };
+
+    if args.flag("json") {
+        let mut output = json!({ "structure": structure.to_json() });
+        if let Some(propagation) = propagation {
+            output["propagation"] = bound_changes_json(&propagation);
+        }
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        return Ok(());
+    }
+
+    print!("{}", structure);
+    if let Some(propagation) = propagation {
+        if !propagation.is_empty() {
+            println!("propagated:");
+        }
+        let show = |bound: &Option<Bound>| match bound {
+            Some(bound) => format!("[{}, {}]", bound.lower, bound.upper),
+            None => "unknown".to_string(),
+        };
+        for change in propagation {
+            println!("  {}: {} -> {}", change.id, show(&change.before), show(&change.after));
+        }
+    }
+    Ok(())
+}
+
+fn run_compile(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &[])?;
+    let [tree, model] = args.positional.as_slice() else {
+        return Err("compile needs a tree and a model file".to_string());
+    };
+    let tree = read_file(Path::new(tree)).map_err(|error| error.to_string())?;
+    write_model(Path::new(model), &tree).map_err(|error| error.to_string())
+}
+
+fn run_dedup(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &[])?;
+    let [tree, output] = args.positional.as_slice() else {
+        return Err("dedup needs a tree and an output file".to_string());
+    };
+    let tree = read_file(Path::new(tree)).map_err(|error| error.to_string())?;
+    let deduplicated = dedup(&tree).map_err(|error| error.to_string())?;
+    write_file(Path::new(output), &deduplicated.tree).map_err(|error| error.to_string())?;
+    for (alias, id) in deduplicated.aliases.iter() {
+        println!("{} -> {}", alias, id);
+    }
+    Ok(())
+}
+
+fn run_solve(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &["root", "time-limit"])?;
+    let [tree] = args.positional.as_slice() else {
+        return Err("solve needs exactly one tree".to_string());
+    };
+    let tree = read_file(Path::new(tree)).map_err(|error| error.to_string())?;
+    let time_limit = time_limit(&args)?;
+
+    let options = SolveOptions { roots: args.values("root").map(|root| root.to_string()).collect(), time_limit };
+    let solution = solve(&tree, &options).map_err(|error| error.to_string())?;
+    match solution.status {
+        Status::Sat => {
+            println!("sat");
+            for (id, value) in solution.model.iter() {
+                println!("  {} = {}", id, value);
+            }
+        },
+        Status::Unsat => println!("unsat"),
+        Status::Unknown => println!("unknown"),
+    }
+    Ok(())
+}
+
+fn run_count(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &["root", "project", "time-limit"])?;
+    let [tree] = args.positional.as_slice() else {
+        return Err("count needs exactly one tree".to_string());
+    };
+    let tree = read_file(Path::new(tree)).map_err(|error| error.to_string())?;
+    let options = CountOptions {
+        roots: args.values("root").map(|root| root.to_string()).collect(),
+        projection: args.values("project").map(|leaf| leaf.to_string()).collect(),
+        time_limit: time_limit(&args)?,
+    };
+    match count(&tree, &options).map_err(|error| error.to_string())? {
+        Some(count) => println!("{}", count),
+        None => println!("unknown"),
+    }
+    Ok(())
+}
+
+fn run_health(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &["root", "time-limit"])?;
+    let [tree] = args.positional.as_slice() else {
+        return Err("health needs exactly one tree".to_string());
+    };
+    let tree = read_file(Path::new(tree)).map_err(|error| error.to_string())?;
+    let time_limit = time_limit(&args)?;
+
+    let options = HealthOptions { roots: args.values("root").map(|root| root.to_string()).collect(), time_limit };
+    let Some(health) = health(&tree, &options).map_err(|error| error.to_string())? else {
+        println!("no values of the leaves make all roots 1");
+        return Ok(());
+    };
+    for (title, nodes) in [("dead", &health.dead), ("mandatory", &health.mandatory)] {
+        println!("{}: {}", title, nodes.len());
+        for (id, constraints) in nodes.iter() {
+            match constraints {
+                Some(constraints) if constraints.is_empty() => println!("  {}", id),
+                Some(constraints) => println!("  {} <- {}", id, constraints.join(", ")),
+                None => println!("  {} <- ?", id),
+            }
+        }
+    }
+    if !health.complete {
+        println!("time limit reached, more nodes may be dead or mandatory");
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(|command| command.as_str()) {
+        Some("diff") => run_diff(&args[1..]),
+        Some("compile") => run_compile(&args[1..]),
+        Some("dedup") => run_dedup(&args[1..]),
+        Some("solve") => run_solve(&args[1..]),
+        Some("count") => run_count(&args[1..]),
+        Some("health") => run_health(&args[1..]),
+        _ => {
+            eprint!("{}", USAGE);
+            process::exit(2);
+        },
+    };
+
+    if let Err(error) = result {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
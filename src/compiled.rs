@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
+use crate::error::LbtError;
+use crate::puan_core::{bic_or_bound, BicOrBound, Bound, LinearBoundedTree};
+
+// A LinearBoundedTree compiled into flat arrays for fast evaluation.
+//
+// Node ids are interned to dense u32 indices, in the order the nodes are
+// stored in the tree. The relations of all nodes are stored in compressed
+// sparse row (CSR) form: the relations of node `i` are found at
+// `offsets[i]..offsets[i + 1]` in `children` and `coefficients`. Bounds have
+// no relations.
+//
+// The string ids are only needed when going from and to a LinearBoundedTree,
+// evaluation itself works on the index arrays only. They are stored one after
+// the other in `names`, with the id of node `i` at
+// `name_offsets[i]..name_offsets[i + 1]`, to avoid one allocation per id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompiledTree {
+    pub names: String,
+    pub name_offsets: Vec<u32>,
+    // Whether each node is a BIC, all other nodes are Bounds
+    pub is_bic: Vec<bool>,
+    // The lower and upper bound of each Bound node. BICs hold [0, 1] here,
+    // which is the widest bound any BIC can be propagated to.
+    pub lower: Vec<i64>,
+    pub upper: Vec<i64>,
+    pub offsets: Vec<u32>,
+    pub children: Vec<u32>,
+    pub coefficients: Vec<i64>,
+    // The BICs that can be propagated, with every BIC after all of its BIC
+    // children. BICs that relate to a node outside the tree, or that are part
+    // of a cycle, are not here and neither are any BICs depending on them.
+    pub order: Vec<u32>,
+    // The BICs in `order` grouped into levels, with level `i` at
+    // `order[levels[i]..levels[i + 1]]`. The BIC children of a BIC are all in
+    // earlier levels, so the BICs within a level are independent of each other.
+    pub levels: Vec<u32>,
+}
+
+// When and how to spread evaluation over the worker threads of the global
+// rayon thread pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParallelOptions {
+    // The number of propagated BICs below which the tree is evaluated on the
+    // calling thread only
+    pub threshold: usize,
+    // The smallest number of BICs handed to a worker thread at once. Levels
+    // with fewer BICs than this are evaluated on the calling thread.
+    pub chunk_size: usize,
+}
+
+impl Default for ParallelOptions {
+    fn default() -> Self {
+        ParallelOptions { threshold: 100_000, chunk_size: 4_096 }
+    }
+}
+
+// The lower and upper bounds of every node of a compiled tree, indexed by node.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Values {
+    pub lower: Vec<i64>,
+    pub upper: Vec<i64>,
+}
+
+impl CompiledTree {
+    // Returns the number of nodes in the tree
+    pub fn len(&self) -> usize {
+        self.is_bic.len()
+    }
+
+    // Returns true if the tree has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.is_bic.is_empty()
+    }
+
+    // Returns the id of a node
+    pub fn id(&self, node: u32) -> &str {
+        &self.names[self.name_offsets[node as usize] as usize..self.name_offsets[node as usize + 1] as usize]
+    }
+
+    // Returns the node index of every id. The map is built on every call, so
+    // callers looking up many ids should hold on to it.
+    pub fn index(&self) -> HashMap<&str, u32> {
+        (0..self.len() as u32).map(|node| (self.id(node), node)).collect()
+    }
+
+    // Returns the children and coefficients of a node
+    pub fn relations(&self, node: u32) -> (&[u32], &[i64]) {
+        let range = self.offsets[node as usize] as usize..self.offsets[node as usize + 1] as usize;
+        (&self.children[range.clone()], &self.coefficients[range])
+    }
+
+    // Returns the values of all nodes before any BIC is propagated
+    pub fn initial_values(&self) -> Values {
+        Values { lower: self.lower.clone(), upper: self.upper.clone() }
+    }
+
+    // Returns whether each node has a bound after evaluation, i.e. whether it
+    // is a Bound or a BIC that can be propagated
+    pub fn resolved(&self) -> Vec<bool> {
+        let mut resolved: Vec<bool> = self.is_bic.iter().map(|is_bic| !is_bic).collect();
+        for node in self.order.iter() {
+            resolved[*node as usize] = true;
+        }
+        resolved
+    }
+
+    // Propagates all BICs in `order`, reading the bounds of their children from
+    // `values` and writing their own bounds back to it. The values of Bound
+    // nodes are left as they are, so they may be changed between evaluations.
+    //
+    // # Arguments
+    //
+    // * `values` - The values of all nodes, e.g. from `initial_values`
+    pub fn evaluate(&self, values: &mut Values) {
+        for node in self.order.iter() {
+            let (children, coefficients) = self.relations(*node);
+            let (lower, upper) = bic_bound(children, coefficients, values);
+            values.lower[*node as usize] = lower;
+            values.upper[*node as usize] = upper;
+        }
+    }
+
+    // Propagates all BICs like `evaluate`, but evaluates the BICs of each level
+    // in parallel. All BICs of a level are computed from the values of earlier
+    // levels before any of them is written, so the result is always the same
+    // as that of `evaluate`, no matter how the work is split between threads.
+    //
+    // # Arguments
+    //
+    // * `values` - The values of all nodes, e.g. from `initial_values`
+    // * `options` - When to evaluate in parallel, see `ParallelOptions`
+    pub fn evaluate_parallel(&self, values: &mut Values, options: &ParallelOptions) {
+        if self.order.len() < options.threshold {
+            self.evaluate(values);
+            return;
+        }
+
+        let mut bounds: Vec<(i64, i64)> = Vec::new();
+        for level in self.levels.windows(2) {
+            let level = &self.order[level[0] as usize..level[1] as usize];
+            if level.len() < options.chunk_size.max(1) * 2 {
+                for node in level.iter() {
+                    let (children, coefficients) = self.relations(*node);
+                    let (lower, upper) = bic_bound(children, coefficients, values);
+                    values.lower[*node as usize] = lower;
+                    values.upper[*node as usize] = upper;
+                }
+                continue;
+            }
+
+            let read: &Values = values;
+            level.par_iter()
+                .with_min_len(options.chunk_size.max(1))
+                .map(|node| {
+                    let (children, coefficients) = self.relations(*node);
+                    bic_bound(children, coefficients, read)
+                })
+                .collect_into_vec(&mut bounds);
+            for (node, (lower, upper)) in level.iter().zip(bounds.iter()) {
+                values.lower[*node as usize] = *lower;
+                values.upper[*node as usize] = *upper;
+            }
+        }
+    }
+
+    // Builds a new tree from the tree that was compiled, with all propagated
+    // BICs replaced by their bounds. Only the relations of the BICs that
+    // could not be propagated are copied.
+    //
+    // # Arguments
+    //
+    // * `values` - The values after evaluation
+    // * `tree` - The tree that was compiled
+    //
+    // # Returns
+    //
+    // The propagated tree
+    pub fn propagated(&self, values: &Values, tree: &LinearBoundedTree) -> LinearBoundedTree {
+        let resolved = self.resolved();
+        let mut index: Option<HashMap<&str, u32>> = None;
+
+        let mut propagated = LinearBoundedTree::default();
+        propagated.nodes.reserve(tree.nodes.len());
+        for (position, (id, node)) in tree.nodes.iter().enumerate() {
+            // Nodes are compiled in the order they are stored in the tree, so
+            // the node at each position is normally the node with the same
+            // index. Only fall back to looking up the id if it is not.
+            let compiled = if position < self.len() && self.id(position as u32) == id {
+                position as u32
+            } else {
+                match index.get_or_insert_with(|| self.index()).get(id.as_str()) {
+                    Some(compiled) => *compiled,
+                    None => {
+                        propagated.nodes.insert(id.to_string(), node.clone());
+                        continue;
+                    },
+                }
+            } as usize;
+
+            let node = if self.is_bic[compiled] && resolved[compiled] {
+                BicOrBound {
+                    part: Some(bic_or_bound::Part::Bound(Bound {
+                        lower: values.lower[compiled],
+                        upper: values.upper[compiled],
+                    })),
+                }
+            } else {
+                node.clone()
+            };
+            propagated.nodes.insert(id.to_string(), node);
+        }
+        propagated
+    }
+
+    // Writes the bounds of all propagated BICs back into a tree, which must be
+    // the tree that was compiled, or a copy of it
+    pub fn write_back(&self, values: &Values, tree: &mut LinearBoundedTree) {
+        // Nodes are compiled in the order they are stored in the tree, so if
+        // the tree still holds the same ids in the same order, all BICs can be
+        // replaced in a single pass without looking up their ids
+        let positional = tree.nodes.len() == self.len()
+            && tree.nodes.keys().enumerate().all(|(node, id)| self.id(node as u32) == id);
+        if positional {
+            let resolved = self.resolved();
+            for (node, existing) in tree.nodes.values_mut().enumerate() {
+                if self.is_bic[node] && resolved[node] {
+                    existing.part = Some(bic_or_bound::Part::Bound(Bound {
+                        lower: values.lower[node],
+                        upper: values.upper[node],
+                    }));
+                }
+            }
+            return;
+        }
+
+        for node in self.order.iter() {
+            let node = *node as usize;
+            if let Some(existing) = tree.nodes.get_mut(self.id(node as u32)) {
+                existing.part = Some(bic_or_bound::Part::Bound(Bound {
+                    lower: values.lower[node],
+                    upper: values.upper[node],
+                }));
+            }
+        }
+    }
+}
+
+// Computes the bound of a BIC from the bounds of its children. The BIC is
+// 1 if the sum of its relations is at least 0, so its lower bound is 1 if the
+// smallest possible sum is at least 0, and its upper bound is 1 if the largest
+// possible sum is at least 0.
+#[inline]
+pub fn bic_bound(children: &[u32], coefficients: &[i64], values: &Values) -> (i64, i64) {
+    let mut lower: i64 = 0;
+    let mut upper: i64 = 0;
+    for (child, coefficient) in children.iter().zip(coefficients.iter()) {
+        let child = *child as usize;
+        // We need to flip the child's bound if the coefficient is negative
+        // (because -1*(0,1) === (-1,0))
+        if *coefficient < 0 {
+            lower += values.upper[child] * coefficient;
+            upper += values.lower[child] * coefficient;
+        } else {
+            lower += values.lower[child] * coefficient;
+            upper += values.upper[child] * coefficient;
+        }
+    }
+    ((lower >= 0) as i64, (upper >= 0) as i64)
+}
+
+// Compiles a LinearBoundedTree into its flat representation.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to compile
+//
+// # Returns
+//
+// The compiled tree, or an error if a node has no part
+pub fn compile(tree: &LinearBoundedTree) -> Result<CompiledTree, LbtError> {
+    let index: HashMap<&str, u32> = tree.nodes.keys()
+        .enumerate()
+        .map(|(index, id)| (id.as_str(), index as u32))
+        .collect();
+
+    let n = tree.nodes.len();
+    let mut compiled = CompiledTree {
+        names: String::with_capacity(tree.nodes.keys().map(|id| id.len()).sum()),
+        name_offsets: Vec::with_capacity(n + 1),
+        is_bic: Vec::with_capacity(n),
+        lower: Vec::with_capacity(n),
+        upper: Vec::with_capacity(n),
+        offsets: Vec::with_capacity(n + 1),
+        ..Default::default()
+    };
+    compiled.offsets.push(0);
+    compiled.name_offsets.push(0);
+
+    // BICs relating to a node outside the tree can never be propagated
+    let mut dangling: Vec<bool> = vec![false; n];
+
+    for (node, (id, tree_node)) in tree.nodes.iter().enumerate() {
+        compiled.names.push_str(id);
+        compiled.name_offsets.push(compiled.names.len() as u32);
+
+        match &tree_node.part {
+            Some(bic_or_bound::Part::Bound(bound)) => {
+                compiled.is_bic.push(false);
+                compiled.lower.push(bound.lower);
+                compiled.upper.push(bound.upper);
+            },
+            Some(bic_or_bound::Part::Bic(bic)) => {
+                compiled.is_bic.push(true);
+                compiled.lower.push(0);
+                compiled.upper.push(1);
+                for relation in bic.relations.iter() {
+                    match index.get(relation.id.as_str()) {
+                        Some(child) => {
+                            compiled.children.push(*child);
+                            compiled.coefficients.push(relation.coefficient);
+                        },
+                        None => dangling[node] = true,
+                    }
+                }
+            },
+            None => return Err(LbtError::MissingPart(id.to_string())),
+        }
+        compiled.offsets.push(compiled.children.len() as u32);
+    }
+
+    (compiled.order, compiled.levels) = evaluation_order(&compiled, &dangling);
+    Ok(compiled)
+}
+
+// Orders the BICs so that every BIC comes after its BIC children, using
+// Kahn's algorithm. A BIC is only ordered once all its children are, so BICs
+// that are dangling, part of a cycle, or depending on such BICs are left out.
+// The BICs are ordered one level at a time, where a level holds the BICs whose
+// last child was ordered in the level before, and the offsets of the levels
+// in the order are returned as well.
+fn evaluation_order(compiled: &CompiledTree, dangling: &[bool]) -> (Vec<u32>, Vec<u32>) {
+    let n = compiled.is_bic.len();
+
+    // The number of relations to BICs not yet ordered, per BIC
+    let mut pending: Vec<u32> = vec![0; n];
+    // The parents of each node, also in CSR form
+    let mut parent_offsets: Vec<u32> = vec![0; n + 1];
+    for (node, count) in pending.iter_mut().enumerate() {
+        let (children, _) = compiled.relations(node as u32);
+        for child in children {
+            if compiled.is_bic[*child as usize] {
+                *count += 1;
+                parent_offsets[*child as usize + 1] += 1;
+            }
+        }
+    }
+    // Turn the counts into offsets by summing them up
+    let mut sum: u32 = 0;
+    for offset in parent_offsets.iter_mut() {
+        sum += *offset;
+        *offset = sum;
+    }
+    let mut parents: Vec<u32> = vec![0; parent_offsets[n] as usize];
+    let mut filled: Vec<u32> = parent_offsets[..n].to_vec();
+    for node in 0..n {
+        let (children, _) = compiled.relations(node as u32);
+        for child in children {
+            if compiled.is_bic[*child as usize] {
+                parents[filled[*child as usize] as usize] = node as u32;
+                filled[*child as usize] += 1;
+            }
+        }
+    }
+
+    let mut order: Vec<u32> = (0..n as u32)
+        .filter(|node| compiled.is_bic[*node as usize] && pending[*node as usize] == 0 && !dangling[*node as usize])
+        .collect();
+    let mut levels: Vec<u32> = vec![0];
+
+    // Every BIC between the start of the current level and the end of the
+    // order is in the current level. Their parents that have no children left
+    // to order are appended to the order and make up the next level.
+    let mut start = 0;
+    while start < order.len() {
+        let end = order.len();
+        levels.push(end as u32);
+        for position in start..end {
+            let node = order[position] as usize;
+            for parent in parents[parent_offsets[node] as usize..parent_offsets[node + 1] as usize].iter() {
+                pending[*parent as usize] -= 1;
+                if pending[*parent as usize] == 0 && !dangling[*parent as usize] {
+                    order.push(*parent);
+                }
+            }
+        }
+        start = end;
+    }
+
+    (order, levels)
+}
@@ -0,0 +1,181 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::puan_core::{bic_or_bound, BicOrBound, CoefRelation, LinearBoundedTree};
+
+// A separately authored tree that is merged into a composed tree under a
+// namespace, e.g. the engine or the wheels of a product model.
+#[derive(Debug, Clone)]
+pub struct Module<'a> {
+    pub namespace: String,
+    pub tree: &'a LinearBoundedTree,
+}
+
+// Options for how modules are merged.
+#[derive(Debug, Clone)]
+pub struct ComposeOptions {
+    // Put between the namespace and the id, e.g. `engine.power`
+    pub separator: String,
+    // Ids that are not namespaced, so that all modules defining or referring
+    // to them end up with the same node
+    pub shared: HashSet<String>,
+}
+
+impl Default for ComposeOptions {
+    fn default() -> Self {
+        ComposeOptions { separator: ".".to_string(), shared: HashSet::new() }
+    }
+}
+
+// A reason why modules could not be composed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    // Two different nodes end up with the same id after namespacing
+    Collision {
+        id: String,
+        first: String,
+        second: String,
+    },
+    // A shared id is defined differently by two modules
+    Definition {
+        id: String,
+        first: String,
+        second: String,
+    },
+}
+
+// All conflicts found while composing modules. Composition does not stop at
+// the first conflict, so that all of them can be fixed at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComposeError {
+    pub conflicts: Vec<Conflict>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::Collision { id, first, second } => write!(
+                f, "id {:?} is produced by both module {:?} and module {:?}", id, first, second,
+            ),
+            Conflict::Definition { id, first, second } => write!(
+                f, "shared id {:?} is defined differently by module {:?} and module {:?}", id, first, second,
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ComposeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} conflicts when composing modules", self.conflicts.len())?;
+        for conflict in self.conflicts.iter() {
+            write!(f, "\n  {}", conflict)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ComposeError {}
+
+// Merges several trees into one.
+//
+// Every node defined by a module is renamed to `<namespace><separator><id>`,
+// unless its id is shared. References to ids that the module does not define
+// are left as is, so a module may refer to nodes of other modules by their
+// namespaced id. Shared ids are never renamed, and may be defined by several
+// modules as long as the definitions are equal. Relations are compared
+// without regard to their order.
+//
+// # Arguments
+//
+// * `modules` - The trees to merge, each with its namespace
+// * `options` - The separator and the shared ids
+//
+// # Returns
+//
+// The composed tree, or all id collisions and conflicting definitions found
+pub fn compose(modules: &[Module], options: &ComposeOptions) -> Result<LinearBoundedTree, ComposeError> {
+    let mut composed = LinearBoundedTree::default();
+    let mut conflicts: Vec<Conflict> = Vec::new();
+
+    // The namespace of the module that defined each id of the composed tree,
+    // and whether the id is shared
+    let mut origin: HashMap<String, (&str, bool)> = HashMap::new();
+
+    for module in modules {
+        let rename = |id: &str| -> String {
+            if options.shared.contains(id) || !module.tree.nodes.contains_key(id) || module.namespace.is_empty() {
+                id.to_string()
+            } else {
+                format!("{}{}{}", module.namespace, options.separator, id)
+            }
+        };
+
+        // Sorted, so that conflicts are reported in a stable order
+        let mut ids: Vec<&String> = module.tree.nodes.keys().collect();
+        ids.sort_unstable();
+
+        for id in ids {
+            let node = renamed(&module.tree.nodes[id], &rename);
+            let new_id = rename(id);
+            let shared = options.shared.contains(id);
+
+            match origin.get(&new_id) {
+                None => {
+                    origin.insert(new_id.to_string(), (&module.namespace, shared));
+                    composed.nodes.insert(new_id, node);
+                },
+                Some((first, first_shared)) if shared && *first_shared => {
+                    if normalized(&composed.nodes[&new_id]) != normalized(&node) {
+                        conflicts.push(Conflict::Definition {
+                            id: new_id,
+                            first: first.to_string(),
+                            second: module.namespace.to_string(),
+                        });
+                    }
+                },
+                Some((first, _)) => {
+                    conflicts.push(Conflict::Collision {
+                        id: new_id,
+                        first: first.to_string(),
+                        second: module.namespace.to_string(),
+                    });
+                },
+            }
+        }
+    }
+
+    if conflicts.is_empty() {
+        Ok(composed)
+    } else {
+        Err(ComposeError { conflicts })
+    }
+}
+
+// Returns a copy of the node with all relations renamed
+fn renamed<F: Fn(&str) -> String>(node: &BicOrBound, rename: &F) -> BicOrBound {
+    match &node.part {
+        Some(bic_or_bound::Part::Bic(bic)) => {
+            let mut bic = bic.clone();
+            for relation in bic.relations.iter_mut() {
+                relation.id = rename(&relation.id);
+            }
+            BicOrBound { part: Some(bic_or_bound::Part::Bic(bic)) }
+        },
+        _ => node.clone(),
+    }
+}
+
+// Returns a copy of the node with its relations sorted, so that definitions
+// can be compared regardless of the order of their relations
+fn normalized(node: &BicOrBound) -> BicOrBound {
+    match &node.part {
+        Some(bic_or_bound::Part::Bic(bic)) => {
+            let mut bic = bic.clone();
+            bic.relations.sort_by(|a: &CoefRelation, b: &CoefRelation| {
+                (&a.id, a.coefficient).cmp(&(&b.id, b.coefficient))
+            });
+            BicOrBound { part: Some(bic_or_bound::Part::Bic(bic)) }
+        },
+        _ => node.clone(),
+    }
+}
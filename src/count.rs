@@ -0,0 +1,740 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use num_bigint::BigUint;
+
+use crate::cdcl::{bic_constraints, compile_searchable, constant, normalise, parentless, CHECKED};
+use crate::compiled::CompiledTree;
+use crate::error::LbtError;
+use crate::graph::topological_order;
+use crate::puan_core::LinearBoundedTree;
+
+// The gates every circuit starts with, the sum of nothing and the product of
+// nothing
+const ZERO: u32 = 0;
+const ONE: u32 = 1;
+
+// Options for `count`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CountOptions {
+    // The nodes that must be 1. If empty, every node that no other node
+    // relates to must be 1.
+    pub roots: Vec<String>,
+    // The leaves to count the assignments of, where assignments that only
+    // differ in other leaves are counted once. If empty, all leaves that are
+    // not fixed are counted.
+    pub projection: Vec<String>,
+    // How long to count for, or no limit if None
+    pub time_limit: Option<Duration>,
+}
+
+// Options for `weighted_count`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WeightedCountOptions {
+    // The nodes that must be 1. If empty, every node that no other node
+    // relates to must be 1.
+    pub roots: Vec<String>,
+    // The take rate of leaves, the weight of a leaf being 1, where the weight
+    // of it being 0 is one minus the rate. Leaves without a rate weigh 1 both
+    // ways, so without any rates the weighted count is the count.
+    pub weights: HashMap<String, f64>,
+    // How long to count for, or no limit if None
+    pub time_limit: Option<Duration>,
+}
+
+// The result of `weighted_count`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WeightedCount {
+    // The natural logarithm of the weighted count, the sum over all valid
+    // assignments of the product of the weights of their leaves. It is
+    // negative infinity if no assignment is valid. The weighted counts of
+    // large trees do not fit in a f64, but their logarithms do.
+    pub log_count: f64,
+    // The probability of each leaf that is not fixed, and of each BIC, being
+    // 1 in a valid assignment drawn by weight. Empty if no assignment is
+    // valid.
+    pub marginals: BTreeMap<String, f64>,
+}
+
+impl WeightedCount {
+    // Returns the weighted count, which is infinite if it does not fit in a
+    // f64
+    pub fn count(&self) -> f64 {
+        self.log_count.exp()
+    }
+}
+
+// A gate of the circuit a count is recorded as. Every gate only depends on
+// gates recorded before it, and gates of components reached more than once
+// are shared.
+enum Gate {
+    // The product of the weights of the literals, of the sums of the weights
+    // of both values of the free nodes, and of the children. No two of them
+    // share a node.
+    Product { literals: Vec<u32>, free: Vec<u32>, children: Vec<u32> },
+    // The sum of the children, no two of which share an assignment
+    Sum(Vec<u32>),
+}
+
+// The numbers a circuit can be evaluated in
+trait Semiring: Clone {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn add(&self, other: &Self) -> Self;
+    fn mul(&self, other: &Self) -> Self;
+}
+
+impl Semiring for BigUint {
+    fn zero() -> Self {
+        BigUint::default()
+    }
+
+    fn one() -> Self {
+        BigUint::from(1u32)
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        self * other
+    }
+}
+
+// A non-negative number held as its natural logarithm
+#[derive(Debug, Clone, Copy)]
+struct Log(f64);
+
+impl Semiring for Log {
+    fn zero() -> Self {
+        Log(f64::NEG_INFINITY)
+    }
+
+    fn one() -> Self {
+        Log(0.0)
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let (high, low) = if self.0 >= other.0 { (self.0, other.0) } else { (other.0, self.0) };
+        if low == f64::NEG_INFINITY {
+            return Log(high);
+        }
+        Log(high + (low - high).exp().ln_1p())
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        Log(self.0 + other.0)
+    }
+}
+
+// Returns the value of every gate of a circuit
+//
+// # Arguments
+//
+// * `gates` - The gates of the circuit
+// * `literal_weight` - The weight of a literal
+// * `free_weight` - The weight of a free node, i.e. of both its values
+fn evaluate<W: Semiring>(gates: &[Gate], literal_weight: impl Fn(u32) -> W, free_weight: impl Fn(u32) -> W) -> Vec<W> {
+    let mut values: Vec<W> = Vec::with_capacity(gates.len());
+    for gate in gates.iter() {
+        let value = match gate {
+            Gate::Product { literals, free, children } => literals.iter()
+                .map(|literal| literal_weight(*literal))
+                .chain(free.iter().map(|node| free_weight(*node)))
+                .chain(children.iter().map(|child| values[*child as usize].clone()))
+                .fold(W::one(), |product, factor| product.mul(&factor)),
+            Gate::Sum(children) => children.iter()
+                .fold(W::zero(), |sum, child| sum.add(&values[*child as usize])),
+        };
+        values.push(value);
+    }
+    values
+}
+
+// A constraint `sum(coefficients[i] * literals[i]) >= degree` over literals,
+// see `Solver`. The degree is only kept in the slack and residual of the
+// constraint.
+struct Constraint {
+    literals: Vec<u32>,
+    coefficients: Vec<i64>,
+}
+
+// The state of a count: the constraints of the BICs the roots depend on, the
+// current partial assignment and the circuit recorded so far.
+struct Counter {
+    constraints: Vec<Constraint>,
+    // The constraints each node is part of
+    occurrences: Vec<Vec<u32>>,
+    // The constraints each literal is part of, with its coefficient. A
+    // constraint can only imply literals or be violated once one of its
+    // literals is false, so it is only checked then.
+    watches: Vec<Vec<(u32, i64)>>,
+    // How much the literals of each constraint that are not false exceed its
+    // degree
+    slack: Vec<i64>,
+    // How much the true literals of each constraint fall short of its degree,
+    // which is 0 or less once the constraint is satisfied
+    residuals: Vec<i64>,
+    // The value of each node, or -1 if it is not assigned
+    values: Vec<i8>,
+    trail: Vec<u32>,
+    // Whether each node is counted, all other nodes only need to have some
+    // value that satisfies the constraints
+    projected: Vec<bool>,
+    // The gate of every component seen, by the nodes of the component and
+    // the constraints it is made of, see `key`
+    cache: HashMap<Vec<i64>, u32>,
+    gates: Vec<Gate>,
+    // Marks for finding components, a node or constraint is marked if it
+    // holds the current epoch
+    node_marks: Vec<u32>,
+    constraint_marks: Vec<u32>,
+    epoch: u32,
+    // When to give up, checked every 256 components and branches
+    deadline: Option<Instant>,
+    steps: u32,
+    // Set once the deadline has passed, after which every component counts
+    // as 0 and nothing more is cached
+    timed_out: bool,
+}
+
+impl Counter {
+    fn literal_value(&self, literal: u32) -> Option<bool> {
+        match self.values[(literal >> 1) as usize] {
+            -1 => None,
+            value => Some((value == 1) == (literal & 1 == 0)),
+        }
+    }
+
+    // Returns true once the deadline has passed
+    fn expired(&mut self) -> bool {
+        if !self.timed_out {
+            self.steps = self.steps.wrapping_add(1);
+            self.timed_out = self.steps.is_multiple_of(256) && self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        }
+        self.timed_out
+    }
+
+    fn assign(&mut self, literal: u32) {
+        self.values[(literal >> 1) as usize] = (literal & 1 == 0) as i8;
+        self.trail.push(literal >> 1);
+        for (index, coefficient) in self.watches[(literal ^ 1) as usize].iter() {
+            self.slack[*index as usize] -= coefficient;
+        }
+        for (index, coefficient) in self.watches[literal as usize].iter() {
+            self.residuals[*index as usize] -= coefficient;
+        }
+    }
+
+    // Returns the literals assigned after the first `from` of the trail
+    fn literals(&self, from: usize) -> Vec<u32> {
+        self.trail[from..].iter()
+            .map(|node| 2 * node + (self.values[*node as usize] == 0) as u32)
+            .collect()
+    }
+
+    // Unassigns all nodes assigned after the first `length` of the trail
+    fn undo(&mut self, length: usize) {
+        for node in self.trail.drain(length..) {
+            let falsified = 2 * node + (self.values[node as usize] == 1) as u32;
+            for (index, coefficient) in self.watches[falsified as usize].iter() {
+                self.slack[*index as usize] += coefficient;
+            }
+            for (index, coefficient) in self.watches[(falsified ^ 1) as usize].iter() {
+                self.residuals[*index as usize] += coefficient;
+            }
+            self.values[node as usize] = -1;
+        }
+    }
+
+    // Returns how much the true literals of a constraint still fall short of
+    // its degree, which is 0 or less once the constraint is satisfied
+    fn residual(&self, index: u32) -> i64 {
+        self.residuals[index as usize]
+    }
+
+    // Assigns the literals a constraint implies. Returns false if the
+    // constraint is violated.
+    fn check(&mut self, index: u32) -> bool {
+        let slack = self.slack[index as usize];
+        let constraint = &self.constraints[index as usize];
+        if slack < 0 {
+            return false;
+        }
+        // Nothing is implied once the constraint is satisfied, or while every
+        // literal could still be false
+        if self.residuals[index as usize] <= 0 || constraint.coefficients[0] <= slack {
+            return true;
+        }
+        let implied: Vec<u32> = constraint.literals.iter()
+            .zip(constraint.coefficients.iter())
+            .take_while(|(_, coefficient)| **coefficient > slack)
+            .map(|(literal, _)| *literal)
+            .filter(|literal| self.literal_value(*literal).is_none())
+            .collect();
+        for literal in implied {
+            self.assign(literal);
+        }
+        true
+    }
+
+    // Propagates the consequences of all nodes assigned after the first
+    // `from` of the trail. Returns false on a contradiction.
+    fn propagate(&mut self, from: usize) -> bool {
+        let mut position = from;
+        while position < self.trail.len() {
+            let node = self.trail[position];
+            position += 1;
+            let falsified = (2 * node + (self.values[node as usize] == 1) as u32) as usize;
+            for watch in 0..self.watches[falsified].len() {
+                if !self.check(self.watches[falsified][watch].0) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // Splits unassigned nodes into components, groups of nodes connected by
+    // constraints that are not satisfied yet. Nodes without any such
+    // constraint are returned separately, as they can take any value.
+    fn components(&mut self, nodes: &[u32]) -> (Vec<Vec<u32>>, Vec<u32>) {
+        self.epoch += 1;
+        let mut components: Vec<Vec<u32>> = Vec::new();
+        let mut free: Vec<u32> = Vec::new();
+        for start in nodes.iter() {
+            if self.values[*start as usize] != -1 || self.node_marks[*start as usize] == self.epoch {
+                continue;
+            }
+            self.node_marks[*start as usize] = self.epoch;
+            let mut component = vec![*start];
+            let mut position = 0;
+            while position < component.len() {
+                let node = component[position] as usize;
+                position += 1;
+                for occurrence in 0..self.occurrences[node].len() {
+                    let index = self.occurrences[node][occurrence];
+                    if self.constraint_marks[index as usize] == self.epoch || self.residual(index) <= 0 {
+                        continue;
+                    }
+                    self.constraint_marks[index as usize] = self.epoch;
+                    for literal in self.constraints[index as usize].literals.iter() {
+                        let other = (literal >> 1) as usize;
+                        if self.values[other] == -1 && self.node_marks[other] != self.epoch {
+                            self.node_marks[other] = self.epoch;
+                            component.push(other as u32);
+                        }
+                    }
+                }
+            }
+            if component.len() == 1 && !self.has_open_constraint(*start) {
+                free.push(*start);
+            } else {
+                components.push(component);
+            }
+        }
+        (components, free)
+    }
+
+    fn has_open_constraint(&self, node: u32) -> bool {
+        self.occurrences[node as usize].iter().any(|index| self.residual(*index) > 0)
+    }
+
+    fn gate(&mut self, gate: Gate) -> u32 {
+        self.gates.push(gate);
+        (self.gates.len() - 1) as u32
+    }
+
+    // Records the count of the assignments of the projected nodes among the
+    // given unassigned nodes, as the product of the counts of their
+    // components, together with the literals assigned before them. Returns
+    // the gate of the product.
+    fn count_nodes(&mut self, nodes: &[u32], literals: Vec<u32>) -> u32 {
+        let (components, free) = self.components(nodes);
+        let mut children: Vec<u32> = Vec::with_capacity(components.len());
+        for component in components {
+            match self.count_component(component) {
+                ZERO => return ZERO,
+                ONE => {},
+                child => children.push(child),
+            }
+        }
+        let free: Vec<u32> = free.into_iter().filter(|node| self.projected[*node as usize]).collect();
+        if literals.is_empty() && free.is_empty() && children.is_empty() {
+            return ONE;
+        }
+        self.gate(Gate::Product { literals, free, children })
+    }
+
+    // Returns the key a component is cached by: its nodes, sorted, followed
+    // by each constraint that is not satisfied yet with how much it still
+    // falls short. Together they decide what is left to count, no matter how
+    // the nodes outside the component were assigned.
+    fn key(&self, component: &mut [u32]) -> Vec<i64> {
+        component.sort_unstable();
+        let mut constraints: Vec<u32> = component.iter()
+            .flat_map(|node| self.occurrences[*node as usize].iter().copied())
+            .collect();
+        constraints.sort_unstable();
+        constraints.dedup();
+
+        let mut key: Vec<i64> = component.iter().map(|node| *node as i64).collect();
+        key.push(-1);
+        for index in constraints {
+            let residual = self.residual(index);
+            if residual > 0 {
+                key.push(index as i64);
+                key.push(residual);
+            }
+        }
+        key
+    }
+
+    fn count_component(&mut self, mut component: Vec<u32>) -> u32 {
+        if self.expired() {
+            return ZERO;
+        }
+        let key = self.key(&mut component);
+        if let Some(gate) = self.cache.get(&key) {
+            return *gate;
+        }
+
+        // Branching on the projected node in the most constraints splits the
+        // component up the quickest
+        let branch = component.iter()
+            .copied()
+            .filter(|node| self.projected[*node as usize])
+            .max_by_key(|node| self.occurrences[*node as usize].len());
+        let gate = match branch {
+            Some(node) => {
+                let mut children: Vec<u32> = Vec::with_capacity(2);
+                for literal in [2 * node + 1, 2 * node] {
+                    let length = self.trail.len();
+                    self.assign(literal);
+                    if self.propagate(length) {
+                        let literals = self.literals(length);
+                        let child = self.count_nodes(&component, literals);
+                        if child != ZERO {
+                            children.push(child);
+                        }
+                    }
+                    self.undo(length);
+                }
+                match children.len() {
+                    0 => ZERO,
+                    _ => self.gate(Gate::Sum(children)),
+                }
+            },
+            // Nodes that are not counted only need some value
+            None if self.satisfiable(&component) => ONE,
+            None => ZERO,
+        };
+        if !self.timed_out {
+            self.cache.insert(key, gate);
+        }
+        gate
+    }
+
+    // Returns true if the unassigned nodes among the given nodes can be
+    // assigned without violating any constraint, or false once the deadline
+    // has passed
+    fn satisfiable(&mut self, nodes: &[u32]) -> bool {
+        if self.expired() {
+            return false;
+        }
+        let Some(node) = nodes.iter().copied().find(|node| self.values[*node as usize] == -1) else {
+            return true;
+        };
+        for literal in [2 * node + 1, 2 * node] {
+            let length = self.trail.len();
+            self.assign(literal);
+            let satisfiable = self.propagate(length) && self.satisfiable(nodes);
+            self.undo(length);
+            if satisfiable {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+// Records the count of the assignments of a tree that make all roots 1 as a
+// circuit. The search goes over the projected leaves, propagating every
+// choice through the BICs the same way `Solver` does. After each choice, the
+// leaves and BICs still unassigned are split into components that share no
+// constraint that is not yet satisfied, and the count is the product of the
+// counts of the components. Each component is recorded once, so when the
+// same component with the same constraints left is reached through
+// different choices, its gate is shared. Leaves that are not projected, and
+// BICs, only need some value satisfying the constraints, so once no
+// projected leaf is left in a component it counts as 1 or 0.
+//
+// # Arguments
+//
+// * `compiled` - The compiled tree
+// * `roots` - The nodes that must be 1
+// * `projected` - Whether each node is counted
+// * `needed` - Whether each BIC is a constraint
+// * `deadline` - When to give up
+//
+// # Returns
+//
+// The gates of the circuit and the gate at its top, or None if the deadline
+// passed before the circuit was complete
+fn record(
+    compiled: &CompiledTree,
+    roots: &[u32],
+    projected: Vec<bool>,
+    needed: &[bool],
+    deadline: Option<Instant>,
+) -> Option<(Vec<Gate>, u32)> {
+    let n = compiled.len();
+    let mut counter = Counter {
+        constraints: Vec::new(),
+        occurrences: vec![Vec::new(); n],
+        watches: vec![Vec::new(); 2 * n],
+        slack: Vec::new(),
+        residuals: Vec::new(),
+        values: vec![-1; n],
+        trail: Vec::new(),
+        projected,
+        cache: HashMap::new(),
+        gates: vec![Gate::Sum(Vec::new()), Gate::Product { literals: Vec::new(), free: Vec::new(), children: Vec::new() }],
+        node_marks: vec![0; n],
+        constraint_marks: Vec::new(),
+        epoch: 0,
+        deadline,
+        steps: 0,
+        timed_out: false,
+    };
+    if (0..n).any(|node| compiled.lower[node] > compiled.upper[node]) {
+        return Some((counter.gates, ZERO));
+    }
+    for bic in (0..n as u32).filter(|node| needed[*node as usize] && compiled.is_bic[*node as usize]) {
+        for (terms, degree) in bic_constraints(compiled, bic) {
+            if let Some((literals, coefficients, degree)) = normalise(terms, degree).expect(CHECKED) {
+                let index = counter.constraints.len() as u32;
+                for (literal, coefficient) in literals.iter().zip(coefficients.iter()) {
+                    counter.occurrences[(literal >> 1) as usize].push(index);
+                    counter.watches[*literal as usize].push((index, *coefficient));
+                }
+                counter.slack.push(coefficients.iter().sum::<i64>() - degree);
+                counter.residuals.push(degree);
+                counter.constraints.push(Constraint { literals, coefficients });
+            }
+        }
+    }
+    counter.constraint_marks = vec![0; counter.constraints.len()];
+
+    for root in roots.iter() {
+        match constant(compiled, *root) {
+            Some(1) => {},
+            Some(_) => return Some((counter.gates, ZERO)),
+            None if counter.values[*root as usize] == -1 => counter.assign(2 * root),
+            None => {},
+        }
+    }
+    let consistent = (0..counter.constraints.len() as u32).all(|index| counter.check(index)) && counter.propagate(0);
+    if !consistent {
+        return Some((counter.gates, ZERO));
+    }
+
+    let nodes: Vec<u32> = (0..n as u32)
+        .filter(|node| (needed[*node as usize] || counter.projected[*node as usize]) && constant(compiled, *node).is_none())
+        .collect();
+    let literals = counter.literals(0);
+    let top = counter.count_nodes(&nodes, literals);
+    (!counter.timed_out).then_some((counter.gates, top))
+}
+
+// Returns the roots of a count, which are the parentless nodes if none are
+// given
+fn roots(compiled: &CompiledTree, ids: &[String]) -> Result<Vec<u32>, LbtError> {
+    if ids.is_empty() {
+        return Ok(parentless(compiled));
+    }
+    let index = compiled.index();
+    ids.iter()
+        .map(|id| index.get(id.as_str()).copied().ok_or_else(|| LbtError::UnknownNode(id.to_string())))
+        .collect()
+}
+
+// Counts the assignments of the leaves of a tree that make all roots 1,
+// exactly, without enumerating them. The count is recorded as a circuit, see
+// `record`, which is then evaluated. Since every BIC is decided by its
+// children, counting all leaves counts every solution.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to count. Leaves must either be fixed,
+//   i.e. have equal lower and upper bounds, or have the bound [0, 1].
+// * `options` - The roots, the leaves to project onto and the time limit
+//
+// # Returns
+//
+// The number of assignments, None if the time limit was reached, or an error if a node has no part, a root,
+// child or projected leaf does not exist in the tree, a projected node is a
+// BIC, or a leaf is neither fixed nor [0, 1]
+pub fn count(tree: &LinearBoundedTree, options: &CountOptions) -> Result<Option<BigUint>, LbtError> {
+    let deadline = options.time_limit.map(|limit| Instant::now() + limit);
+    let compiled = compile_searchable(tree)?;
+    let roots = roots(&compiled, &options.roots)?;
+
+    let n = compiled.len();
+    let mut projected = vec![false; n];
+    if options.projection.is_empty() {
+        for leaf in 0..n as u32 {
+            projected[leaf as usize] = !compiled.is_bic[leaf as usize] && constant(&compiled, leaf).is_none();
+        }
+    } else {
+        let index = compiled.index();
+        for id in options.projection.iter() {
+            let leaf = index.get(id.as_str()).copied().ok_or_else(|| LbtError::UnknownNode(id.to_string()))?;
+            if compiled.is_bic[leaf as usize] {
+                return Err(LbtError::Malformed(format!("node {} is a BIC, only leaves can be projected onto", id)));
+            }
+            projected[leaf as usize] = constant(&compiled, leaf).is_none();
+        }
+    }
+
+    // Only the BICs the roots depend on are constraints
+    let mut needed = vec![false; n];
+    let mut stack: Vec<u32> = roots.clone();
+    while let Some(node) = stack.pop() {
+        if !needed[node as usize] {
+            needed[node as usize] = true;
+            stack.extend(compiled.relations(node).0.iter());
+        }
+    }
+
+    let Some((gates, top)) = record(&compiled, &roots, projected, &needed, deadline) else {
+        return Ok(None);
+    };
+    let values = evaluate(&gates, |_| BigUint::one(), |_| BigUint::from(2u32));
+    Ok(Some(values[top as usize].clone()))
+}
+
+// Counts the assignments of the leaves of a tree that make all roots 1,
+// weighted by the take rates of the leaves, together with the probability
+// of each leaf and BIC being 1 among them.
+//
+// The count is recorded as a circuit, see `record`, over all leaves and all
+// BICs, so that every valid assignment passes through exactly one literal of
+// every node. After evaluating the circuit, a second pass from the top down
+// finds the derivative of the count by each gate, the weight of everything
+// around it. The weight of the assignments through a literal is then the
+// derivative of a product it is in times the value of that product, so all
+// marginals come from one pass rather than a count per node.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to count. It must have no cycles, and
+//   leaves must either be fixed, i.e. have equal lower and upper bounds, or
+//   have the bound [0, 1].
+// * `options` - The roots, the take rates and the time limit
+//
+// # Returns
+//
+// The weighted count and the marginals, None if the time limit was reached,
+// or an error if a node has no part,
+// a root, child or weighted leaf does not exist in the tree, the relations
+// form a cycle, a weighted node is a BIC, a take rate is not between 0 and 1,
+// or a leaf is neither fixed nor [0, 1]
+pub fn weighted_count(tree: &LinearBoundedTree, options: &WeightedCountOptions) -> Result<Option<WeightedCount>, LbtError> {
+    let deadline = options.time_limit.map(|limit| Instant::now() + limit);
+    // A BIC in a cycle is not decided by the leaves, so it has no marginal
+    topological_order(tree)?;
+    let compiled = compile_searchable(tree)?;
+    let roots = roots(&compiled, &options.roots)?;
+
+    let n = compiled.len();
+    let index = compiled.index();
+    // The weight of each node being 0 and being 1
+    let mut weights: Vec<(Log, Log)> = vec![(Log::one(), Log::one()); n];
+    for (id, rate) in options.weights.iter() {
+        let leaf = index.get(id.as_str()).copied().ok_or_else(|| LbtError::UnknownNode(id.to_string()))?;
+        if compiled.is_bic[leaf as usize] {
+            return Err(LbtError::Malformed(format!("node {} is a BIC, only leaves can be weighted", id)));
+        }
+        if !(0.0..=1.0).contains(rate) {
+            return Err(LbtError::Malformed(format!("leaf {} has the take rate {}, take rates must be between 0 and 1", id, rate)));
+        }
+        weights[leaf as usize] = (Log((1.0 - rate).ln()), Log(rate.ln()));
+    }
+
+    let projected: Vec<bool> = (0..n as u32)
+        .map(|node| !compiled.is_bic[node as usize] && constant(&compiled, node).is_none())
+        .collect();
+    let Some((gates, top)) = record(&compiled, &roots, projected, &compiled.is_bic, deadline) else {
+        return Ok(None);
+    };
+    let literal_weight = |literal: u32| match literal & 1 {
+        0 => weights[(literal >> 1) as usize].1,
+        _ => weights[(literal >> 1) as usize].0,
+    };
+    let free_weight = |node: u32| weights[node as usize].0.add(&weights[node as usize].1);
+    let values = evaluate(&gates, literal_weight, free_weight);
+    let total = values[top as usize];
+    if total.0 == f64::NEG_INFINITY {
+        return Ok(Some(WeightedCount { log_count: total.0, marginals: BTreeMap::new() }));
+    }
+
+    // Parents are recorded after their children, so going through the gates
+    // backwards visits every parent of a gate before the gate itself
+    let mut derivatives: Vec<Log> = vec![Log::zero(); gates.len()];
+    derivatives[top as usize] = Log::one();
+    // The weight of the valid assignments in which each node is 1
+    let mut ones: Vec<Log> = vec![Log::zero(); n];
+    for gate in (0..gates.len()).rev() {
+        let derivative = derivatives[gate];
+        if derivative.0 == f64::NEG_INFINITY {
+            continue;
+        }
+        match &gates[gate] {
+            Gate::Product { literals, free: nodes, children } => {
+                let through = derivative.mul(&values[gate]);
+                for positive in literals.iter().filter(|literal| *literal & 1 == 0) {
+                    ones[(positive >> 1) as usize] = ones[(positive >> 1) as usize].add(&through);
+                }
+
+                // The weight around a factor is the product of the factors
+                // before it and the factors after it, which avoids dividing
+                // by factors that may be 0
+                let factors: Vec<Log> = nodes.iter()
+                    .map(|node| free_weight(*node))
+                    .chain(children.iter().map(|child| values[*child as usize]))
+                    .collect();
+                let mut before: Vec<Log> = Vec::with_capacity(factors.len());
+                let mut product = literals.iter().fold(derivative, |product, literal| product.mul(&literal_weight(*literal)));
+                for factor in factors.iter() {
+                    before.push(product);
+                    product = product.mul(factor);
+                }
+                let mut after = Log::one();
+                for (position, factor) in factors.iter().enumerate().rev() {
+                    let around = before[position].mul(&after);
+                    if position < nodes.len() {
+                        let node = nodes[position] as usize;
+                        ones[node] = ones[node].add(&around.mul(&weights[node].1));
+                    } else {
+                        let child = children[position - nodes.len()] as usize;
+                        derivatives[child] = derivatives[child].add(&around);
+                    }
+                    after = after.mul(factor);
+                }
+            },
+            Gate::Sum(children) => {
+                for child in children.iter() {
+                    derivatives[*child as usize] = derivatives[*child as usize].add(&derivative);
+                }
+            },
+        }
+    }
+
+    let marginals = (0..n as u32)
+        .filter(|node| compiled.is_bic[*node as usize] || constant(&compiled, *node).is_none())
+        .map(|node| (compiled.id(node).to_string(), (ones[node as usize].0 - total.0).exp().min(1.0)))
+        .collect();
+    Ok(Some(WeightedCount { log_count: total.0, marginals }))
+}
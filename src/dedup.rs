@@ -0,0 +1,165 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::error::LbtError;
+use crate::graph::topological_order;
+use crate::puan_core::{
+    bic_or_bound, BicOrBound, BinaryInequalityConstraint, CoefRelation, LinearBoundedTree,
+};
+
+// The result of deduplicating a tree
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dedup {
+    // The tree with a single node per group of identical nodes
+    pub tree: LinearBoundedTree,
+    // The id of every node that was merged into another node, mapped to the
+    // id of the node it was merged into
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl Dedup {
+    // Returns the id a node of the original tree has in the deduplicated tree
+    pub fn resolve<'a>(&'a self, id: &'a str) -> &'a str {
+        self.aliases.get(id).map(|id| id.as_str()).unwrap_or(id)
+    }
+
+    // Adds all merged nodes back to a tree derived from the deduplicated tree,
+    // e.g. after propagating it, each as a copy of the node it was merged into.
+    // Aliases whose node is not part of the tree are skipped.
+    pub fn expand(&self, tree: &LinearBoundedTree) -> LinearBoundedTree {
+        let mut expanded = tree.clone();
+        expand_in_place(&mut expanded, &self.aliases);
+        expanded
+    }
+}
+
+// Adds merged nodes back to a tree like `Dedup::expand`, without copying the
+// tree.
+//
+// # Arguments
+//
+// * `tree` - The tree to add the merged nodes to
+// * `aliases` - The id of every merged node, mapped to the id of the node it
+//   was merged into, which must not itself be merged
+pub fn expand_in_place(tree: &mut LinearBoundedTree, aliases: &BTreeMap<String, String>) {
+    for (alias, id) in aliases.iter() {
+        if let Some(node) = tree.nodes.get(id).cloned() {
+            tree.nodes.insert(alias.to_string(), node);
+        }
+    }
+}
+
+// What makes two nodes identical. Children are referred to by the class of
+// the node, so the key of a BIC covers the whole sub-tree below it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key<'a> {
+    // A Bound that may take more than one value. Two such nodes are distinct
+    // variables even if their bounds are the same, so they are never merged.
+    Leaf(&'a str),
+    // A Bound with a single value
+    Constant(i64),
+    // A child that is not part of the tree
+    Missing(&'a str),
+    // A BIC, by the class of the child and the coefficient of each relation,
+    // sorted
+    Bic(Vec<(u32, i64)>),
+}
+
+// Merges structurally identical nodes of a tree, a form of hash-consing. Each
+// node is given a class by its key: Bounds with a single value by that value,
+// and BICs by their relations, normalised by sorting them. Relations to the
+// same class are not summed, since propagation bounds every relation on its
+// own, e.g. `x - x` propagates to [-1, 1] for x in [0, 1] and not to 0, so
+// summing them would change the result. Since the key of a BIC holds
+// the classes of its children rather than their ids, BICs are identical if
+// they relate to identical sub-trees, no matter the ids used, in the same way
+// as a Merkle tree. Nodes are classified children first, so the classes of
+// all children are known when a BIC is classified.
+//
+// The node with the smallest id is kept for every class. BICs in the result
+// relate to the kept nodes, with their relations sorted by child id. A merged
+// node always propagates to the same bound as the node it was merged into.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to deduplicate
+//
+// # Returns
+//
+// The deduplicated tree with the aliases of the merged nodes, or an error if
+// a node has no part or the relations form a cycle
+pub fn dedup(tree: &LinearBoundedTree) -> Result<Dedup, LbtError> {
+    let order = topological_order(tree)?;
+
+    let mut classes: HashMap<Key, u32> = HashMap::new();
+    // The id kept for each class
+    let mut kept: Vec<&str> = Vec::new();
+    let mut class_of: HashMap<&str, u32> = HashMap::with_capacity(tree.nodes.len());
+
+    for id in order.iter() {
+        let key = match &tree.nodes[*id].part {
+            Some(bic_or_bound::Part::Bound(bound)) if bound.lower == bound.upper => Key::Constant(bound.lower),
+            Some(bic_or_bound::Part::Bound(_)) => Key::Leaf(id),
+            Some(bic_or_bound::Part::Bic(bic)) => {
+                let mut relations: Vec<(u32, i64)> = Vec::with_capacity(bic.relations.len());
+                for relation in bic.relations.iter() {
+                    let child = match class_of.get(relation.id.as_str()) {
+                        Some(child) => *child,
+                        None => {
+                            // Children outside the tree are never classified
+                            // as nodes, so give them a class of their own
+                            let next = kept.len() as u32;
+                            let child = *classes.entry(Key::Missing(&relation.id)).or_insert(next);
+                            if child == next {
+                                kept.push(&relation.id);
+                            }
+                            child
+                        },
+                    };
+                    relations.push((child, relation.coefficient));
+                }
+                relations.sort_unstable();
+                Key::Bic(relations)
+            },
+            None => return Err(LbtError::MissingPart(id.to_string())),
+        };
+
+        let next = kept.len() as u32;
+        let class = *classes.entry(key).or_insert(next);
+        if class == next {
+            kept.push(id);
+        } else if *id < kept[class as usize] {
+            kept[class as usize] = id;
+        }
+        class_of.insert(id, class);
+    }
+
+    let mut result = Dedup::default();
+    for (key, class) in classes.iter() {
+        let id = kept[*class as usize];
+        let node = match key {
+            Key::Missing(_) => continue,
+            Key::Leaf(_) | Key::Constant(_) => tree.nodes[id].clone(),
+            Key::Bic(relations) => {
+                let mut relations: Vec<CoefRelation> = relations.iter()
+                    .map(|(child, coefficient)| CoefRelation {
+                        id: kept[*child as usize].to_string(),
+                        coefficient: *coefficient,
+                    })
+                    .collect();
+                relations.sort_by(|a, b| (&a.id, a.coefficient).cmp(&(&b.id, b.coefficient)));
+                BicOrBound {
+                    part: Some(bic_or_bound::Part::Bic(BinaryInequalityConstraint { relations })),
+                }
+            },
+        };
+        result.tree.nodes.insert(id.to_string(), node);
+    }
+    for id in order.iter() {
+        let kept = kept[class_of[id] as usize];
+        if kept != *id {
+            result.aliases.insert(id.to_string(), kept.to_string());
+        }
+    }
+
+    Ok(result)
+}
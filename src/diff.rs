@@ -0,0 +1,337 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use serde_json::{json, Value};
+
+use crate::error::LbtError;
+use crate::propagate::propagate_in_place;
+use crate::puan_core::{bic_or_bound, BicOrBound, Bound, LinearBoundedTree};
+
+// A change of a single node that exists in both trees.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeChange {
+    // The node went from being a BIC to a Bound, or the other way around
+    Kind {
+        before: BicOrBound,
+        after: BicOrBound,
+    },
+    // The node is a Bound in both trees, but with different values
+    Bound {
+        before: Bound,
+        after: Bound,
+    },
+    // The node is a BIC in both trees, but with different relations. Relations
+    // are compared per child id as a multiset of coefficients, so reordering
+    // relations is not a change, but adding a duplicate relation is, even if
+    // the coefficients sum up to the same.
+    Relations {
+        // Relations only in the second tree, as child and coefficient
+        added: Vec<(String, i64)>,
+        // Relations only in the first tree, as child and coefficient
+        removed: Vec<(String, i64)>,
+        // Children with a single relation in both trees that is not in the
+        // other tree, with the coefficient before and after
+        coefficients: Vec<(String, i64, i64)>,
+    },
+}
+
+// The structural difference between two trees. All ids are sorted, so the
+// same pair of trees always gives the same diff.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TreeDiff {
+    // Nodes only in the second tree
+    pub added: Vec<String>,
+    // Nodes only in the first tree
+    pub removed: Vec<String>,
+    // Nodes in both trees that are not equal
+    pub changed: Vec<(String, NodeChange)>,
+}
+
+// The bound of a node after propagation, in either tree. A node that is
+// missing from a tree, or that could not be propagated, has no bound.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundChange {
+    pub id: String,
+    pub before: Option<Bound>,
+    pub after: Option<Bound>,
+}
+
+impl TreeDiff {
+    // Returns true if the trees are structurally equal
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    // Returns the diff as a JSON value, with one key per kind of change
+    pub fn to_json(&self) -> Value {
+        let changed: Vec<Value> = self.changed.iter()
+            .map(|(id, change)| match change {
+                NodeChange::Kind { before, after } => json!({
+                    "id": id,
+                    "change": "kind",
+                    "before": kind_json(before),
+                    "after": kind_json(after),
+                }),
+                NodeChange::Bound { before, after } => json!({
+                    "id": id,
+                    "change": "bound",
+                    "before": bound_json(before),
+                    "after": bound_json(after),
+                }),
+                NodeChange::Relations { added, removed, coefficients } => json!({
+                    "id": id,
+                    "change": "relations",
+                    "added": added.iter()
+                        .map(|(child, coefficient)| json!({"id": child, "coefficient": coefficient}))
+                        .collect::<Vec<Value>>(),
+                    "removed": removed.iter()
+                        .map(|(child, coefficient)| json!({"id": child, "coefficient": coefficient}))
+                        .collect::<Vec<Value>>(),
+                    "coefficients": coefficients.iter()
+                        .map(|(child, before, after)| json!({"id": child, "before": before, "after": after}))
+                        .collect::<Vec<Value>>(),
+                }),
+            })
+            .collect();
+
+        json!({
+            "added": self.added,
+            "removed": self.removed,
+            "changed": changed,
+        })
+    }
+}
+
+impl fmt::Display for TreeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for id in self.added.iter() {
+            writeln!(f, "+ {}", id)?;
+        }
+        for id in self.removed.iter() {
+            writeln!(f, "- {}", id)?;
+        }
+        for (id, change) in self.changed.iter() {
+            match change {
+                NodeChange::Kind { before, after } => {
+                    writeln!(f, "~ {}: {} -> {}", id, kind_name(before), kind_name(after))?;
+                },
+                NodeChange::Bound { before, after } => {
+                    writeln!(
+                        f, "~ {}: bound [{}, {}] -> [{}, {}]",
+                        id, before.lower, before.upper, after.lower, after.upper,
+                    )?;
+                },
+                NodeChange::Relations { added, removed, coefficients } => {
+                    writeln!(f, "~ {}:", id)?;
+                    for (child, coefficient) in added.iter() {
+                        writeln!(f, "    + {} * {}", coefficient, child)?;
+                    }
+                    for (child, coefficient) in removed.iter() {
+                        writeln!(f, "    - {} * {}", coefficient, child)?;
+                    }
+                    for (child, before, after) in coefficients.iter() {
+                        writeln!(f, "    ~ {}: {} -> {}", child, before, after)?;
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+// Computes the structural difference between two trees.
+//
+// # Arguments
+//
+// * `a` - The tree before the change
+// * `b` - The tree after the change
+//
+// # Returns
+//
+// The nodes that were added, removed and changed going from `a` to `b`
+pub fn diff(a: &LinearBoundedTree, b: &LinearBoundedTree) -> TreeDiff {
+    let mut result = TreeDiff::default();
+
+    let ids: BTreeSet<&String> = a.nodes.keys().chain(b.nodes.keys()).collect();
+    for id in ids {
+        let (before, after) = match (a.nodes.get(id), b.nodes.get(id)) {
+            (Some(before), Some(after)) => (before, after),
+            (Some(_), None) => {
+                result.removed.push(id.to_string());
+                continue;
+            },
+            (None, _) => {
+                result.added.push(id.to_string());
+                continue;
+            },
+        };
+
+        let change = match (&before.part, &after.part) {
+            (Some(bic_or_bound::Part::Bound(x)), Some(bic_or_bound::Part::Bound(y))) => {
+                if x == y {
+                    continue;
+                }
+                NodeChange::Bound { before: x.clone(), after: y.clone() }
+            },
+            (Some(bic_or_bound::Part::Bic(x)), Some(bic_or_bound::Part::Bic(y))) => {
+                let x = coefficients(x.relations.iter().map(|r| (&r.id, r.coefficient)));
+                let y = coefficients(y.relations.iter().map(|r| (&r.id, r.coefficient)));
+                if x == y {
+                    continue;
+                }
+
+                let mut added = Vec::new();
+                let mut removed = Vec::new();
+                let mut changed = Vec::new();
+                let children: BTreeSet<&String> = x.keys().chain(y.keys()).copied().collect();
+                for child in children {
+                    let (before, after) = difference(
+                        x.get(child).map_or(&[][..], Vec::as_slice),
+                        y.get(child).map_or(&[][..], Vec::as_slice),
+                    );
+                    match (&before[..], &after[..]) {
+                        ([before], [after]) => changed.push((child.to_string(), *before, *after)),
+                        _ => {
+                            removed.extend(before.into_iter().map(|coefficient| (child.to_string(), coefficient)));
+                            added.extend(after.into_iter().map(|coefficient| (child.to_string(), coefficient)));
+                        },
+                    }
+                }
+                NodeChange::Relations { added, removed, coefficients: changed }
+            },
+            _ => {
+                if before == after {
+                    continue;
+                }
+                NodeChange::Kind { before: before.clone(), after: after.clone() }
+            },
+        };
+        result.changed.push((id.to_string(), change));
+    }
+
+    result
+}
+
+// Propagates both trees under the same leaf assignment and compares the
+// resulting bounds of every node.
+//
+// # Arguments
+//
+// * `a` - The tree before the change
+// * `b` - The tree after the change
+// * `assignment` - Bounds that replace the nodes with the same id in both
+//   trees before propagating. Ids that are not part of a tree are ignored.
+//
+// # Returns
+//
+// The nodes whose bound differs after propagation, sorted by id, or an error
+// if either tree could not be propagated
+pub fn diff_propagated(
+    a: &LinearBoundedTree,
+    b: &LinearBoundedTree,
+    assignment: &HashMap<String, Bound>,
+) -> Result<Vec<BoundChange>, LbtError> {
+    let mut a = assign(a, assignment);
+    propagate_in_place(&mut a)?;
+    let mut b = assign(b, assignment);
+    propagate_in_place(&mut b)?;
+
+    let ids: BTreeSet<&String> = a.nodes.keys().chain(b.nodes.keys()).collect();
+    Ok(
+        ids.into_iter()
+            .map(|id| BoundChange {
+                id: id.to_string(),
+                before: a.nodes.get(id).and_then(bound),
+                after: b.nodes.get(id).and_then(bound),
+            })
+            .filter(|change| change.before != change.after)
+            .collect()
+    )
+}
+
+// Returns a copy of the tree with the nodes in the assignment replaced by
+// their assigned bound
+fn assign(tree: &LinearBoundedTree, assignment: &HashMap<String, Bound>) -> LinearBoundedTree {
+    let mut tree = tree.clone();
+    for (id, value) in assignment.iter() {
+        if let Some(node) = tree.nodes.get_mut(id) {
+            node.part = Some(bic_or_bound::Part::Bound(value.clone()));
+        }
+    }
+    tree
+}
+
+// Collects the coefficients of the relations per child id, sorted
+fn coefficients<'a, I>(relations: I) -> BTreeMap<&'a String, Vec<i64>>
+where
+    I: Iterator<Item = (&'a String, i64)>,
+{
+    let mut coefficients: BTreeMap<&String, Vec<i64>> = BTreeMap::new();
+    for (id, coefficient) in relations {
+        coefficients.entry(id).or_default().push(coefficient);
+    }
+    for values in coefficients.values_mut() {
+        values.sort_unstable();
+    }
+    coefficients
+}
+
+// Returns the coefficients only in the first and only in the second of two
+// sorted lists, counting duplicates
+fn difference(a: &[i64], b: &[i64]) -> (Vec<i64>, Vec<i64>) {
+    let (mut only_a, mut only_b) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if j == b.len() || (i < a.len() && a[i] < b[j]) {
+            only_a.push(a[i]);
+            i += 1;
+        } else if i == a.len() || b[j] < a[i] {
+            only_b.push(b[j]);
+            j += 1;
+        } else {
+            i += 1;
+            j += 1;
+        }
+    }
+    (only_a, only_b)
+}
+
+fn bound(node: &BicOrBound) -> Option<Bound> {
+    match &node.part {
+        Some(bic_or_bound::Part::Bound(bound)) => Some(bound.clone()),
+        _ => None,
+    }
+}
+
+fn kind_name(node: &BicOrBound) -> String {
+    match &node.part {
+        Some(bic_or_bound::Part::Bic(_)) => "bic".to_string(),
+        Some(bic_or_bound::Part::Bound(bound)) => format!("bound [{}, {}]", bound.lower, bound.upper),
+        None => "nothing".to_string(),
+    }
+}
+
+fn kind_json(node: &BicOrBound) -> Value {
+    match &node.part {
+        Some(bic_or_bound::Part::Bic(_)) => json!("bic"),
+        Some(bic_or_bound::Part::Bound(bound)) => bound_json(bound),
+        None => Value::Null,
+    }
+}
+
+fn bound_json(bound: &Bound) -> Value {
+    json!({"lower": bound.lower, "upper": bound.upper})
+}
+
+// Returns the propagation changes as a JSON value
+pub fn bound_changes_json(changes: &[BoundChange]) -> Value {
+    Value::Array(
+        changes.iter()
+            .map(|change| json!({
+                "id": change.id,
+                "before": change.before.as_ref().map(bound_json),
+                "after": change.after.as_ref().map(bound_json),
+            }))
+            .collect()
+    )
+}
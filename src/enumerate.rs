@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::cdcl::Solver;
+use crate::error::LbtError;
+use crate::puan_core::LinearBoundedTree;
+use crate::solve::Status;
+
+// Options for `enumerate`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnumerateOptions {
+    // The nodes that must be 1. If empty, every node that no other node
+    // relates to must be 1.
+    pub roots: Vec<String>,
+    // The largest number of solutions to produce, or no limit if None
+    pub limit: Option<usize>,
+    // The leaves each solution holds the value of. Solutions that only
+    // differ in other leaves are produced once. If empty, all leaves that
+    // are not fixed are used.
+    pub projection: Vec<String>,
+    // How long to search for all solutions, or no limit if None
+    pub time_limit: Option<Duration>,
+}
+
+// An iterator over the solutions of a tree, each one the values of the
+// projected leaves. Every solution is searched for when it is asked for, so
+// stopping early, e.g. with `take`, skips the search for the rest.
+pub struct Solutions {
+    solver: Solver,
+    leaves: Vec<u32>,
+    remaining: Option<usize>,
+    deadline: Option<Instant>,
+    // The status of the last search, Sat until no solution is left
+    status: Status,
+}
+
+impl Solutions {
+    // Returns true if the time limit was reached before all solutions were
+    // produced
+    pub fn timed_out(&self) -> bool {
+        self.status == Status::Unknown
+    }
+
+    // Returns the ids of the leaves every solution holds the value of
+    pub fn leaves(&self) -> impl Iterator<Item = &str> {
+        self.leaves.iter().map(|leaf| self.solver.compiled().id(*leaf))
+    }
+
+    // Returns the iterator with its searches stopping once a function returns
+    // true, e.g. when the client is gone, in which case no more solutions are
+    // produced and `timed_out` returns true. It is called during the search,
+    // so a search that would not find another solution is stopped too.
+    pub fn cancel_when(mut self, cancelled: impl Fn() -> bool + Send + 'static) -> Self {
+        self.solver.interrupt_when(cancelled);
+        self
+    }
+}
+
+impl Iterator for Solutions {
+    type Item = BTreeMap<String, i64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.status != Status::Sat || self.remaining == Some(0) {
+            return None;
+        }
+        self.status = self.solver.solve(&[], self.deadline);
+        if self.status != Status::Sat {
+            return None;
+        }
+        self.remaining = self.remaining.map(|remaining| remaining - 1);
+
+        // Leaves no constraint depends on yet have no value, they take their
+        // lower bound until the clause below adds them to the search
+        let compiled = self.solver.compiled();
+        let values: Vec<(u32, i64)> = self.leaves.iter()
+            .map(|leaf| (*leaf, self.solver.value(*leaf).unwrap_or(compiled.lower[*leaf as usize])))
+            .collect();
+        let solution = values.iter()
+            .map(|(leaf, value)| (compiled.id(*leaf).to_string(), *value))
+            .collect();
+
+        // Any later solution must differ in at least one projected leaf that
+        // is not fixed. If all of them are, this was the last solution.
+        let clause: Vec<(u32, bool)> = values.iter()
+            .filter(|(leaf, _)| self.solver.constant(*leaf).is_none())
+            .map(|(leaf, value)| (*leaf, *value == 0))
+            .collect();
+        if clause.is_empty() {
+            self.status = Status::Unsat;
+        } else {
+            self.solver.add_clause(&clause);
+        }
+        Some(solution)
+    }
+}
+
+// Returns every assignment of the leaves of a tree that makes all roots 1,
+// projected onto a set of leaves, as an iterator. Each solution is found by
+// searching with `Solver`, after which a clause ruling out the projected
+// values of that solution is added, so that no projected solution is
+// produced twice and the search never restarts from nothing.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to enumerate. Leaves must either be
+//   fixed, i.e. have equal lower and upper bounds, or have the bound [0, 1].
+// * `options` - The roots, projection and limits
+//
+// # Returns
+//
+// An iterator over the solutions, or an error if a node has no part, a root,
+// child or projected leaf does not exist in the tree, a projected node is a
+// BIC, or a leaf is neither fixed nor [0, 1]
+pub fn enumerate(tree: &LinearBoundedTree, options: &EnumerateOptions) -> Result<Solutions, LbtError> {
+    let deadline = options.time_limit.map(|limit| Instant::now() + limit);
+    let mut solver = Solver::new(tree)?;
+    let roots: Vec<u32> = if options.roots.is_empty() {
+        solver.parentless()
+    } else {
+        options.roots.iter().map(|root| solver.node(root)).collect::<Result<_, _>>()?
+    };
+    solver.require(&roots);
+
+    let compiled = solver.compiled();
+    let mut leaves: Vec<u32> = if options.projection.is_empty() {
+        (0..compiled.len() as u32)
+            .filter(|node| !compiled.is_bic[*node as usize] && solver.constant(*node).is_none())
+            .collect()
+    } else {
+        let mut leaves: Vec<u32> = Vec::with_capacity(options.projection.len());
+        for id in options.projection.iter() {
+            let node = solver.node(id)?;
+            if compiled.is_bic[node as usize] {
+                return Err(LbtError::Malformed(format!("node {} is a BIC, only leaves can be projected onto", id)));
+            }
+            leaves.push(node);
+        }
+        leaves
+    };
+    leaves.sort_unstable_by_key(|leaf| compiled.id(*leaf));
+    leaves.dedup();
+
+    Ok(Solutions { solver, leaves, remaining: options.limit, deadline, status: Status::Sat })
+}
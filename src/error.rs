@@ -0,0 +1,46 @@
+use std::fmt;
+
+// Errors that may occur when a LinearBoundedTree is processed by any of the
+// functions in this library. Each variant carries the id of the node that
+// caused the error, so that it can be reported back to the model author.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LbtError {
+    // A node was found without any part, i.e. it is neither a BIC nor a Bound
+    MissingPart(String),
+    // A node was referenced (e.g. as a root) but does not exist in the tree
+    UnknownNode(String),
+    // The relations of the tree form a cycle passing through the node
+    Cycle(String),
+    // The id cannot be represented in the requested output format
+    InvalidId(String),
+    // The input does not describe a valid model, the string explains why
+    Malformed(String),
+    // Reading or writing a file failed, the string holds the reason
+    Io(String),
+}
+
+impl fmt::Display for LbtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LbtError::MissingPart(id) => write!(f, "node {} has no part", id),
+            LbtError::UnknownNode(id) => write!(f, "node {} does not exist in the tree", id),
+            LbtError::Cycle(id) => write!(f, "node {} is part of a cycle", id),
+            LbtError::InvalidId(id) => write!(f, "id {:?} cannot be represented", id),
+            LbtError::Malformed(reason) => write!(f, "malformed input: {}", reason),
+            LbtError::Io(reason) => write!(f, "io error: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for LbtError {}
+
+// Errors are reported to gRPC clients as invalid arguments, except for io
+// errors which are on our side
+impl From<LbtError> for tonic::Status {
+    fn from(error: LbtError) -> Self {
+        match error {
+            LbtError::Io(_) => tonic::Status::internal(error.to_string()),
+            _ => tonic::Status::invalid_argument(error.to_string()),
+        }
+    }
+}
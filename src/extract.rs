@@ -0,0 +1,89 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::error::LbtError;
+use crate::graph::relations;
+use crate::puan_core::LinearBoundedTree;
+
+// Extracts the sub-tree reachable from a set of roots through the relations,
+// i.e. the roots together with all their descendants. The result contains
+// everything needed to propagate the roots, and nothing more.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to extract from
+// * `roots` - The ids of the nodes to extract the sub-tree of
+//
+// # Returns
+//
+// The extracted LinearBoundedTree, or an error if a root does not exist or a
+// node has no part
+pub fn extract(tree: &LinearBoundedTree, roots: &[String]) -> Result<LinearBoundedTree, LbtError> {
+    let mut extracted = LinearBoundedTree::default();
+    let mut stack: Vec<&str> = Vec::with_capacity(roots.len());
+
+    for root in roots {
+        if !tree.nodes.contains_key(root) {
+            return Err(LbtError::UnknownNode(root.to_string()));
+        }
+        stack.push(root);
+    }
+
+    while let Some(id) = stack.pop() {
+        if extracted.nodes.contains_key(id) {
+            continue;
+        }
+        let node = &tree.nodes[id];
+        for relation in relations(id, node)? {
+            // Children outside of the tree cannot be extracted, and children
+            // already extracted have already had their relations visited
+            if tree.nodes.contains_key(&relation.id) && !extracted.nodes.contains_key(&relation.id) {
+                stack.push(&relation.id);
+            }
+        }
+        extracted.nodes.insert(id.to_string(), node.clone());
+    }
+
+    Ok(extracted)
+}
+
+// Finds all nodes that are affected by a set of nodes, i.e. all nodes that
+// have at least one of them as a descendant. The given nodes are only part of
+// the result if they are affected by another one of the given nodes.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to search
+// * `ids` - The ids of the nodes, typically leaves, to find the ancestors of
+//
+// # Returns
+//
+// The sorted ids of all ancestors, or an error if an id does not exist or a
+// node has no part
+pub fn ancestors(tree: &LinearBoundedTree, ids: &[String]) -> Result<BTreeSet<String>, LbtError> {
+    // Invert the relations, so that we can walk from children to parents
+    let mut parents: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (id, node) in tree.nodes.iter() {
+        for relation in relations(id, node)? {
+            parents.entry(relation.id.as_str()).or_default().push(id);
+        }
+    }
+
+    let mut visited: HashSet<&str> = HashSet::new();
+    let mut stack: Vec<&str> = Vec::with_capacity(ids.len());
+    for id in ids {
+        if !tree.nodes.contains_key(id) {
+            return Err(LbtError::UnknownNode(id.to_string()));
+        }
+        stack.push(id);
+    }
+
+    while let Some(id) = stack.pop() {
+        for parent in parents.get(id).into_iter().flatten() {
+            if visited.insert(parent) {
+                stack.push(parent);
+            }
+        }
+    }
+
+    Ok(visited.into_iter().map(|id| id.to_string()).collect())
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use crate::error::LbtError;
+use crate::puan_core::{bic_or_bound, BicOrBound, CoefRelation, LinearBoundedTree};
+
+// Returns the relations of a node. Bounds have no relations, so an empty
+// slice is returned for them.
+//
+// # Arguments
+//
+// * `id` - The id of the node, only used for error reporting
+// * `node` - The node to get the relations from
+//
+// # Returns
+//
+// The relations of the node, or an error if the node has no part
+pub fn relations<'a>(id: &str, node: &'a BicOrBound) -> Result<&'a [CoefRelation], LbtError> {
+    match &node.part {
+        Some(bic_or_bound::Part::Bic(bic)) => Ok(&bic.relations),
+        Some(bic_or_bound::Part::Bound(_)) => Ok(&[]),
+        None => Err(LbtError::MissingPart(id.to_string())),
+    }
+}
+
+// Orders all nodes of a tree such that every node comes after all of its
+// children. Children that are not part of the tree are skipped, since there
+// is nothing to order for them. Nodes are visited in sorted id order, which
+// makes the result deterministic regardless of the hash map order.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to order
+//
+// # Returns
+//
+// The ids of the tree in topological (children first) order, or an error if
+// a node has no part or if the relations form a cycle
+pub fn topological_order(tree: &LinearBoundedTree) -> Result<Vec<&str>, LbtError> {
+    // 0 = not visited, 1 = on the current path, 2 = done
+    let mut state: HashMap<&str, u8> = HashMap::with_capacity(tree.nodes.len());
+    let mut order: Vec<&str> = Vec::with_capacity(tree.nodes.len());
+
+    let mut ids: Vec<&str> = tree.nodes.keys().map(|id| id.as_str()).collect();
+    ids.sort_unstable();
+
+    for start in ids {
+        if state.contains_key(start) {
+            continue;
+        }
+
+        // Each stack entry holds a node id and the index of the next relation
+        // to visit. A node is emitted once all its relations are visited.
+        let mut stack: Vec<(&str, usize)> = vec![(start, 0)];
+        state.insert(start, 1);
+
+        while let Some(&(id, next)) = stack.last() {
+            let node_relations = relations(id, &tree.nodes[id])?;
+            if next == node_relations.len() {
+                state.insert(id, 2);
+                order.push(id);
+                stack.pop();
+                continue;
+            }
+
+            let child = node_relations[next].id.as_str();
+            if let Some(top) = stack.last_mut() {
+                top.1 += 1;
+            }
+
+            // Children outside the tree cannot be ordered, just skip them
+            let Some((child, _)) = tree.nodes.get_key_value(child) else {
+                continue;
+            };
+            match state.get(child.as_str()) {
+                Some(1) => return Err(LbtError::Cycle(child.to_string())),
+                Some(_) => {},
+                None => {
+                    state.insert(child, 1);
+                    stack.push((child, 0));
+                },
+            }
+        }
+    }
+
+    Ok(order)
+}
@@ -0,0 +1,184 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use crate::backbone::fixed;
+use crate::cdcl::Solver;
+use crate::error::LbtError;
+use crate::puan_core::LinearBoundedTree;
+use crate::solve::Status;
+
+// Options for `health`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthOptions {
+    // The nodes that must be 1 in a valid configuration. If empty, every
+    // node that no other node relates to must be 1.
+    pub roots: Vec<String>,
+    // How long to search, or no limit if None
+    pub time_limit: Option<Duration>,
+}
+
+// The result of `health`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Health {
+    // Every node that is 0 in all valid configurations, with the constraints
+    // that together rule out it being 1, sorted by id, or None if the time
+    // limit was reached before they were found. No constraints means the
+    // node can never be 1 on its own.
+    pub dead: BTreeMap<String, Option<Vec<String>>>,
+    // Every node that is 1 in all valid configurations, apart from the
+    // roots, with the constraints that together rule out it being 0
+    pub mandatory: BTreeMap<String, Option<Vec<String>>>,
+    // Whether every node was decided and explained. If the time limit was
+    // reached first, the nodes found so far are still dead or mandatory, but
+    // others may be too.
+    pub complete: bool,
+}
+
+// Finds the nodes of a tree that have the same value in every valid
+// configuration, i.e. every assignment of its leaves that makes all roots 1,
+// such as options that can never be selected.
+//
+// The nodes are found like the leaves of `backbone`, but over all nodes. The
+// constraints responsible are taken among the roots and the BICs that are
+// mandatory: a second solver without the roots required searches
+// with all of them assumed to be 1 and the node assumed to have its other
+// value, and the core of that Unsat search names the constraints that cannot
+// hold together with it. The core is not necessarily the smallest one.
+// Mandatory BICs are explained by the other constraints, see
+// `explain_constraints`. Leaves that are fixed in the tree are left out,
+// since their bound already says so.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to analyse. Leaves must either be fixed,
+//   i.e. have equal lower and upper bounds, or have the bound [0, 1].
+// * `options` - The roots and the time limit
+//
+// # Returns
+//
+// The dead and mandatory nodes, None if there is no valid configuration at
+// all, or an error if a node has no part, a root or child does not exist in
+// the tree, or a leaf is neither fixed nor [0, 1]
+pub fn health(tree: &LinearBoundedTree, options: &HealthOptions) -> Result<Option<Health>, LbtError> {
+    let deadline = options.time_limit.map(|limit| Instant::now() + limit);
+    let mut solver = Solver::new(tree)?;
+    let roots: Vec<u32> = if options.roots.is_empty() {
+        solver.parentless()
+    } else {
+        options.roots.iter().map(|root| solver.node(root)).collect::<Result<_, _>>()?
+    };
+    solver.require(&roots);
+
+    let nodes: Vec<u32> = (0..solver.compiled().len() as u32)
+        .filter(|node| solver.constant(*node).is_none())
+        .collect();
+    let Some(mut found) = fixed(&mut solver, &nodes, deadline) else {
+        return Ok(None);
+    };
+    found.values.sort_unstable();
+    let compiled = solver.compiled();
+    // Every root is assumed, leaves included, as a root leaf may be what
+    // rules out a value
+    let required: Vec<(u32, bool)> = roots.iter().map(|root| (*root, true)).collect();
+    let constraints: Vec<(u32, bool)> = found.values.iter()
+        .filter(|(node, value)| *value && compiled.is_bic[*node as usize] && !roots.contains(node))
+        .copied()
+        .collect();
+
+    let mut explainer = Solver::new(tree)?;
+    explainer.add_nodes(&nodes);
+
+    let mut explained: HashMap<u32, Option<Vec<u32>>> = HashMap::new();
+    let mut assumed: Vec<(u32, bool)> = Vec::new();
+    explain_constraints(&mut explainer, &mut assumed, &constraints, &required, deadline, &mut explained);
+    assumed.extend_from_slice(&constraints);
+    for (node, value) in found.values.iter() {
+        if !roots.contains(node) && !explained.contains_key(node) {
+            explained.insert(*node, explain(&mut explainer, &mut assumed, &required, (*node, !value), deadline));
+        }
+    }
+
+    let mut health = Health { dead: BTreeMap::new(), mandatory: BTreeMap::new(), complete: found.complete };
+    for (node, value) in found.values.iter() {
+        let Some(responsible) = explained.remove(node) else {
+            continue;
+        };
+        let responsible: Option<Vec<String>> = responsible.map(|responsible| {
+            let mut responsible: Vec<String> = responsible.into_iter()
+                .map(|other| compiled.id(other).to_string())
+                .collect();
+            responsible.sort_unstable();
+            responsible
+        });
+        health.complete &= responsible.is_some();
+        let id = compiled.id(*node).to_string();
+        if *value {
+            health.mandatory.insert(id, responsible);
+        } else {
+            health.dead.insert(id, responsible);
+        }
+    }
+    Ok(Some(health))
+}
+
+// Explains every constraint with all other constraints assumed, see
+// `explain`. A mandatory BIC is one of the constraints, but cannot explain
+// itself. Rather than assuming all others anew for each, each half of the
+// constraints is assumed while the other half is explained, so that
+// consecutive searches share most of their assumptions and keep their
+// levels.
+fn explain_constraints(
+    solver: &mut Solver,
+    assumed: &mut Vec<(u32, bool)>,
+    constraints: &[(u32, bool)],
+    roots: &[(u32, bool)],
+    deadline: Option<Instant>,
+    explained: &mut HashMap<u32, Option<Vec<u32>>>,
+) {
+    match constraints {
+        [] => {},
+        [(node, value)] => {
+            explained.insert(*node, explain(solver, assumed, roots, (*node, !value), deadline));
+        },
+        _ => {
+            let (left, right) = constraints.split_at(constraints.len() / 2);
+            for (half, other) in [(left, right), (right, left)] {
+                let length = assumed.len();
+                assumed.extend_from_slice(half);
+                explain_constraints(solver, assumed, other, roots, deadline, explained);
+                assumed.truncate(length);
+            }
+        },
+    }
+}
+
+// Searches with the constraints assumed and a node assumed to have the value
+// it never has in a valid configuration. The roots are assumed after all
+// other constraints, since they usually imply the others, which would then
+// not be decided and never be part of a core.
+//
+// # Returns
+//
+// The constraints in the core of the search, or None if the deadline was
+// reached first or the search is Sat. Since all roots are assumed, Sat would
+// mean a valid configuration has the value, so the node was not fixed.
+fn explain(
+    solver: &mut Solver,
+    assumed: &mut Vec<(u32, bool)>,
+    roots: &[(u32, bool)],
+    assumption: (u32, bool),
+    deadline: Option<Instant>,
+) -> Option<Vec<u32>> {
+    let length = assumed.len();
+    assumed.extend_from_slice(roots);
+    assumed.push(assumption);
+    let status = solver.solve(assumed, deadline);
+    assumed.truncate(length);
+    match status {
+        Status::Unsat => Some(solver.core().iter()
+            .filter(|(node, _)| *node != assumption.0)
+            .map(|(node, _)| *node)
+            .collect()),
+        Status::Sat | Status::Unknown => None,
+    }
+}
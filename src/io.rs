@@ -0,0 +1,302 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage};
+
+use crate::error::LbtError;
+use crate::puan_core::{bic_or_bound, LinearBoundedTree};
+use crate::DESCRIPTOR_SET;
+
+// How the messages of a binary stream of trees are delimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    // Each message is prefixed with its length as a varint. This is what
+    // `writeDelimitedTo` and `parseDelimitedFrom` use in the protobuf
+    // libraries for other languages.
+    Varint,
+    // Each message is prefixed with a compression flag byte and its length as
+    // a big-endian u32. This is how gRPC frames messages on the wire, so a
+    // recorded `PropagateLbtStreamed` request body can be read as is.
+    Grpc,
+}
+
+// Writes a tree in the protobuf text format.
+//
+// Nodes are written in sorted id order and all fields are written, even the
+// ones holding default values. This keeps the output stable and easy to read
+// in code review. The output can be read back by `from_text_format`, or by
+// any other protobuf text format parser.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to write
+//
+// # Returns
+//
+// The tree in the protobuf text format
+pub fn to_text_format(tree: &LinearBoundedTree) -> String {
+    let mut ids: Vec<&String> = tree.nodes.keys().collect();
+    ids.sort_unstable();
+
+    // Writing to a String cannot fail, hence all the unwraps below
+    let mut text = String::new();
+    for id in ids {
+        writeln!(text, "nodes {{").unwrap();
+        writeln!(text, "  key: {}", quote(id)).unwrap();
+        writeln!(text, "  value {{").unwrap();
+        match &tree.nodes[id].part {
+            Some(bic_or_bound::Part::Bic(bic)) => {
+                writeln!(text, "    bic {{").unwrap();
+                for relation in bic.relations.iter() {
+                    writeln!(
+                        text,
+                        "      relations {{ id: {} coefficient: {} }}",
+                        quote(&relation.id), relation.coefficient,
+                    ).unwrap();
+                }
+                writeln!(text, "    }}").unwrap();
+            },
+            Some(bic_or_bound::Part::Bound(bound)) => {
+                writeln!(text, "    bound {{ lower: {} upper: {} }}", bound.lower, bound.upper).unwrap();
+            },
+            None => {},
+        }
+        writeln!(text, "  }}").unwrap();
+        writeln!(text, "}}").unwrap();
+    }
+
+    text
+}
+
+// Reads a tree from the protobuf text format.
+//
+// # Arguments
+//
+// * `text` - The tree in the protobuf text format
+//
+// # Returns
+//
+// The LinearBoundedTree, or an error if the text could not be parsed
+pub fn from_text_format(text: &str) -> Result<LinearBoundedTree, LbtError> {
+    let pool = DescriptorPool::decode(DESCRIPTOR_SET)
+        .map_err(|error| LbtError::Malformed(error.to_string()))?;
+    let descriptor = pool.get_message_by_name("puan_core.LinearBoundedTree")
+        .ok_or_else(|| LbtError::Malformed("missing descriptor for LinearBoundedTree".to_string()))?;
+
+    DynamicMessage::parse_text_format(descriptor, text)
+        .map_err(|error| LbtError::Malformed(error.to_string()))?
+        .transcode_to::<LinearBoundedTree>()
+        .map_err(|error| LbtError::Malformed(error.to_string()))
+}
+
+// Reads a single tree from a file. Files ending with `.txtpb`, `.textproto`
+// or `.pbtxt` are read as protobuf text format, all others as binary
+// protobuf.
+//
+// # Arguments
+//
+// * `path` - The file to read
+//
+// # Returns
+//
+// The LinearBoundedTree, or an error if the file could not be read or parsed
+pub fn read_file(path: &Path) -> Result<LinearBoundedTree, LbtError> {
+    let bytes = fs::read(path)
+        .map_err(|error| LbtError::Io(format!("{}: {}", path.display(), error)))?;
+
+    if is_text_format(path) {
+        let text = String::from_utf8(bytes)
+            .map_err(|error| LbtError::Malformed(error.to_string()))?;
+        from_text_format(&text)
+    } else {
+        LinearBoundedTree::decode(bytes.as_slice())
+            .map_err(|error| LbtError::Malformed(error.to_string()))
+    }
+}
+
+// Writes a single tree to a file, choosing the format from the file
+// extension in the same way as `read_file`.
+//
+// # Arguments
+//
+// * `path` - The file to write
+// * `tree` - The LinearBoundedTree to write
+pub fn write_file(path: &Path, tree: &LinearBoundedTree) -> Result<(), LbtError> {
+    let bytes = if is_text_format(path) {
+        to_text_format(tree).into_bytes()
+    } else {
+        tree.encode_to_vec()
+    };
+    fs::write(path, bytes)
+        .map_err(|error| LbtError::Io(format!("{}: {}", path.display(), error)))
+}
+
+fn is_text_format(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("txtpb") | Some("textproto") | Some("pbtxt")
+    )
+}
+
+// Writes trees as a binary stream of delimited messages.
+//
+// # Arguments
+//
+// * `writer` - Where to write the stream
+// * `trees` - The trees to write, in order
+// * `framing` - How to delimit the messages
+pub fn write_delimited<'a, W, I>(writer: &mut W, trees: I, framing: Framing) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a LinearBoundedTree>,
+{
+    let mut buffer: Vec<u8> = Vec::new();
+    for tree in trees {
+        buffer.clear();
+        match framing {
+            Framing::Varint => {
+                tree.encode_length_delimited(&mut buffer)
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+            },
+            Framing::Grpc => {
+                let length = u32::try_from(tree.encoded_len())
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+                // Messages are never written compressed
+                buffer.push(0);
+                buffer.extend_from_slice(&length.to_be_bytes());
+                tree.encode(&mut buffer)
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+            },
+        }
+        writer.write_all(&buffer)?;
+    }
+    Ok(())
+}
+
+// Reads trees one by one from a binary stream of delimited messages. The
+// stream is read a few bytes at a time, so wrapping the reader in a
+// `BufReader` is recommended.
+pub struct DelimitedReader<R> {
+    reader: R,
+    framing: Framing,
+    // Set once an error was returned, since the stream cannot be resynced
+    failed: bool,
+}
+
+impl<R: Read> DelimitedReader<R> {
+    pub fn new(reader: R, framing: Framing) -> Self {
+        DelimitedReader { reader, framing, failed: false }
+    }
+
+    // Reads the length prefix of the next message, or returns None if the
+    // stream ended right before it
+    fn read_length(&mut self) -> io::Result<Option<usize>> {
+        match self.framing {
+            Framing::Varint => {
+                let mut length: u64 = 0;
+                for shift in (0..64).step_by(7) {
+                    let mut byte = [0u8; 1];
+                    if read_full(&mut self.reader, &mut byte)? == 0 {
+                        if shift == 0 {
+                            return Ok(None);
+                        }
+                        return Err(io::ErrorKind::UnexpectedEof.into());
+                    }
+                    length |= ((byte[0] & 0x7f) as u64) << shift;
+                    if byte[0] & 0x80 == 0 {
+                        return Ok(Some(length as usize));
+                    }
+                }
+                Err(io::Error::new(io::ErrorKind::InvalidData, "varint length prefix is too long"))
+            },
+            Framing::Grpc => {
+                let mut header = [0u8; 5];
+                let read = read_full(&mut self.reader, &mut header)?;
+                if read == 0 {
+                    return Ok(None);
+                }
+                if read < header.len() {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                if header[0] != 0 {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "compressed messages are not supported"));
+                }
+                Ok(Some(u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize))
+            },
+        }
+    }
+
+    // Reads the next message, or returns None if the stream ended
+    fn read_message(&mut self) -> Option<io::Result<LinearBoundedTree>> {
+        let length = match self.read_length() {
+            Ok(Some(length)) => length,
+            Ok(None) => return None,
+            Err(error) => return Some(Err(error)),
+        };
+
+        // Read through `take` instead of allocating `length` bytes up front,
+        // so that a corrupt length prefix cannot allocate huge amounts of memory
+        let mut message: Vec<u8> = Vec::new();
+        match (&mut self.reader).take(length as u64).read_to_end(&mut message) {
+            Ok(read) if read == length => {},
+            Ok(_) => return Some(Err(io::ErrorKind::UnexpectedEof.into())),
+            Err(error) => return Some(Err(error)),
+        }
+
+        Some(
+            LinearBoundedTree::decode(message.as_slice())
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+        )
+    }
+}
+
+impl<R: Read> Iterator for DelimitedReader<R> {
+    type Item = io::Result<LinearBoundedTree>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.read_message();
+        self.failed = matches!(result, Some(Err(_)));
+        result
+    }
+}
+
+// Reads until the buffer is full or the reader is exhausted, and returns the
+// number of bytes read
+fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buffer.len() {
+        match reader.read(&mut buffer[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {},
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(read)
+}
+
+// Quotes a string for the protobuf text format. Bytes outside of printable
+// ASCII are written as octal escapes, which all parsers understand.
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for byte in value.bytes() {
+        match byte {
+            b'"' => quoted.push_str("\\\""),
+            b'\\' => quoted.push_str("\\\\"),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\t' => quoted.push_str("\\t"),
+            0x20..=0x7e => quoted.push(byte as char),
+            _ => write!(quoted, "\\{:03o}", byte).unwrap(),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
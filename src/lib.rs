@@ -0,0 +1,34 @@
+pub mod puan_core;
+pub mod puan_analysis;
+
+pub mod backbone;
+pub mod batch;
+pub mod bitsliced;
+pub mod cdcl;
+pub mod compiled;
+pub mod compose;
+pub mod count;
+pub mod dedup;
+pub mod diff;
+pub mod enumerate;
+pub mod error;
+pub mod extract;
+pub mod graph;
+pub mod health;
+pub mod io;
+pub mod model;
+pub mod optimize;
+pub mod passes;
+pub mod polyhedron;
+pub mod program;
+pub mod propagate;
+pub mod range;
+pub mod simplify;
+pub mod smt;
+pub mod solve;
+
+pub use error::LbtError;
+
+// The encoded file descriptor set of all protobuf files, used both for
+// server reflection and for reading trees in the protobuf text format.
+pub const DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("descriptor");
@@ -0,0 +1,372 @@
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::compiled::{bic_bound, compile, CompiledTree, Values};
+use crate::error::LbtError;
+use crate::puan_core::LinearBoundedTree;
+
+// The first bytes of every model file
+pub const MAGIC: &[u8; 8] = b"LBTMODEL";
+// The version of the format written by this library. Files of any other
+// version are rejected.
+pub const VERSION: u32 = 1;
+// The size of the header, after which the sections start
+pub const HEADER_SIZE: usize = 64;
+
+// A compiled tree stored in a single buffer that can be evaluated in place,
+// e.g. straight from a memory mapped file, without decoding it first.
+//
+// The file starts with a 64 byte header, all numbers little endian:
+//
+// * 0..8 - the magic `LBTMODEL`
+// * 8..12 - the format version
+// * 12..16 - reserved, always 0
+// * 16..24 - the number of nodes
+// * 24..32 - the number of relations
+// * 32..40 - the number of BICs in the evaluation order
+// * 40..48 - the number of level offsets
+// * 48..56 - the length of all ids in bytes
+// * 56..60 - the CRC-32 of everything after the header
+// * 60..64 - the CRC-32 of the first 60 bytes of the header
+//
+// It is followed by the arrays of the compiled tree (see `CompiledTree`), each
+// starting at a multiple of 8 bytes: `lower`, `upper` and `coefficients` as
+// i64, `name_offsets`, `offsets`, `children`, `order` and `levels` as u32,
+// `is_bic` as one byte per node and finally `names`. Where each array starts
+// follows from the counts in the header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelView<'a> {
+    pub names: &'a str,
+    pub name_offsets: &'a [u32],
+    pub is_bic: &'a [u8],
+    pub lower: &'a [i64],
+    pub upper: &'a [i64],
+    pub offsets: &'a [u32],
+    pub children: &'a [u32],
+    pub coefficients: &'a [i64],
+    pub order: &'a [u32],
+    pub levels: &'a [u32],
+}
+
+// The counts stored in the header, from which the position of every array
+// is computed
+struct Layout {
+    nodes: usize,
+    relations: usize,
+    ordered: usize,
+    levels: usize,
+    names: usize,
+}
+
+// The position of every array in the file, in the order they are stored
+struct Sections {
+    lower: Range<usize>,
+    upper: Range<usize>,
+    coefficients: Range<usize>,
+    name_offsets: Range<usize>,
+    offsets: Range<usize>,
+    children: Range<usize>,
+    order: Range<usize>,
+    levels: Range<usize>,
+    is_bic: Range<usize>,
+    names: Range<usize>,
+    end: usize,
+}
+
+impl Layout {
+    fn sections(&self) -> Option<Sections> {
+        let mut position = HEADER_SIZE;
+        let mut section = |count: usize, size: usize| -> Option<Range<usize>> {
+            let start = position;
+            let end = start.checked_add(count.checked_mul(size)?)?;
+            position = end.checked_add(7)? / 8 * 8;
+            Some(start..end)
+        };
+        Some(Sections {
+            lower: section(self.nodes, 8)?,
+            upper: section(self.nodes, 8)?,
+            coefficients: section(self.relations, 8)?,
+            name_offsets: section(self.nodes.checked_add(1)?, 4)?,
+            offsets: section(self.nodes.checked_add(1)?, 4)?,
+            children: section(self.relations, 4)?,
+            order: section(self.ordered, 4)?,
+            levels: section(self.levels, 4)?,
+            is_bic: section(self.nodes, 1)?,
+            names: section(self.names, 1)?,
+            end: position,
+        })
+    }
+}
+
+impl<'a> ModelView<'a> {
+    // Reads a model from a buffer without copying it. The buffer must start
+    // at an address that is a multiple of 8, which memory mapped files always
+    // do.
+    //
+    // # Arguments
+    //
+    // * `bytes` - The model, as written by `to_bytes`
+    //
+    // # Returns
+    //
+    // A view of the model, or an error if the magic, version, checksums or
+    // sizes do not match, or an offset or node in it is out of range
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, LbtError> {
+        parse(bytes, true)
+    }
+
+    // Returns the number of nodes in the model
+    pub fn len(&self) -> usize {
+        self.is_bic.len()
+    }
+
+    // Returns true if the model has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.is_bic.is_empty()
+    }
+
+    // Returns the id of a node
+    pub fn id(&self, node: u32) -> &'a str {
+        &self.names[self.name_offsets[node as usize] as usize..self.name_offsets[node as usize + 1] as usize]
+    }
+
+    // Returns the children and coefficients of a node
+    pub fn relations(&self, node: u32) -> (&'a [u32], &'a [i64]) {
+        let range = self.offsets[node as usize] as usize..self.offsets[node as usize + 1] as usize;
+        (&self.children[range.clone()], &self.coefficients[range])
+    }
+
+    // Returns the values of all nodes before any BIC is propagated
+    pub fn initial_values(&self) -> Values {
+        Values { lower: self.lower.to_vec(), upper: self.upper.to_vec() }
+    }
+
+    // Propagates all BICs in the evaluation order, like `CompiledTree::evaluate`
+    pub fn evaluate(&self, values: &mut Values) {
+        for node in self.order.iter() {
+            let (children, coefficients) = self.relations(*node);
+            let (lower, upper) = bic_bound(children, coefficients, values);
+            values.lower[*node as usize] = lower;
+            values.upper[*node as usize] = upper;
+        }
+    }
+
+    // Copies the model into a compiled tree
+    pub fn to_compiled(&self) -> CompiledTree {
+        CompiledTree {
+            names: self.names.to_string(),
+            name_offsets: self.name_offsets.to_vec(),
+            is_bic: self.is_bic.iter().map(|is_bic| *is_bic != 0).collect(),
+            lower: self.lower.to_vec(),
+            upper: self.upper.to_vec(),
+            offsets: self.offsets.to_vec(),
+            children: self.children.to_vec(),
+            coefficients: self.coefficients.to_vec(),
+            order: self.order.to_vec(),
+            levels: self.levels.to_vec(),
+        }
+    }
+}
+
+// A model file mapped into memory. The file is checked once when it is
+// opened, after which views of it are free to create.
+#[derive(Debug)]
+pub struct MappedModel {
+    mmap: Mmap,
+}
+
+impl MappedModel {
+    // Maps a model file into memory and checks it.
+    //
+    // # Arguments
+    //
+    // * `path` - The path of a file written by `write_model`
+    //
+    // # Returns
+    //
+    // The mapped model, or an error if the file cannot be mapped or is not a
+    // valid model
+    pub fn open(path: &Path) -> Result<Self, LbtError> {
+        let file = File::open(path).map_err(|error| LbtError::Io(format!("{}: {}", path.display(), error)))?;
+        // Safety: the map is read only. The model file must not be changed
+        // while it is mapped, which is the case for files only written by
+        // `write_model`, as that replaces the file rather than changing it.
+        let mmap = unsafe { Mmap::map(&file) }
+            .map_err(|error| LbtError::Io(format!("{}: {}", path.display(), error)))?;
+        parse(&mmap, true)?;
+        Ok(MappedModel { mmap })
+    }
+
+    // Returns a view of the model
+    pub fn view(&self) -> ModelView<'_> {
+        parse(&self.mmap, false).expect("model was checked when opened")
+    }
+}
+
+// Reads the header and sections of a model. The checksum and the offsets and
+// nodes of the sections are only checked if `verify` is set, as it means
+// reading the whole model.
+fn parse(bytes: &[u8], verify: bool) -> Result<ModelView<'_>, LbtError> {
+    if cfg!(target_endian = "big") {
+        return Err(LbtError::Malformed("models can only be read on little endian machines".to_string()));
+    }
+    if bytes.len() < HEADER_SIZE || &bytes[..8] != MAGIC {
+        return Err(LbtError::Malformed("not a model file".to_string()));
+    }
+    let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+    let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+
+    if crc32fast::hash(&bytes[..60]) != u32_at(60) {
+        return Err(LbtError::Malformed("model header checksum does not match".to_string()));
+    }
+    if u32_at(8) != VERSION {
+        return Err(LbtError::Malformed(format!("unsupported model version {}, expected {}", u32_at(8), VERSION)));
+    }
+
+    let count = |at: usize| usize::try_from(u64_at(at))
+        .map_err(|_| LbtError::Malformed("model is too large".to_string()));
+    let layout = Layout {
+        nodes: count(16)?,
+        relations: count(24)?,
+        ordered: count(32)?,
+        levels: count(40)?,
+        names: count(48)?,
+    };
+    let sections = layout.sections()
+        .filter(|sections| sections.end == bytes.len())
+        .ok_or_else(|| LbtError::Malformed("model size does not match its header".to_string()))?;
+    if verify && crc32fast::hash(&bytes[HEADER_SIZE..]) != u32_at(56) {
+        return Err(LbtError::Malformed("model checksum does not match".to_string()));
+    }
+
+    let misaligned = |_| LbtError::Malformed("model is not aligned to 8 bytes".to_string());
+    let view = ModelView {
+        names: std::str::from_utf8(&bytes[sections.names])
+            .map_err(|_| LbtError::Malformed("model ids are not valid utf-8".to_string()))?,
+        name_offsets: bytemuck::try_cast_slice(&bytes[sections.name_offsets]).map_err(misaligned)?,
+        is_bic: &bytes[sections.is_bic],
+        lower: bytemuck::try_cast_slice(&bytes[sections.lower]).map_err(misaligned)?,
+        upper: bytemuck::try_cast_slice(&bytes[sections.upper]).map_err(misaligned)?,
+        offsets: bytemuck::try_cast_slice(&bytes[sections.offsets]).map_err(misaligned)?,
+        children: bytemuck::try_cast_slice(&bytes[sections.children]).map_err(misaligned)?,
+        coefficients: bytemuck::try_cast_slice(&bytes[sections.coefficients]).map_err(misaligned)?,
+        order: bytemuck::try_cast_slice(&bytes[sections.order]).map_err(misaligned)?,
+        levels: bytemuck::try_cast_slice(&bytes[sections.levels]).map_err(misaligned)?,
+    };
+
+    // The last offsets must match the lengths of the arrays they index
+    if view.offsets[layout.nodes] as usize != layout.relations
+        || view.name_offsets[layout.nodes] as usize != layout.names
+    {
+        return Err(LbtError::Malformed("model offsets do not match its header".to_string()));
+    }
+    // The checksum only covers corruption, so a model read for the first
+    // time is also checked to never index out of bounds when used
+    if verify {
+        check(&view)?;
+    }
+    Ok(view)
+}
+
+// Checks that every offset and node in a model is in range
+fn check(view: &ModelView) -> Result<(), LbtError> {
+    let increasing = |offsets: &[u32]| offsets.windows(2).all(|pair| pair[0] <= pair[1]);
+    if !increasing(view.offsets) || !increasing(view.name_offsets) || !increasing(view.levels) {
+        return Err(LbtError::Malformed("model offsets are not increasing".to_string()));
+    }
+    if view.name_offsets.iter().any(|offset| !view.names.is_char_boundary(*offset as usize)) {
+        return Err(LbtError::Malformed("model id offsets are not at character boundaries".to_string()));
+    }
+    if view.levels.last().is_some_and(|last| *last as usize > view.order.len()) {
+        return Err(LbtError::Malformed("model levels are out of range".to_string()));
+    }
+    let nodes = view.len();
+    if view.children.iter().chain(view.order.iter()).any(|node| *node as usize >= nodes) {
+        return Err(LbtError::Malformed("model nodes are out of range".to_string()));
+    }
+    Ok(())
+}
+
+// Writes a compiled tree in the model format.
+//
+// # Arguments
+//
+// * `compiled` - The compiled tree to write
+//
+// # Returns
+//
+// The model as bytes
+pub fn to_bytes(compiled: &CompiledTree) -> Vec<u8> {
+    let layout = Layout {
+        nodes: compiled.len(),
+        relations: compiled.children.len(),
+        ordered: compiled.order.len(),
+        levels: compiled.levels.len(),
+        names: compiled.names.len(),
+    };
+    let sections = layout.sections().expect("compiled tree fits in memory");
+
+    let mut bytes: Vec<u8> = vec![0; sections.end];
+    bytes[..8].copy_from_slice(MAGIC);
+    bytes[8..12].copy_from_slice(&VERSION.to_le_bytes());
+    bytes[16..24].copy_from_slice(&(layout.nodes as u64).to_le_bytes());
+    bytes[24..32].copy_from_slice(&(layout.relations as u64).to_le_bytes());
+    bytes[32..40].copy_from_slice(&(layout.ordered as u64).to_le_bytes());
+    bytes[40..48].copy_from_slice(&(layout.levels as u64).to_le_bytes());
+    bytes[48..56].copy_from_slice(&(layout.names as u64).to_le_bytes());
+
+    put_i64(&mut bytes[sections.lower], &compiled.lower);
+    put_i64(&mut bytes[sections.upper], &compiled.upper);
+    put_i64(&mut bytes[sections.coefficients], &compiled.coefficients);
+    put_u32(&mut bytes[sections.name_offsets], &compiled.name_offsets);
+    put_u32(&mut bytes[sections.offsets], &compiled.offsets);
+    put_u32(&mut bytes[sections.children], &compiled.children);
+    put_u32(&mut bytes[sections.order], &compiled.order);
+    put_u32(&mut bytes[sections.levels], &compiled.levels);
+    for (byte, is_bic) in bytes[sections.is_bic].iter_mut().zip(compiled.is_bic.iter()) {
+        *byte = *is_bic as u8;
+    }
+    bytes[sections.names].copy_from_slice(compiled.names.as_bytes());
+
+    let payload = crc32fast::hash(&bytes[HEADER_SIZE..]);
+    bytes[56..60].copy_from_slice(&payload.to_le_bytes());
+    let header = crc32fast::hash(&bytes[..60]);
+    bytes[60..64].copy_from_slice(&header.to_le_bytes());
+    bytes
+}
+
+fn put_i64(bytes: &mut [u8], values: &[i64]) {
+    for (chunk, value) in bytes.chunks_exact_mut(8).zip(values.iter()) {
+        chunk.copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn put_u32(bytes: &mut [u8], values: &[u32]) {
+    for (chunk, value) in bytes.chunks_exact_mut(4).zip(values.iter()) {
+        chunk.copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+// Compiles a tree and writes it as a model file. The model is written to a
+// temporary file next to `path` which then replaces `path`, so a model that
+// is mapped by another process is never changed under it.
+//
+// # Arguments
+//
+// * `path` - Where to write the model
+// * `tree` - The LinearBoundedTree to convert
+//
+// # Returns
+//
+// Nothing, or an error if the tree cannot be compiled or the file cannot be written
+pub fn write_model(path: &Path, tree: &LinearBoundedTree) -> Result<(), LbtError> {
+    let bytes = to_bytes(&compile(tree)?);
+    let mut temporary = path.as_os_str().to_os_string();
+    temporary.push(".tmp");
+    let io_error = |error: std::io::Error| LbtError::Io(format!("{}: {}", path.display(), error));
+    std::fs::write(&temporary, bytes).map_err(io_error)?;
+    std::fs::rename(&temporary, path).map_err(io_error)
+}
@@ -0,0 +1,249 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::cdcl::Solver;
+use crate::error::LbtError;
+use crate::puan_core::LinearBoundedTree;
+use crate::solve::{free_leaves, SolveStats, Status};
+
+// Whether to find the smallest or the largest value of an objective
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Sense {
+    #[default]
+    Minimize,
+    Maximize,
+}
+
+// Options for `optimize`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OptimizeOptions {
+    // The nodes that must be 1. If empty, every node that no other node
+    // relates to must be 1.
+    pub roots: Vec<String>,
+    // The coefficient of each node in the objective, e.g. the price of each
+    // option
+    pub objective: HashMap<String, i64>,
+    pub sense: Sense,
+    // How long to search for the optimum, or no limit if None
+    pub time_limit: Option<Duration>,
+}
+
+// How far the search for the optimum got
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptimizeStatus {
+    // The time limit was reached before any assignment was found
+    Unknown,
+    // An assignment was found, but the time limit was reached before it was
+    // proven to be optimal
+    Feasible,
+    // The assignment found is proven to be optimal
+    Optimal,
+    // No assignment makes all roots 1
+    Infeasible,
+}
+
+// The result of `optimize`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Optimum {
+    pub status: OptimizeStatus,
+    // The objective of the best assignment found, if any
+    pub value: Option<i64>,
+    // The best objective any assignment could have, as far as the search
+    // proved. It equals the value once the value is optimal.
+    pub bound: i64,
+    // The value of every leaf that is not fixed in the best assignment found,
+    // empty if none was found
+    pub model: BTreeMap<String, i64>,
+    pub stats: SolveStats,
+}
+
+impl Optimum {
+    // Returns how much better than the value found an assignment could still
+    // be, which is 0 once the value is optimal, or None if nothing was found
+    pub fn gap(&self) -> Option<i64> {
+        self.value.map(|value| (value - self.bound).abs())
+    }
+}
+
+// A part of the objective the search tries to avoid paying: the weight is
+// paid unless the node has the given value
+struct Soft {
+    node: u32,
+    value: bool,
+    weight: i64,
+    // If the node counts the softs of a core, the core and how many of them
+    // it allows for free, see `relax`
+    counter: Option<(Vec<(u32, bool)>, usize)>,
+}
+
+// Adds a node that is 1 whenever more than `free` of the softs of a core
+// are not met, i.e. `sum(unmet) <= free + (len - free) * node`
+fn relax(solver: &mut Solver, core: &[(u32, bool)], free: usize) -> Result<u32, LbtError> {
+    let node = solver.add_variable();
+    // An unmet soft wanting 0 is the node itself and one wanting 1 is one
+    // minus the node, so the constraint is -sum(unmet) + (len - free) * node >= -free
+    let mut degree = -(free as i64);
+    let mut terms: Vec<(u32, i64)> = Vec::with_capacity(core.len() + 1);
+    for (other, value) in core.iter() {
+        if *value {
+            terms.push((*other, 1));
+            degree += 1;
+        } else {
+            terms.push((*other, -1));
+        }
+    }
+    terms.push((node, (core.len() - free) as i64));
+    solver.add_linear(&terms, degree)?;
+    Ok(node)
+}
+
+// Finds the assignment of the leaves of a tree that makes all roots 1 and
+// minimizes or maximizes a linear objective over its nodes.
+//
+// The search is done by a `Solver` over the same constraints as `solve`,
+// with the objective split into softs: a node with a positive coefficient
+// should be 0 and one with a negative coefficient should be 1, each paying
+// the size of its coefficient if it is not. The solver searches with every
+// soft assumed to be met. When that is Unsat, the solver names a core of
+// softs that cannot all be met, so the smallest weight among them is
+// certainly paid and is added to the bound. Their weights are lowered by
+// it, and a new soft is added that is only met if at most one of the core
+// is not, and so on once that one ends up in a core (OLL). Once the search
+// is Sat, the assignment pays exactly the bound and is optimal. Only the
+// softs of the largest weights are assumed at first, halving the weight
+// needed until all are, which finds good assignments early. Maximizing is
+// done by minimizing the negated objective.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to optimize over. Leaves must either be
+//   fixed, i.e. have equal lower and upper bounds, or have the bound [0, 1].
+// * `options` - The roots, the objective, its sense and the time limit
+//
+// # Returns
+//
+// The optimum, or an error if a node has no part, a root, child or node of
+// the objective does not exist in the tree, a leaf is neither fixed nor
+// [0, 1], or the objective of some assignment does not fit in 64 bits
+pub fn optimize(tree: &LinearBoundedTree, options: &OptimizeOptions) -> Result<Optimum, LbtError> {
+    let deadline = options.time_limit.map(|limit| Instant::now() + limit);
+    let mut solver = Solver::new(tree)?;
+    let roots: Vec<u32> = if options.roots.is_empty() {
+        solver.parentless()
+    } else {
+        options.roots.iter().map(|root| solver.node(root)).collect::<Result<_, _>>()?
+    };
+    solver.require(&roots);
+
+    let sign = match options.sense {
+        Sense::Minimize => 1,
+        Sense::Maximize => -1,
+    };
+    let overflow = || LbtError::Malformed("the objective does not fit in 64 bits".to_string());
+    let mut terms: Vec<(u32, i64)> = Vec::with_capacity(options.objective.len());
+    for (id, coefficient) in options.objective.iter() {
+        terms.push((solver.node(id)?, coefficient.checked_mul(sign).ok_or_else(overflow)?));
+    }
+
+    // A negative coefficient is paid up front and given back if the node is
+    // 1, since c * x is c + -c * (1 - x)
+    let mut bound: i64 = 0;
+    let mut softs: Vec<Soft> = Vec::new();
+    for (node, coefficient) in terms.iter() {
+        match solver.constant(*node) {
+            Some(value) => {
+                bound = coefficient.checked_mul(value).and_then(|paid| bound.checked_add(paid)).ok_or_else(overflow)?;
+            },
+            None if *coefficient > 0 => softs.push(Soft { node: *node, value: false, weight: *coefficient, counter: None }),
+            None if *coefficient < 0 => {
+                bound = bound.checked_add(*coefficient).ok_or_else(overflow)?;
+                let weight = coefficient.checked_neg().ok_or_else(overflow)?;
+                softs.push(Soft { node: *node, value: true, weight, counter: None });
+            },
+            None => {},
+        }
+    }
+    // Every value and bound of the search lies between the bound so far and
+    // it plus the weights of all softs, so if both ends fit, and can be
+    // negated back when maximizing, nothing after this overflows
+    softs.iter()
+        .try_fold(bound, |worst, soft| worst.checked_add(soft.weight))
+        .and(bound.checked_neg())
+        .ok_or_else(overflow)?;
+    solver.add_nodes(&terms.iter().map(|(node, _)| *node).collect::<Vec<u32>>());
+
+    let mut best: Option<(i64, BTreeMap<String, i64>)> = None;
+    let mut threshold = softs.iter().map(|soft| soft.weight).max().unwrap_or(0);
+    let status = loop {
+        if best.as_ref().is_some_and(|(value, _)| *value == bound) {
+            break OptimizeStatus::Optimal;
+        }
+        let assumptions: Vec<(u32, bool)> = softs.iter()
+            .filter(|soft| soft.weight >= threshold)
+            .map(|soft| (soft.node, soft.value))
+            .collect();
+        let result = solver.solve(&assumptions, deadline);
+        match result {
+            Status::Sat => {
+                let value: i64 = terms.iter()
+                    .map(|(node, coefficient)| coefficient * solver.value(*node).unwrap_or(0))
+                    .sum();
+                if best.as_ref().is_none_or(|(best, _)| value < *best) {
+                    best = Some((value, free_leaves(&solver)));
+                }
+                // With every soft assumed, the assignment only pays what the
+                // cores proved must be paid
+                if assumptions.len() == softs.len() {
+                    bound = value;
+                    break OptimizeStatus::Optimal;
+                }
+                threshold = softs.iter()
+                    .map(|soft| soft.weight)
+                    .filter(|weight| *weight < threshold)
+                    .max()
+                    .unwrap_or(0)
+                    .min(threshold / 2)
+                    .max(1);
+            },
+            Status::Unsat if solver.core().is_empty() => break OptimizeStatus::Infeasible,
+            Status::Unsat => {
+                let core: HashSet<(u32, bool)> = solver.core().iter().copied().collect();
+                // The softs of the core go last, so that the next search
+                // runs into the softs that were not part of a core yet
+                // before deciding these again
+                let (mut cored, rest): (Vec<Soft>, Vec<Soft>) = softs.into_iter()
+                    .partition(|soft| core.contains(&(soft.node, soft.value)));
+                softs = rest;
+                let weight = cored.iter().map(|soft| soft.weight).min().unwrap_or(0);
+                bound += weight;
+
+                let mut added: Vec<Soft> = Vec::new();
+                for soft in cored.iter_mut() {
+                    soft.weight -= weight;
+                    // A counter in the core allows one more of its own core
+                    if let Some((counted, free)) = &soft.counter {
+                        if free + 1 < counted.len() {
+                            let node = relax(&mut solver, counted, free + 1)?;
+                            added.push(Soft { node, value: false, weight, counter: Some((counted.clone(), free + 1)) });
+                        }
+                    }
+                }
+                if cored.len() > 1 {
+                    let counted: Vec<(u32, bool)> = cored.iter().map(|soft| (soft.node, soft.value)).collect();
+                    let node = relax(&mut solver, &counted, 1)?;
+                    added.push(Soft { node, value: false, weight, counter: Some((counted, 1)) });
+                }
+                softs.extend(cored.into_iter().filter(|soft| soft.weight > 0));
+                softs.extend(added);
+            },
+            Status::Unknown if best.is_some() => break OptimizeStatus::Feasible,
+            Status::Unknown => break OptimizeStatus::Unknown,
+        }
+    };
+
+    let (value, model) = match best {
+        Some((value, model)) => (Some(sign * value), model),
+        None => (None, BTreeMap::new()),
+    };
+    Ok(Optimum { status, value, bound: sign * bound, model, stats: solver.stats() })
+}
@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::dedup::dedup;
+use crate::error::LbtError;
+use crate::extract::extract;
+use crate::graph::{relations, topological_order};
+use crate::propagate::propagate_in_place;
+use crate::puan_core::{bic_or_bound, LinearBoundedTree};
+use crate::simplify::{simplify, SimplifyOptions};
+
+// The id of every node that was merged into another node, mapped to the id
+// of that node
+pub type Aliases = BTreeMap<String, String>;
+
+// A transformation of a tree, run as one step of a pipeline. Passes take and
+// return the tree by value, so that passes working in place need no copy.
+pub trait Pass: Send + Sync {
+    // The name of the pass, as used in pipelines and statistics
+    fn name(&self) -> &str;
+
+    // Runs the pass on a tree
+    //
+    // # Arguments
+    //
+    // * `tree` - The tree to transform
+    // * `aliases` - The nodes that earlier passes merged into other nodes.
+    //   Passes that merge nodes add to it, so that results can be reported
+    //   under the original ids.
+    //
+    // # Returns
+    //
+    // The transformed tree, or an error if the pass cannot be run on the tree
+    fn run(&self, tree: LinearBoundedTree, aliases: &mut Aliases) -> Result<LinearBoundedTree, LbtError>;
+}
+
+// Checks that every node has a part, that every relation is to a node in the
+// tree and that the relations do not form a cycle. The tree is not changed.
+#[derive(Debug, Clone, Default)]
+pub struct Validate;
+
+impl Pass for Validate {
+    fn name(&self) -> &str {
+        "validate"
+    }
+
+    fn run(&self, tree: LinearBoundedTree, _: &mut Aliases) -> Result<LinearBoundedTree, LbtError> {
+        for (id, node) in tree.nodes.iter() {
+            for relation in relations(id, node)? {
+                if !tree.nodes.contains_key(&relation.id) {
+                    return Err(LbtError::UnknownNode(relation.id.to_string()));
+                }
+            }
+        }
+        topological_order(&tree)?;
+        Ok(tree)
+    }
+}
+
+// Simplifies the tree, see `simplify`. If the tree already uses the bias id,
+// a number is appended to it until it does not, so that the bias node is
+// always a node of its own that can be told apart from the nodes of the tree.
+#[derive(Debug, Clone, Default)]
+pub struct Simplify {
+    pub options: SimplifyOptions,
+}
+
+impl Pass for Simplify {
+    fn name(&self) -> &str {
+        "simplify"
+    }
+
+    fn run(&self, tree: LinearBoundedTree, _: &mut Aliases) -> Result<LinearBoundedTree, LbtError> {
+        let mut options = self.options.clone();
+        let mut suffix = 0;
+        while uses(&tree, &options.bias_id) {
+            suffix += 1;
+            options.bias_id = format!("{}{}", self.options.bias_id, suffix);
+        }
+        simplify(&tree, &options)
+    }
+}
+
+// Merges identical nodes, see `dedup`. The merged nodes are not part of the
+// resulting tree, but are added to the aliases.
+#[derive(Debug, Clone, Default)]
+pub struct Dedup;
+
+impl Pass for Dedup {
+    fn name(&self) -> &str {
+        "dedup"
+    }
+
+    fn run(&self, tree: LinearBoundedTree, aliases: &mut Aliases) -> Result<LinearBoundedTree, LbtError> {
+        let dedup = dedup(&tree)?;
+        // Nodes merged by earlier passes now refer to the node that their
+        // node was merged into
+        for id in aliases.values_mut() {
+            if let Some(kept) = dedup.aliases.get(id) {
+                *id = kept.to_string();
+            }
+        }
+        aliases.extend(dedup.aliases);
+        Ok(dedup.tree)
+    }
+}
+
+// Keeps only the given roots and their descendants, see `extract`
+#[derive(Debug, Clone, Default)]
+pub struct Extract {
+    pub roots: Vec<String>,
+}
+
+impl Pass for Extract {
+    fn name(&self) -> &str {
+        "extract"
+    }
+
+    fn run(&self, tree: LinearBoundedTree, _: &mut Aliases) -> Result<LinearBoundedTree, LbtError> {
+        extract(&tree, &self.roots)
+    }
+}
+
+// Propagates the tree in place, see `propagate_in_place`
+#[derive(Debug, Clone, Default)]
+pub struct Propagate;
+
+impl Pass for Propagate {
+    fn name(&self) -> &str {
+        "propagate"
+    }
+
+    fn run(&self, mut tree: LinearBoundedTree, _: &mut Aliases) -> Result<LinearBoundedTree, LbtError> {
+        propagate_in_place(&mut tree)?;
+        Ok(tree)
+    }
+}
+
+// What a single pass did to the tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassStats {
+    pub name: String,
+    pub duration: Duration,
+    pub nodes_before: usize,
+    pub nodes_after: usize,
+    pub relations_before: usize,
+    pub relations_after: usize,
+}
+
+// Runs a sequence of passes over a tree, in the order they were added
+#[derive(Default)]
+pub struct PassManager {
+    passes: Vec<Box<dyn Pass>>,
+}
+
+// Pipelines that can be referred to by a single name, with the passes they
+// are made of
+pub const PIPELINES: &[(&str, &str)] = &[
+    ("none", ""),
+    ("compact", "simplify,dedup"),
+    ("strict", "validate,simplify,dedup"),
+];
+
+impl PassManager {
+    // Returns a pass manager without any passes
+    pub fn new() -> Self {
+        PassManager::default()
+    }
+
+    // Returns the pass manager with a pass added to the end of the pipeline
+    pub fn with<P: Pass + 'static>(mut self, pass: P) -> Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    // Builds a pipeline from its name, which is either one of `PIPELINES` or
+    // a comma separated list of the passes `validate`, `simplify`, `dedup`
+    // and `propagate`. Passes that need arguments, like `Extract`, can only be
+    // added with `with`.
+    //
+    // # Arguments
+    //
+    // * `name` - The name of the pipeline, e.g. `compact` or `validate,dedup`
+    //
+    // # Returns
+    //
+    // The pass manager, or an error if a pass is not known
+    pub fn parse(name: &str) -> Result<Self, LbtError> {
+        let passes = PIPELINES.iter()
+            .find(|(pipeline, _)| *pipeline == name)
+            .map(|(_, passes)| *passes)
+            .unwrap_or(name);
+
+        let mut manager = PassManager::new();
+        for pass in passes.split(',').map(|pass| pass.trim()).filter(|pass| !pass.is_empty()) {
+            manager = match pass {
+                "validate" => manager.with(Validate),
+                "simplify" => manager.with(Simplify::default()),
+                "dedup" => manager.with(Dedup),
+                "propagate" => manager.with(Propagate),
+                _ => return Err(LbtError::Malformed(format!("unknown pass {}", pass))),
+            };
+        }
+        Ok(manager)
+    }
+
+    // Returns the names of the passes, in the order they are run
+    pub fn names(&self) -> Vec<&str> {
+        self.passes.iter().map(|pass| pass.name()).collect()
+    }
+
+    // Returns true if the pipeline has no passes
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    // Runs all passes over a tree.
+    //
+    // # Arguments
+    //
+    // * `tree` - The tree to transform
+    //
+    // # Returns
+    //
+    // The transformed tree, the nodes that were merged into nodes of the
+    // transformed tree, see `Dedup::expand`, and the statistics of every
+    // pass, or the error of the first pass that failed
+    pub fn run(&self, mut tree: LinearBoundedTree) -> Result<(LinearBoundedTree, Aliases, Vec<PassStats>), LbtError> {
+        let mut stats: Vec<PassStats> = Vec::with_capacity(self.passes.len());
+        let mut aliases = Aliases::new();
+        for pass in self.passes.iter() {
+            let nodes_before = tree.nodes.len();
+            let relations_before = relation_count(&tree);
+            let start = Instant::now();
+            tree = pass.run(tree, &mut aliases)?;
+            stats.push(PassStats {
+                name: pass.name().to_string(),
+                duration: start.elapsed(),
+                nodes_before,
+                nodes_after: tree.nodes.len(),
+                relations_before,
+                relations_after: relation_count(&tree),
+            });
+        }
+        Ok((tree, aliases, stats))
+    }
+}
+
+// Returns true if a tree has a node with an id, or a BIC relating to it
+fn uses(tree: &LinearBoundedTree, id: &str) -> bool {
+    tree.nodes.contains_key(id) || tree.nodes.values().any(|node| match &node.part {
+        Some(bic_or_bound::Part::Bic(bic)) => bic.relations.iter().any(|relation| relation.id == id),
+        _ => false,
+    })
+}
+
+// Returns the number of relations of all BICs in a tree
+fn relation_count(tree: &LinearBoundedTree) -> usize {
+    tree.nodes.values()
+        .map(|node| match &node.part {
+            Some(bic_or_bound::Part::Bic(bic)) => bic.relations.len(),
+            _ => 0,
+        })
+        .sum()
+}
+
+// Returns the statistics of a pipeline as a JSON value, with durations in
+// microseconds
+pub fn stats_json(stats: &[PassStats]) -> Value {
+    Value::Array(
+        stats.iter()
+            .map(|stats| json!({
+                "pass": stats.name,
+                "micros": stats.duration.as_micros() as u64,
+                "nodes": [stats.nodes_before, stats.nodes_after],
+                "relations": [stats.relations_before, stats.relations_after],
+            }))
+            .collect()
+    )
+}
@@ -0,0 +1,244 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::error::LbtError;
+use crate::puan_core::{
+    bic_or_bound, BicOrBound, BinaryInequalityConstraint, Bound, CoefRelation, LinearBoundedTree,
+};
+
+// A sparse integer matrix in compressed sparse row (CSR) format, laid out the
+// same way as scipy's `csr_matrix((data, indices, indptr))`. The entries of
+// row `r` are found at `indptr[r]..indptr[r + 1]` in `indices` and `data`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CsrMatrix {
+    pub nrows: usize,
+    pub ncols: usize,
+    pub indptr: Vec<usize>,
+    pub indices: Vec<usize>,
+    pub data: Vec<i64>,
+}
+
+impl CsrMatrix {
+    // Returns the column indices and values of a row
+    pub fn row(&self, row: usize) -> (&[usize], &[i64]) {
+        let range = self.indptr[row]..self.indptr[row + 1];
+        (&self.indices[range.clone()], &self.data[range])
+    }
+}
+
+// A LinearBoundedTree in the `ge_polyhedron` form used by Puan, i.e. as a
+// system `A x >= b` over the integer variables in `variables`.
+//
+// Every row defines one BIC node: the variable `rows[r]` equals 1 if and only
+// if `A[r] x >= b[r]`. All other variables are leaves, which take any value
+// within their bound. BIC variables always have the bound [0, 1].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Polyhedron {
+    pub a: CsrMatrix,
+    pub b: Vec<i64>,
+    // Column index to node id
+    pub variables: Vec<String>,
+    // Column index to the bound of the variable
+    pub bounds: Vec<Bound>,
+    // Row index to the id of the BIC node the row defines
+    pub rows: Vec<String>,
+}
+
+impl Polyhedron {
+    // Returns the column index of every variable
+    pub fn variable_index(&self) -> HashMap<&str, usize> {
+        self.variables.iter()
+            .enumerate()
+            .map(|(index, id)| (id.as_str(), index))
+            .collect()
+    }
+
+    // Returns the polyhedron as a dense matrix, with the bias `b` as the first
+    // column followed by one column per variable. This is the layout of
+    // Puan's `ge_polyhedron` numpy arrays.
+    pub fn to_dense(&self) -> Vec<Vec<i64>> {
+        (0..self.a.nrows)
+            .map(|row| {
+                let mut dense = vec![0; self.a.ncols + 1];
+                dense[0] = self.b[row];
+                let (indices, data) = self.a.row(row);
+                for (column, value) in indices.iter().zip(data.iter()) {
+                    dense[column + 1] += value;
+                }
+                dense
+            })
+            .collect()
+    }
+}
+
+// Converts a LinearBoundedTree into its polyhedron form.
+//
+// Variables are ordered by id and rows follow the order of the variables, so
+// converting the same tree twice gives the same polyhedron. Relations from the
+// same BIC to the same child are summed into a single matrix entry, and zero
+// entries are left out, as is usual for CSR matrices. Since BICs compare
+// their sum against 0, all of `b` is 0.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to convert
+//
+// # Returns
+//
+// The polyhedron, or an error if a node has no part, if a BIC refers to a
+// node that is not part of the tree, or if the relations of a BIC to the same
+// child sum to more than fits in 64 bits
+pub fn to_polyhedron(tree: &LinearBoundedTree) -> Result<Polyhedron, LbtError> {
+    let mut variables: Vec<String> = tree.nodes.keys().cloned().collect();
+    variables.sort_unstable();
+    let index: HashMap<&str, usize> = variables.iter()
+        .enumerate()
+        .map(|(index, id)| (id.as_str(), index))
+        .collect();
+
+    let mut polyhedron = Polyhedron {
+        a: CsrMatrix {
+            ncols: variables.len(),
+            indptr: vec![0],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    for id in variables.iter() {
+        match &tree.nodes[id].part {
+            Some(bic_or_bound::Part::Bound(bound)) => {
+                polyhedron.bounds.push(bound.clone());
+            },
+            Some(bic_or_bound::Part::Bic(bic)) => {
+                polyhedron.bounds.push(Bound { lower: 0, upper: 1 });
+
+                // A BTreeMap keeps the column indices of the row sorted
+                let mut row: BTreeMap<usize, i64> = BTreeMap::new();
+                for relation in bic.relations.iter() {
+                    let column = index.get(relation.id.as_str())
+                        .ok_or_else(|| LbtError::UnknownNode(relation.id.to_string()))?;
+                    let entry = row.entry(*column).or_insert(0);
+                    *entry = entry.checked_add(relation.coefficient).ok_or_else(|| LbtError::Malformed(
+                        format!("the relations of {} to {} do not sum to a 64 bit coefficient", id, relation.id),
+                    ))?;
+                }
+                for (column, value) in row.into_iter().filter(|(_, value)| *value != 0) {
+                    polyhedron.a.indices.push(column);
+                    polyhedron.a.data.push(value);
+                }
+                polyhedron.a.indptr.push(polyhedron.a.indices.len());
+                polyhedron.a.nrows += 1;
+                polyhedron.b.push(0);
+                polyhedron.rows.push(id.to_string());
+            },
+            None => return Err(LbtError::MissingPart(id.to_string())),
+        }
+    }
+
+    polyhedron.variables = variables;
+    Ok(polyhedron)
+}
+
+// Converts a polyhedron back into a LinearBoundedTree.
+//
+// BICs can only compare their sum against 0, so a row with a non-zero bias
+// `b` is given an extra relation with coefficient `-b` to a constant node
+// with the bound [1, 1]. That node is added under `bias_id`, and only if at
+// least one row needs it.
+//
+// # Arguments
+//
+// * `polyhedron` - The polyhedron to convert
+// * `bias_id` - The id of the constant node used for non-zero biases
+//
+// # Returns
+//
+// The LinearBoundedTree, or an error if the dimensions of the polyhedron do
+// not match, or if `bias_id` is already used by a variable
+pub fn from_polyhedron(polyhedron: &Polyhedron, bias_id: &str) -> Result<LinearBoundedTree, LbtError> {
+    validate(polyhedron)?;
+
+    let mut tree = LinearBoundedTree::default();
+    for (id, bound) in polyhedron.variables.iter().zip(polyhedron.bounds.iter()) {
+        tree.nodes.insert(id.to_string(), BicOrBound {
+            part: Some(bic_or_bound::Part::Bound(bound.clone())),
+        });
+    }
+
+    let mut needs_bias = false;
+    for (row, id) in polyhedron.rows.iter().enumerate() {
+        let (indices, data) = polyhedron.a.row(row);
+        let mut relations: Vec<CoefRelation> = indices.iter()
+            .zip(data.iter())
+            .map(|(column, value)| CoefRelation {
+                id: polyhedron.variables[*column].to_string(),
+                coefficient: *value,
+            })
+            .collect();
+
+        if polyhedron.b[row] != 0 {
+            needs_bias = true;
+            relations.push(CoefRelation {
+                id: bias_id.to_string(),
+                coefficient: polyhedron.b[row].checked_neg()
+                    .ok_or_else(|| LbtError::Malformed(format!("bias of row {} cannot be negated", row)))?,
+            });
+        }
+
+        tree.nodes.insert(id.to_string(), BicOrBound {
+            part: Some(bic_or_bound::Part::Bic(BinaryInequalityConstraint { relations })),
+        });
+    }
+
+    if needs_bias {
+        if tree.nodes.contains_key(bias_id) {
+            return Err(LbtError::InvalidId(bias_id.to_string()));
+        }
+        tree.nodes.insert(bias_id.to_string(), BicOrBound {
+            part: Some(bic_or_bound::Part::Bound(Bound { lower: 1, upper: 1 })),
+        });
+    }
+
+    Ok(tree)
+}
+
+// Checks that all parts of a polyhedron agree on its dimensions
+fn validate(polyhedron: &Polyhedron) -> Result<(), LbtError> {
+    let a = &polyhedron.a;
+    let malformed = |reason: &str| Err(LbtError::Malformed(reason.to_string()));
+
+    if a.indptr.len() != a.nrows + 1 || a.indptr[0] != 0 {
+        return malformed("indptr must start at 0 and have one entry more than there are rows");
+    }
+    if a.indptr.windows(2).any(|pair| pair[0] > pair[1]) || a.indptr[a.nrows] != a.indices.len() {
+        return malformed("indptr must be non-decreasing and end at the number of entries");
+    }
+    if a.indices.len() != a.data.len() {
+        return malformed("indices and data must have the same length");
+    }
+    if a.indices.iter().any(|column| *column >= a.ncols) {
+        return malformed("column index out of range");
+    }
+    if polyhedron.b.len() != a.nrows || polyhedron.rows.len() != a.nrows {
+        return malformed("b and rows must have one entry per row");
+    }
+    if polyhedron.variables.len() != a.ncols || polyhedron.bounds.len() != a.ncols {
+        return malformed("variables and bounds must have one entry per column");
+    }
+
+    let index = polyhedron.variable_index();
+    if index.len() != a.ncols {
+        return malformed("variables must be unique");
+    }
+    let mut defined = vec![false; a.ncols];
+    for id in polyhedron.rows.iter() {
+        let column = *index.get(id.as_str())
+            .ok_or_else(|| LbtError::UnknownNode(id.to_string()))?;
+        if defined[column] {
+            return malformed("a variable is defined by more than one row");
+        }
+        defined[column] = true;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use crate::compiled::{self, Values};
+use crate::error::LbtError;
+use crate::puan_core::{Bound, LinearBoundedTree};
+
+// The first bytes of every serialised program, followed by the format version
+const MAGIC: &[u8; 4] = b"LBTP";
+const VERSION: u32 = 1;
+
+// A single instruction of a program. Instructions work on a memory holding
+// the lower and upper bound of every slot, and on an accumulator holding the
+// bound of the sum of the BIC being evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    // Sets the bound of a slot
+    Load { slot: u32, lower: i64, upper: i64 },
+    // Adds the bound of a slot times the coefficient to the accumulator. The
+    // bound is flipped if the coefficient is negative. The accumulator holds
+    // 128 bits, so every product is exact and sums only saturate once they
+    // pass 2^127, which takes several products of values near the ends of i64.
+    MulAcc { slot: u32, coefficient: i64 },
+    // Replaces the accumulator by its BIC bound, i.e. 1 for each end that is
+    // at least 0 and 0 otherwise
+    Threshold,
+    // Writes the accumulator to a slot, clamped to i64, and clears it
+    Store { slot: u32 },
+}
+
+// A LinearBoundedTree compiled into a flat stream of instructions. Each node
+// of the tree has one slot. The program starts with a `Load` for every Bound,
+// followed by the code of every BIC that can be propagated, in topological
+// order: a `MulAcc` per relation, a `Threshold` and a `Store`. BICs that
+// cannot be propagated have no code and keep the bound [0, 1].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+    // The id of each slot
+    pub ids: Vec<String>,
+    pub instructions: Vec<Instruction>,
+    // The position of the first instruction that is not a `Load`
+    pub entry: usize,
+}
+
+impl Program {
+    // Returns the slot of every id. The map is built on every call.
+    pub fn index(&self) -> HashMap<&str, u32> {
+        self.ids.iter().enumerate().map(|(slot, id)| (id.as_str(), slot as u32)).collect()
+    }
+
+    // Returns the memory after all `Load` instructions have run, with every
+    // other slot set to [0, 1]. Bounds may be changed before calling `execute`.
+    pub fn memory(&self) -> Values {
+        let mut memory = Values { lower: vec![0; self.ids.len()], upper: vec![1; self.ids.len()] };
+        for instruction in self.instructions[..self.entry].iter() {
+            if let Instruction::Load { slot, lower, upper } = instruction {
+                memory.lower[*slot as usize] = *lower;
+                memory.upper[*slot as usize] = *upper;
+            }
+        }
+        memory
+    }
+
+    // Runs every instruction after the `Load` instructions.
+    //
+    // # Arguments
+    //
+    // * `memory` - The memory to run on, e.g. from `memory`
+    pub fn execute(&self, memory: &mut Values) {
+        let mut sum_lower: i128 = 0;
+        let mut sum_upper: i128 = 0;
+        for instruction in self.instructions[self.entry..].iter() {
+            match *instruction {
+                Instruction::Load { slot, lower, upper } => {
+                    memory.lower[slot as usize] = lower;
+                    memory.upper[slot as usize] = upper;
+                },
+                Instruction::MulAcc { slot, coefficient } => {
+                    let slot = slot as usize;
+                    let (lower, upper) = if coefficient < 0 {
+                        (memory.upper[slot], memory.lower[slot])
+                    } else {
+                        (memory.lower[slot], memory.upper[slot])
+                    };
+                    sum_lower = sum_lower.saturating_add(lower as i128 * coefficient as i128);
+                    sum_upper = sum_upper.saturating_add(upper as i128 * coefficient as i128);
+                },
+                Instruction::Threshold => {
+                    sum_lower = (sum_lower >= 0) as i128;
+                    sum_upper = (sum_upper >= 0) as i128;
+                },
+                Instruction::Store { slot } => {
+                    memory.lower[slot as usize] = sum_lower.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+                    memory.upper[slot as usize] = sum_upper.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+                    sum_lower = 0;
+                    sum_upper = 0;
+                },
+            }
+        }
+    }
+
+    // Runs the whole program and returns the resulting memory
+    pub fn run(&self) -> Values {
+        let mut memory = self.memory();
+        self.execute(&mut memory);
+        memory
+    }
+
+    // Returns the bound of every slot that is loaded or stored to, by id,
+    // i.e. of every Bound and every BIC that is propagated
+    pub fn bounds(&self, memory: &Values) -> HashMap<&str, Bound> {
+        self.instructions.iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Load { slot, .. } | Instruction::Store { slot } => Some(*slot as usize),
+                _ => None,
+            })
+            .map(|slot| (self.ids[slot].as_str(), Bound { lower: memory.lower[slot], upper: memory.upper[slot] }))
+            .collect()
+    }
+
+    // Serialises the program. All numbers are stored little endian, after a
+    // magic and the format version.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.ids.len() as u32).to_le_bytes());
+        for id in self.ids.iter() {
+            bytes.extend_from_slice(&(id.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(id.as_bytes());
+        }
+        bytes.extend_from_slice(&(self.instructions.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.entry as u32).to_le_bytes());
+        for instruction in self.instructions.iter() {
+            match instruction {
+                Instruction::Load { slot, lower, upper } => {
+                    bytes.push(0);
+                    bytes.extend_from_slice(&slot.to_le_bytes());
+                    bytes.extend_from_slice(&lower.to_le_bytes());
+                    bytes.extend_from_slice(&upper.to_le_bytes());
+                },
+                Instruction::MulAcc { slot, coefficient } => {
+                    bytes.push(1);
+                    bytes.extend_from_slice(&slot.to_le_bytes());
+                    bytes.extend_from_slice(&coefficient.to_le_bytes());
+                },
+                Instruction::Threshold => bytes.push(2),
+                Instruction::Store { slot } => {
+                    bytes.push(3);
+                    bytes.extend_from_slice(&slot.to_le_bytes());
+                },
+            }
+        }
+        bytes
+    }
+
+    // Reads a program written by `to_bytes`. Every slot is checked to be in
+    // range, so a program read without error can be run without panicking.
+    //
+    // # Arguments
+    //
+    // * `bytes` - The serialised program
+    //
+    // # Returns
+    //
+    // The program, or an error if the bytes are not a valid program
+    pub fn from_bytes(bytes: &[u8]) -> Result<Program, LbtError> {
+        let mut reader = Reader { bytes, position: 0 };
+        if reader.take(4)? != MAGIC {
+            return Err(LbtError::Malformed("not a program".to_string()));
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(LbtError::Malformed(format!("unsupported program version {}", version)));
+        }
+
+        let slots = reader.u32()?;
+        let mut ids: Vec<String> = Vec::new();
+        for _ in 0..slots {
+            let length = reader.u32()? as usize;
+            let id = std::str::from_utf8(reader.take(length)?)
+                .map_err(|_| LbtError::Malformed("id is not valid utf-8".to_string()))?;
+            ids.push(id.to_string());
+        }
+
+        let count = reader.u32()? as usize;
+        let entry = reader.u32()? as usize;
+        if entry > count {
+            return Err(LbtError::Malformed(format!("entry {} is past the last instruction", entry)));
+        }
+        let slot = |slot: u32| if slot < slots {
+            Ok(slot)
+        } else {
+            Err(LbtError::Malformed(format!("slot {} is out of range", slot)))
+        };
+        let mut instructions: Vec<Instruction> = Vec::new();
+        for _ in 0..count {
+            let instruction = match reader.take(1)?[0] {
+                0 => Instruction::Load { slot: slot(reader.u32()?)?, lower: reader.i64()?, upper: reader.i64()? },
+                1 => Instruction::MulAcc { slot: slot(reader.u32()?)?, coefficient: reader.i64()? },
+                2 => Instruction::Threshold,
+                3 => Instruction::Store { slot: slot(reader.u32()?)? },
+                opcode => return Err(LbtError::Malformed(format!("unknown opcode {}", opcode))),
+            };
+            instructions.push(instruction);
+        }
+        if reader.position != bytes.len() {
+            return Err(LbtError::Malformed("trailing bytes after program".to_string()));
+        }
+
+        Ok(Program { ids, instructions, entry })
+    }
+}
+
+// Reads little endian numbers from the front of a byte slice
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], LbtError> {
+        let end = self.position.checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| LbtError::Malformed("program is truncated".to_string()))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, LbtError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i64(&mut self) -> Result<i64, LbtError> {
+        Ok(i64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+// Compiles a LinearBoundedTree into a program.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to compile
+//
+// # Returns
+//
+// The program, or an error if a node has no part
+pub fn compile(tree: &LinearBoundedTree) -> Result<Program, LbtError> {
+    let compiled = compiled::compile(tree)?;
+
+    let mut program = Program {
+        ids: (0..compiled.len() as u32).map(|node| compiled.id(node).to_string()).collect(),
+        instructions: Vec::with_capacity(compiled.len() + compiled.children.len() + compiled.order.len()),
+        entry: 0,
+    };
+    for node in 0..compiled.len() {
+        if !compiled.is_bic[node] {
+            program.instructions.push(Instruction::Load {
+                slot: node as u32,
+                lower: compiled.lower[node],
+                upper: compiled.upper[node],
+            });
+        }
+    }
+    program.entry = program.instructions.len();
+
+    for node in compiled.order.iter() {
+        let (children, coefficients) = compiled.relations(*node);
+        for (child, coefficient) in children.iter().zip(coefficients.iter()) {
+            program.instructions.push(Instruction::MulAcc { slot: *child, coefficient: *coefficient });
+        }
+        program.instructions.push(Instruction::Threshold);
+        program.instructions.push(Instruction::Store { slot: *node });
+    }
+
+    Ok(program)
+}
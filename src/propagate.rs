@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use crate::compiled::{compile, ParallelOptions};
+use crate::error::LbtError;
+use crate::puan_core::{Bound, LinearBoundedTree};
+
+// Propagates a Linear Bounded Tree (LBT) by propagating all Binary Inequality Constraints (BICs)
+// in the tree. The tree is first compiled into flat arrays (see `compiled`), in which the BICs are
+// ordered such that every BIC comes after all of its children. The BICs are then propagated in
+// that order, so each BIC is visited exactly once and all of its children are already propagated
+// when it is. If a BIC has a child that is not in the tree, we cannot ever propagate the BIC, so
+// it is left as is, together with every BIC depending on it.
+//
+// BICs are grouped into levels, where the BICs of one level only depend on BICs of earlier
+// levels. Large trees, which are often made up of many independent parts, have wide levels, and
+// the BICs of each level are then propagated on a thread pool. Trees smaller than the threshold
+// in `ParallelOptions::default()` are propagated on the calling thread only. Either way, the
+// result is the same.
+//
+// The algorithm is implemented as a function that takes a LinearBoundedTree and returns a new
+// LinearBoundedTree. This is done to make it easier to test the algorithm, since we can just
+// create a LinearBoundedTree and pass it to the function, and then check the result. Callers
+// that own the tree and do not need the original should use `propagate_in_place` instead, which
+// does not build a second tree.
+//
+// # Arguments
+//
+// * `tree` - A LinearBoundedTree to propagate
+//
+// # Returns
+//
+// A new LinearBoundedTree with all BICs propagated, or an error if a node has no part
+pub fn propagate(tree: &LinearBoundedTree) -> Result<LinearBoundedTree, LbtError> {
+    let compiled = compile(tree)?;
+    let mut values = compiled.initial_values();
+    compiled.evaluate_parallel(&mut values, &ParallelOptions::default());
+
+    Ok(compiled.propagated(&values, tree))
+}
+
+// Propagates a Linear Bounded Tree (LBT) in place, replacing every BIC that can be propagated by
+// its bound. BICs that cannot be propagated are left untouched, just like in `propagate`. Only
+// the compiled arrays are allocated, the nodes of the tree are never copied.
+//
+// # Arguments
+//
+// * `tree` - A LinearBoundedTree to propagate
+//
+// # Returns
+//
+// Nothing, or an error if a node has no part. The tree is not changed on error.
+pub fn propagate_in_place(tree: &mut LinearBoundedTree) -> Result<(), LbtError> {
+    let compiled = compile(tree)?;
+    let mut values = compiled.initial_values();
+    compiled.evaluate_parallel(&mut values, &ParallelOptions::default());
+
+    compiled.write_back(&values, tree);
+    Ok(())
+}
+
+// Propagates a Linear Bounded Tree (LBT) without changing or copying it, and returns only the
+// bounds computed for the BICs. Bound nodes and BICs that cannot be propagated are not part of
+// the result.
+//
+// # Arguments
+//
+// * `tree` - A LinearBoundedTree to propagate
+//
+// # Returns
+//
+// The bound of every propagated BIC by id, or an error if a node has no part
+pub fn propagate_bounds(tree: &LinearBoundedTree) -> Result<HashMap<&str, Bound>, LbtError> {
+    let compiled = compile(tree)?;
+    let mut values = compiled.initial_values();
+    compiled.evaluate_parallel(&mut values, &ParallelOptions::default());
+
+    // The nodes are compiled in the order they are stored in the tree, so the
+    // ids can be borrowed from the tree rather than from the compiled tree
+    let ids: Vec<&str> = tree.nodes.keys().map(|id| id.as_str()).collect();
+    Ok(
+        compiled.order.iter()
+            .map(|node| {
+                let node = *node as usize;
+                (ids[node], Bound { lower: values.lower[node], upper: values.upper[node] })
+            })
+            .collect()
+    )
+}
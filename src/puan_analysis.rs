@@ -0,0 +1,1444 @@
+/// Request for extracting a part of a Linear Bounded Tree
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExtractRequest {
+    #[prost(message, optional, tag = "1")]
+    pub tree: ::core::option::Option<super::puan_core::LinearBoundedTree>,
+    #[prost(string, repeated, tag = "2")]
+    pub ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(enumeration = "extract_request::Direction", tag = "3")]
+    pub direction: i32,
+    /// Whether to propagate the extracted tree before returning it
+    #[prost(bool, tag = "4")]
+    pub propagate: bool,
+}
+/// Nested message and enum types in `ExtractRequest`.
+pub mod extract_request {
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum Direction {
+        /// The given nodes and everything they depend on
+        Descendants = 0,
+        /// Every node that depends on any of the given nodes
+        Ancestors = 1,
+    }
+    impl Direction {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Direction::Descendants => "DESCENDANTS",
+                Direction::Ancestors => "ANCESTORS",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "DESCENDANTS" => Some(Self::Descendants),
+                "ANCESTORS" => Some(Self::Ancestors),
+                _ => None,
+            }
+        }
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExtractResponse {
+    /// The extracted tree, containing every node needed to propagate the
+    /// matched nodes
+    #[prost(message, optional, tag = "1")]
+    pub tree: ::core::option::Option<super::puan_core::LinearBoundedTree>,
+    /// The ids matched by the request, sorted
+    #[prost(string, repeated, tag = "2")]
+    pub ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Bounds replacing the bounds of the leaves with the same ids
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Assignment {
+    #[prost(map = "string, message", tag = "1")]
+    pub bounds: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        super::puan_core::Bound,
+    >,
+}
+/// Request for evaluating many leaf assignments against the same tree
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchRequest {
+    #[prost(message, optional, tag = "1")]
+    pub tree: ::core::option::Option<super::puan_core::LinearBoundedTree>,
+    /// The nodes to return the bounds of. If empty, every node that no other
+    /// node relates to is returned.
+    #[prost(string, repeated, tag = "2")]
+    pub roots: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(message, repeated, tag = "3")]
+    pub assignments: ::prost::alloc::vec::Vec<Assignment>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchResult {
+    /// The bound of every root after propagation. Roots that could not be
+    /// propagated are left out.
+    #[prost(map = "string, message", tag = "1")]
+    pub roots: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        super::puan_core::Bound,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BatchResponse {
+    /// The roots that were evaluated
+    #[prost(string, repeated, tag = "1")]
+    pub roots: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// One result per assignment, in the order of the request
+    #[prost(message, repeated, tag = "2")]
+    pub results: ::prost::alloc::vec::Vec<BatchResult>,
+}
+/// Request for finding values of the leaves that make a set of roots 1
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SolveRequest {
+    #[prost(message, optional, tag = "1")]
+    pub tree: ::core::option::Option<super::puan_core::LinearBoundedTree>,
+    /// The nodes that must be 1. If empty, every node that no other node
+    /// relates to must be 1.
+    #[prost(string, repeated, tag = "2")]
+    pub roots: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// How long to search in milliseconds, or no limit if 0
+    #[prost(uint64, tag = "3")]
+    pub time_limit_ms: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SolveStatistics {
+    #[prost(uint64, tag = "1")]
+    pub decisions: u64,
+    #[prost(uint64, tag = "2")]
+    pub conflicts: u64,
+    #[prost(uint64, tag = "3")]
+    pub propagations: u64,
+    /// Constraints learned from conflicts
+    #[prost(uint64, tag = "4")]
+    pub learned: u64,
+    #[prost(uint64, tag = "5")]
+    pub restarts: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SolveResponse {
+    #[prost(enumeration = "solve_response::Status", tag = "1")]
+    pub status: i32,
+    /// The value of every leaf that is not fixed, if the status is SAT
+    #[prost(map = "string, int64", tag = "2")]
+    pub model: ::std::collections::HashMap<::prost::alloc::string::String, i64>,
+    #[prost(message, optional, tag = "3")]
+    pub statistics: ::core::option::Option<SolveStatistics>,
+}
+/// Nested message and enum types in `SolveResponse`.
+pub mod solve_response {
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum Status {
+        /// The time limit was reached before the search finished
+        Unknown = 0,
+        /// Values making all roots 1 were found
+        Sat = 1,
+        /// No values make all roots 1
+        Unsat = 2,
+    }
+    impl Status {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Status::Unknown => "UNKNOWN",
+                Status::Sat => "SAT",
+                Status::Unsat => "UNSAT",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "UNKNOWN" => Some(Self::Unknown),
+                "SAT" => Some(Self::Sat),
+                "UNSAT" => Some(Self::Unsat),
+                _ => None,
+            }
+        }
+    }
+}
+/// Request for all values of the leaves that make a set of roots 1
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EnumerateRequest {
+    #[prost(message, optional, tag = "1")]
+    pub tree: ::core::option::Option<super::puan_core::LinearBoundedTree>,
+    /// The nodes that must be 1. If empty, every node that no other node
+    /// relates to must be 1.
+    #[prost(string, repeated, tag = "2")]
+    pub roots: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// The largest number of solutions to return, or no limit if 0
+    #[prost(uint64, tag = "3")]
+    pub limit: u64,
+    /// The leaves to return the values of, with solutions that only differ in
+    /// other leaves returned once. If empty, all leaves that are not fixed.
+    #[prost(string, repeated, tag = "4")]
+    pub projection: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// How long to search for all solutions in milliseconds, or no limit if 0
+    #[prost(uint64, tag = "5")]
+    pub time_limit_ms: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EnumerateResponse {
+    /// The value of every projected leaf
+    #[prost(map = "string, int64", tag = "1")]
+    pub model: ::std::collections::HashMap<::prost::alloc::string::String, i64>,
+}
+/// Request for the number of values of the leaves that make a set of roots 1
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CountRequest {
+    #[prost(message, optional, tag = "1")]
+    pub tree: ::core::option::Option<super::puan_core::LinearBoundedTree>,
+    /// The nodes that must be 1. If empty, every node that no other node
+    /// relates to must be 1.
+    #[prost(string, repeated, tag = "2")]
+    pub roots: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// The leaves to count the values of, with values that only differ in
+    /// other leaves counted once. If empty, all leaves that are not fixed.
+    #[prost(string, repeated, tag = "3")]
+    pub projection: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// How long to count in milliseconds, or no limit if 0
+    #[prost(uint64, tag = "4")]
+    pub time_limit_ms: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CountResponse {
+    /// The exact count in decimal, as it may not fit in 64 bits
+    #[prost(string, tag = "1")]
+    pub count: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WeightedCountRequest {
+    #[prost(message, optional, tag = "1")]
+    pub tree: ::core::option::Option<super::puan_core::LinearBoundedTree>,
+    /// The nodes that must be 1. If empty, every node that no other node
+    /// relates to must be 1.
+    #[prost(string, repeated, tag = "2")]
+    pub roots: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// The take rate of leaves, between 0 and 1. Leaves without a rate weigh
+    /// 1 both when 0 and when 1.
+    #[prost(map = "string, double", tag = "3")]
+    pub weights: ::std::collections::HashMap<::prost::alloc::string::String, f64>,
+    /// How long to count in milliseconds, or no limit if 0
+    #[prost(uint64, tag = "4")]
+    pub time_limit_ms: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WeightedCountResponse {
+    /// The weighted count, which is infinite if it does not fit in a double
+    #[prost(double, tag = "1")]
+    pub count: f64,
+    /// The natural logarithm of the weighted count
+    #[prost(double, tag = "2")]
+    pub log_count: f64,
+    /// The probability of each leaf that is not fixed, and of each BIC, being 1
+    /// in a valid assignment drawn by weight
+    #[prost(map = "string, double", tag = "3")]
+    pub marginals: ::std::collections::HashMap<::prost::alloc::string::String, f64>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OptimizeRequest {
+    #[prost(message, optional, tag = "1")]
+    pub tree: ::core::option::Option<super::puan_core::LinearBoundedTree>,
+    /// The nodes that must be 1. If empty, every node that no other node
+    /// relates to must be 1.
+    #[prost(string, repeated, tag = "2")]
+    pub roots: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// The coefficient of each node in the objective
+    #[prost(map = "string, int64", tag = "3")]
+    pub objective: ::std::collections::HashMap<::prost::alloc::string::String, i64>,
+    #[prost(enumeration = "optimize_request::Sense", tag = "4")]
+    pub sense: i32,
+    /// How long to search for the optimum, no limit if 0
+    #[prost(uint64, tag = "5")]
+    pub time_limit_ms: u64,
+}
+/// Nested message and enum types in `OptimizeRequest`.
+pub mod optimize_request {
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum Sense {
+        Minimize = 0,
+        Maximize = 1,
+    }
+    impl Sense {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Sense::Minimize => "MINIMIZE",
+                Sense::Maximize => "MAXIMIZE",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "MINIMIZE" => Some(Self::Minimize),
+                "MAXIMIZE" => Some(Self::Maximize),
+                _ => None,
+            }
+        }
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OptimizeResponse {
+    #[prost(enumeration = "optimize_response::Status", tag = "1")]
+    pub status: i32,
+    /// The objective of the best assignment found, unset if none was found
+    #[prost(int64, optional, tag = "2")]
+    pub value: ::core::option::Option<i64>,
+    /// The best objective any assignment could have, as far as the search
+    /// proved. It equals the value if the status is OPTIMAL.
+    #[prost(int64, tag = "3")]
+    pub bound: i64,
+    /// The value of every leaf that is not fixed in the best assignment found
+    #[prost(map = "string, int64", tag = "4")]
+    pub model: ::std::collections::HashMap<::prost::alloc::string::String, i64>,
+    #[prost(message, optional, tag = "5")]
+    pub statistics: ::core::option::Option<SolveStatistics>,
+}
+/// Nested message and enum types in `OptimizeResponse`.
+pub mod optimize_response {
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum Status {
+        /// No assignment was found before the time limit
+        Unknown = 0,
+        /// An assignment was found, but not proven optimal before the time limit
+        Feasible = 1,
+        Optimal = 2,
+        Infeasible = 3,
+    }
+    impl Status {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Status::Unknown => "UNKNOWN",
+                Status::Feasible => "FEASIBLE",
+                Status::Optimal => "OPTIMAL",
+                Status::Infeasible => "INFEASIBLE",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "UNKNOWN" => Some(Self::Unknown),
+                "FEASIBLE" => Some(Self::Feasible),
+                "OPTIMAL" => Some(Self::Optimal),
+                "INFEASIBLE" => Some(Self::Infeasible),
+                _ => None,
+            }
+        }
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RangeRequest {
+    #[prost(message, optional, tag = "1")]
+    pub tree: ::core::option::Option<super::puan_core::LinearBoundedTree>,
+    /// The nodes that must be 1. If empty, every node that no other node
+    /// relates to must be 1.
+    #[prost(string, repeated, tag = "2")]
+    pub roots: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// The coefficient of each node in the expression
+    #[prost(map = "string, int64", tag = "3")]
+    pub expression: ::std::collections::HashMap<::prost::alloc::string::String, i64>,
+    /// Bounds replacing the bounds of the leaves with the same ids, e.g. the
+    /// options selected so far
+    #[prost(message, optional, tag = "4")]
+    pub assignment: ::core::option::Option<Assignment>,
+    #[prost(enumeration = "range_request::Mode", tag = "5")]
+    pub mode: i32,
+    /// How long to search in the EXACT mode, no limit if 0
+    #[prost(uint64, tag = "6")]
+    pub time_limit_ms: u64,
+}
+/// Nested message and enum types in `RangeRequest`.
+pub mod range_request {
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration
+    )]
+    #[repr(i32)]
+    pub enum Mode {
+        /// The smallest and the largest reachable value
+        Exact = 0,
+        /// An interval containing every reachable value, found by propagation only
+        Propagated = 1,
+    }
+    impl Mode {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Mode::Exact => "EXACT",
+                Mode::Propagated => "PROPAGATED",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "EXACT" => Some(Self::Exact),
+                "PROPAGATED" => Some(Self::Propagated),
+                _ => None,
+            }
+        }
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RangeResponse {
+    /// Whether any assignment makes all roots 1. The interval is empty if not.
+    #[prost(bool, tag = "1")]
+    pub feasible: bool,
+    #[prost(int64, tag = "2")]
+    pub lower: i64,
+    #[prost(int64, tag = "3")]
+    pub upper: i64,
+    /// Whether both ends are reachable. If not, the interval only contains
+    /// every reachable value.
+    #[prost(bool, tag = "4")]
+    pub exact: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BackboneRequest {
+    #[prost(message, optional, tag = "1")]
+    pub tree: ::core::option::Option<super::puan_core::LinearBoundedTree>,
+    /// The nodes that must be 1. If empty, every node that no other node
+    /// relates to must be 1.
+    #[prost(string, repeated, tag = "2")]
+    pub roots: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// How long to search, no limit if 0
+    #[prost(uint64, tag = "3")]
+    pub time_limit_ms: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BackboneResponse {
+    /// Whether any assignment makes all roots 1
+    #[prost(bool, tag = "1")]
+    pub feasible: bool,
+    /// The value of every leaf that has the same value in all assignments
+    /// making the roots 1. Leaves that are fixed in the tree are left out.
+    #[prost(map = "string, int64", tag = "2")]
+    pub leaves: ::std::collections::HashMap<::prost::alloc::string::String, i64>,
+    /// Whether every leaf was decided before the time limit. If not, other
+    /// leaves may be fixed too.
+    #[prost(bool, tag = "3")]
+    pub complete: bool,
+}
+/// Generated client implementations.
+pub mod lbt_analysis_service_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    #[derive(Debug, Clone)]
+    pub struct LbtAnalysisServiceClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl LbtAnalysisServiceClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> LbtAnalysisServiceClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> LbtAnalysisServiceClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            LbtAnalysisServiceClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        /// Extracts the part of a Linear Bounded Tree reachable from, or affected by, a set of nodes.
+        pub async fn extract_lbt(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ExtractRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ExtractResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/puan_analysis.LbtAnalysisService/ExtractLbt",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("puan_analysis.LbtAnalysisService", "ExtractLbt"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Compiles a Linear Bounded Tree once and propagates it under each of the given leaf
+        /// assignments, returning the bounds of the roots.
+        pub async fn evaluate_batch(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BatchRequest>,
+        ) -> std::result::Result<tonic::Response<super::BatchResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/puan_analysis.LbtAnalysisService/EvaluateBatch",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("puan_analysis.LbtAnalysisService", "EvaluateBatch"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Searches for values of the [0, 1] leaves of a Linear Bounded Tree that make all roots 1.
+        pub async fn solve(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SolveRequest>,
+        ) -> std::result::Result<tonic::Response<super::SolveResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/puan_analysis.LbtAnalysisService/Solve",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("puan_analysis.LbtAnalysisService", "Solve"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Streams every assignment of the leaves of a Linear Bounded Tree that makes all roots 1,
+        /// optionally projected onto some of the leaves. The stream ends with DEADLINE_EXCEEDED if
+        /// the time limit is reached first.
+        pub async fn enumerate(
+            &mut self,
+            request: impl tonic::IntoRequest<super::EnumerateRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::EnumerateResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/puan_analysis.LbtAnalysisService/Enumerate",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("puan_analysis.LbtAnalysisService", "Enumerate"),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Counts the assignments of the leaves of a Linear Bounded Tree that make all roots 1,
+        /// optionally projected onto some of the leaves.
+        pub async fn count(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CountRequest>,
+        ) -> std::result::Result<tonic::Response<super::CountResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/puan_analysis.LbtAnalysisService/Count",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("puan_analysis.LbtAnalysisService", "Count"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Counts the assignments of the leaves of a Linear Bounded Tree that make all roots 1,
+        /// weighted by take rates, and the probability of each leaf and BIC being 1 among them.
+        pub async fn weighted_count(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WeightedCountRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::WeightedCountResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/puan_analysis.LbtAnalysisService/WeightedCount",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("puan_analysis.LbtAnalysisService", "WeightedCount"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Finds the assignment of the [0, 1] leaves of a Linear Bounded Tree that makes all roots 1
+        /// and minimizes or maximizes a linear objective, with the bound proven on the way.
+        pub async fn optimize(
+            &mut self,
+            request: impl tonic::IntoRequest<super::OptimizeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::OptimizeResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/puan_analysis.LbtAnalysisService/Optimize",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("puan_analysis.LbtAnalysisService", "Optimize"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Computes the interval of values a linear expression over the nodes of a Linear Bounded Tree
+        /// takes in the assignments of its leaves that make all roots 1.
+        pub async fn range(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RangeRequest>,
+        ) -> std::result::Result<tonic::Response<super::RangeResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/puan_analysis.LbtAnalysisService/Range",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("puan_analysis.LbtAnalysisService", "Range"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Finds the leaves of a Linear Bounded Tree that have the same value in every assignment that
+        /// makes all roots 1.
+        pub async fn backbone(
+            &mut self,
+            request: impl tonic::IntoRequest<super::BackboneRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::BackboneResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/puan_analysis.LbtAnalysisService/Backbone",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("puan_analysis.LbtAnalysisService", "Backbone"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod lbt_analysis_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with LbtAnalysisServiceServer.
+    #[async_trait]
+    pub trait LbtAnalysisService: Send + Sync + 'static {
+        /// Extracts the part of a Linear Bounded Tree reachable from, or affected by, a set of nodes.
+        async fn extract_lbt(
+            &self,
+            request: tonic::Request<super::ExtractRequest>,
+        ) -> std::result::Result<tonic::Response<super::ExtractResponse>, tonic::Status>;
+        /// Compiles a Linear Bounded Tree once and propagates it under each of the given leaf
+        /// assignments, returning the bounds of the roots.
+        async fn evaluate_batch(
+            &self,
+            request: tonic::Request<super::BatchRequest>,
+        ) -> std::result::Result<tonic::Response<super::BatchResponse>, tonic::Status>;
+        /// Searches for values of the [0, 1] leaves of a Linear Bounded Tree that make all roots 1.
+        async fn solve(
+            &self,
+            request: tonic::Request<super::SolveRequest>,
+        ) -> std::result::Result<tonic::Response<super::SolveResponse>, tonic::Status>;
+        /// Server streaming response type for the Enumerate method.
+        type EnumerateStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::EnumerateResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Streams every assignment of the leaves of a Linear Bounded Tree that makes all roots 1,
+        /// optionally projected onto some of the leaves. The stream ends with DEADLINE_EXCEEDED if
+        /// the time limit is reached first.
+        async fn enumerate(
+            &self,
+            request: tonic::Request<super::EnumerateRequest>,
+        ) -> std::result::Result<tonic::Response<Self::EnumerateStream>, tonic::Status>;
+        /// Counts the assignments of the leaves of a Linear Bounded Tree that make all roots 1,
+        /// optionally projected onto some of the leaves.
+        async fn count(
+            &self,
+            request: tonic::Request<super::CountRequest>,
+        ) -> std::result::Result<tonic::Response<super::CountResponse>, tonic::Status>;
+        /// Counts the assignments of the leaves of a Linear Bounded Tree that make all roots 1,
+        /// weighted by take rates, and the probability of each leaf and BIC being 1 among them.
+        async fn weighted_count(
+            &self,
+            request: tonic::Request<super::WeightedCountRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::WeightedCountResponse>,
+            tonic::Status,
+        >;
+        /// Finds the assignment of the [0, 1] leaves of a Linear Bounded Tree that makes all roots 1
+        /// and minimizes or maximizes a linear objective, with the bound proven on the way.
+        async fn optimize(
+            &self,
+            request: tonic::Request<super::OptimizeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::OptimizeResponse>,
+            tonic::Status,
+        >;
+        /// Computes the interval of values a linear expression over the nodes of a Linear Bounded Tree
+        /// takes in the assignments of its leaves that make all roots 1.
+        async fn range(
+            &self,
+            request: tonic::Request<super::RangeRequest>,
+        ) -> std::result::Result<tonic::Response<super::RangeResponse>, tonic::Status>;
+        /// Finds the leaves of a Linear Bounded Tree that have the same value in every assignment that
+        /// makes all roots 1.
+        async fn backbone(
+            &self,
+            request: tonic::Request<super::BackboneRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::BackboneResponse>,
+            tonic::Status,
+        >;
+    }
+    #[derive(Debug)]
+    pub struct LbtAnalysisServiceServer<T: LbtAnalysisService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: LbtAnalysisService> LbtAnalysisServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for LbtAnalysisServiceServer<T>
+    where
+        T: LbtAnalysisService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/puan_analysis.LbtAnalysisService/ExtractLbt" => {
+                    #[allow(non_camel_case_types)]
+                    struct ExtractLbtSvc<T: LbtAnalysisService>(pub Arc<T>);
+                    impl<
+                        T: LbtAnalysisService,
+                    > tonic::server::UnaryService<super::ExtractRequest>
+                    for ExtractLbtSvc<T> {
+                        type Response = super::ExtractResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ExtractRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as LbtAnalysisService>::extract_lbt(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ExtractLbtSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/puan_analysis.LbtAnalysisService/EvaluateBatch" => {
+                    #[allow(non_camel_case_types)]
+                    struct EvaluateBatchSvc<T: LbtAnalysisService>(pub Arc<T>);
+                    impl<
+                        T: LbtAnalysisService,
+                    > tonic::server::UnaryService<super::BatchRequest>
+                    for EvaluateBatchSvc<T> {
+                        type Response = super::BatchResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BatchRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as LbtAnalysisService>::evaluate_batch(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = EvaluateBatchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/puan_analysis.LbtAnalysisService/Solve" => {
+                    #[allow(non_camel_case_types)]
+                    struct SolveSvc<T: LbtAnalysisService>(pub Arc<T>);
+                    impl<
+                        T: LbtAnalysisService,
+                    > tonic::server::UnaryService<super::SolveRequest> for SolveSvc<T> {
+                        type Response = super::SolveResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SolveRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as LbtAnalysisService>::solve(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = SolveSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/puan_analysis.LbtAnalysisService/Enumerate" => {
+                    #[allow(non_camel_case_types)]
+                    struct EnumerateSvc<T: LbtAnalysisService>(pub Arc<T>);
+                    impl<
+                        T: LbtAnalysisService,
+                    > tonic::server::ServerStreamingService<super::EnumerateRequest>
+                    for EnumerateSvc<T> {
+                        type Response = super::EnumerateResponse;
+                        type ResponseStream = T::EnumerateStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::EnumerateRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as LbtAnalysisService>::enumerate(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = EnumerateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/puan_analysis.LbtAnalysisService/Count" => {
+                    #[allow(non_camel_case_types)]
+                    struct CountSvc<T: LbtAnalysisService>(pub Arc<T>);
+                    impl<
+                        T: LbtAnalysisService,
+                    > tonic::server::UnaryService<super::CountRequest> for CountSvc<T> {
+                        type Response = super::CountResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CountRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as LbtAnalysisService>::count(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CountSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/puan_analysis.LbtAnalysisService/WeightedCount" => {
+                    #[allow(non_camel_case_types)]
+                    struct WeightedCountSvc<T: LbtAnalysisService>(pub Arc<T>);
+                    impl<
+                        T: LbtAnalysisService,
+                    > tonic::server::UnaryService<super::WeightedCountRequest>
+                    for WeightedCountSvc<T> {
+                        type Response = super::WeightedCountResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WeightedCountRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as LbtAnalysisService>::weighted_count(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = WeightedCountSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/puan_analysis.LbtAnalysisService/Optimize" => {
+                    #[allow(non_camel_case_types)]
+                    struct OptimizeSvc<T: LbtAnalysisService>(pub Arc<T>);
+                    impl<
+                        T: LbtAnalysisService,
+                    > tonic::server::UnaryService<super::OptimizeRequest>
+                    for OptimizeSvc<T> {
+                        type Response = super::OptimizeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::OptimizeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as LbtAnalysisService>::optimize(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = OptimizeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/puan_analysis.LbtAnalysisService/Range" => {
+                    #[allow(non_camel_case_types)]
+                    struct RangeSvc<T: LbtAnalysisService>(pub Arc<T>);
+                    impl<
+                        T: LbtAnalysisService,
+                    > tonic::server::UnaryService<super::RangeRequest> for RangeSvc<T> {
+                        type Response = super::RangeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RangeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as LbtAnalysisService>::range(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RangeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/puan_analysis.LbtAnalysisService/Backbone" => {
+                    #[allow(non_camel_case_types)]
+                    struct BackboneSvc<T: LbtAnalysisService>(pub Arc<T>);
+                    impl<
+                        T: LbtAnalysisService,
+                    > tonic::server::UnaryService<super::BackboneRequest>
+                    for BackboneSvc<T> {
+                        type Response = super::BackboneResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::BackboneRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as LbtAnalysisService>::backbone(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = BackboneSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: LbtAnalysisService> Clone for LbtAnalysisServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T: LbtAnalysisService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: LbtAnalysisService> tonic::server::NamedService
+    for LbtAnalysisServiceServer<T> {
+        const NAME: &'static str = "puan_analysis.LbtAnalysisService";
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::cdcl::Solver;
+use crate::error::LbtError;
+use crate::optimize::{optimize, OptimizeOptions, OptimizeStatus, Sense};
+use crate::puan_core::{bic_or_bound, Bound, LinearBoundedTree};
+
+// How `range` computes the interval
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RangeMode {
+    // The smallest and the largest value reachable, found by `optimize`
+    #[default]
+    Exact,
+    // An interval containing every reachable value, found by propagating
+    // the constraints without searching
+    Propagated,
+}
+
+// Options for `range`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RangeOptions {
+    // The nodes that must be 1. If empty, every node that no other node
+    // relates to must be 1.
+    pub roots: Vec<String>,
+    // The coefficient of each node in the expression, e.g. the price of each
+    // option
+    pub expression: HashMap<String, i64>,
+    // Bounds that replace the bounds of the leaves with the same ids, e.g.
+    // the options selected so far
+    pub assignment: HashMap<String, Bound>,
+    pub mode: RangeMode,
+    // How long to search in the Exact mode, or no limit if None
+    pub time_limit: Option<Duration>,
+}
+
+// The values an expression can take in the assignments that make all roots 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub lower: i64,
+    pub upper: i64,
+    // Whether both ends are values of some assignment. If not, the interval
+    // only contains every reachable value, since the mode is Propagated or
+    // the time limit was reached.
+    pub exact: bool,
+}
+
+// Computes the interval of values a linear expression over the nodes of a
+// tree takes in the assignments of its leaves that make all roots 1.
+//
+// In the Exact mode, the ends are the minimum and the maximum of the
+// expression, found by `optimize`. If the time limit is reached first, the
+// bounds proven so far are returned instead. In the Propagated mode, the
+// constraints of `Solver` are only propagated on the first level, and every
+// node that is not fixed by that counts with whichever of 0 and 1 moves an
+// end further out. That is cheap, but misses every node that is fixed only
+// because its other value rules out all assignments.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to compute the range over. Leaves must
+//   either be fixed, i.e. have equal lower and upper bounds, or have the
+//   bound [0, 1], after the assignment replaced their bounds.
+// * `options` - The roots, the expression, the assignment, the mode and the
+//   time limit
+//
+// # Returns
+//
+// The range, None if no assignment makes all roots 1, or an error if a node
+// has no part, a root, child, node of the expression or assigned node does
+// not exist in the tree, an assigned node is a BIC, a leaf is neither fixed
+// nor [0, 1], or an end of the range does not fit in 64 bits
+pub fn range(tree: &LinearBoundedTree, options: &RangeOptions) -> Result<Option<Range>, LbtError> {
+    let tree = assign(tree, &options.assignment)?;
+    match options.mode {
+        RangeMode::Exact => exact_range(&tree, options),
+        RangeMode::Propagated => propagated_range(&tree, options),
+    }
+}
+
+// Returns a copy of the tree with the leaves in the assignment replaced by
+// their assigned bound
+fn assign(tree: &LinearBoundedTree, assignment: &HashMap<String, Bound>) -> Result<LinearBoundedTree, LbtError> {
+    let mut tree = tree.clone();
+    for (id, bound) in assignment.iter() {
+        let node = tree.nodes.get_mut(id).ok_or_else(|| LbtError::UnknownNode(id.to_string()))?;
+        if let Some(bic_or_bound::Part::Bic(_)) = node.part {
+            return Err(LbtError::Malformed(format!("node {} is a BIC, only Bound nodes can be assigned", id)));
+        }
+        node.part = Some(bic_or_bound::Part::Bound(bound.clone()));
+    }
+    Ok(tree)
+}
+
+fn exact_range(tree: &LinearBoundedTree, options: &RangeOptions) -> Result<Option<Range>, LbtError> {
+    // Both searches share the time limit
+    let deadline = options.time_limit.map(|limit| Instant::now() + limit);
+    let mut ends: Vec<(i64, bool)> = Vec::with_capacity(2);
+    for sense in [Sense::Minimize, Sense::Maximize] {
+        let optimum = optimize(tree, &OptimizeOptions {
+            roots: options.roots.clone(),
+            objective: options.expression.clone(),
+            sense,
+            time_limit: deadline.map(|deadline| deadline.saturating_duration_since(Instant::now())),
+        })?;
+        if optimum.status == OptimizeStatus::Infeasible {
+            return Ok(None);
+        }
+        ends.push((optimum.bound, optimum.status == OptimizeStatus::Optimal));
+    }
+    let [(lower, lower_exact), (upper, upper_exact)] = ends[..] else {
+        unreachable!("both ends are searched");
+    };
+    Ok(Some(Range { lower, upper, exact: lower_exact && upper_exact }))
+}
+
+fn propagated_range(tree: &LinearBoundedTree, options: &RangeOptions) -> Result<Option<Range>, LbtError> {
+    let mut solver = Solver::new(tree)?;
+    let roots: Vec<u32> = if options.roots.is_empty() {
+        solver.parentless()
+    } else {
+        options.roots.iter().map(|root| solver.node(root)).collect::<Result<_, _>>()?
+    };
+    solver.require(&roots);
+
+    let terms: Vec<(u32, i64)> = options.expression.iter()
+        .map(|(id, coefficient)| Ok((solver.node(id)?, *coefficient)))
+        .collect::<Result<_, LbtError>>()?;
+    solver.add_nodes(&terms.iter().map(|(node, _)| *node).collect::<Vec<u32>>());
+    if !solver.propagate_first_level() {
+        return Ok(None);
+    }
+
+    let overflow = || LbtError::Malformed("the objective does not fit in 64 bits".to_string());
+    let (mut lower, mut upper): (i64, i64) = (0, 0);
+    for (node, coefficient) in terms.iter() {
+        let (low, high) = match solver.value(*node) {
+            Some(value) => {
+                let value = coefficient.checked_mul(value).ok_or_else(overflow)?;
+                (value, value)
+            },
+            None => ((*coefficient).min(0), (*coefficient).max(0)),
+        };
+        lower = lower.checked_add(low).ok_or_else(overflow)?;
+        upper = upper.checked_add(high).ok_or_else(overflow)?;
+    }
+    Ok(Some(Range { lower, upper, exact: false }))
+}
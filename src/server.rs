@@ -1,130 +1,79 @@
-mod puan_core;
-use puan_core::{LinearBoundedTree, Bound, bic_or_bound, BicOrBound};
-use puan_core::lbt_evaluation_service_server::{LbtEvaluationService, LbtEvaluationServiceServer};
+use puan_eval::puan_core::LinearBoundedTree;
+use puan_eval::puan_core::lbt_evaluation_service_server::{LbtEvaluationService, LbtEvaluationServiceServer};
+use puan_eval::puan_analysis::{
+    extract_request, optimize_request, optimize_response, range_request, solve_response, BackboneRequest, BackboneResponse,
+    BatchRequest, BatchResponse, BatchResult, CountRequest, CountResponse, EnumerateRequest, EnumerateResponse, ExtractRequest,
+    ExtractResponse, OptimizeRequest, OptimizeResponse, RangeRequest, RangeResponse, SolveRequest, SolveResponse, SolveStatistics,
+    WeightedCountRequest, WeightedCountResponse,
+};
+use puan_eval::puan_analysis::lbt_analysis_service_server::{LbtAnalysisService, LbtAnalysisServiceServer};
+use puan_eval::backbone::{backbone, BackboneOptions};
+use puan_eval::batch::Batch;
+use puan_eval::count::{count, weighted_count, CountOptions, WeightedCountOptions};
+use puan_eval::enumerate::{enumerate, EnumerateOptions};
+use puan_eval::extract::{ancestors, extract};
+use puan_eval::optimize::{optimize, OptimizeOptions, OptimizeStatus, Sense};
+use puan_eval::dedup::expand_in_place;
+use puan_eval::passes::{stats_json, PassManager, PassStats};
+use puan_eval::propagate::propagate_in_place;
+use puan_eval::range::{range, RangeMode, RangeOptions};
+use puan_eval::solve::{self, solve, SolveOptions, SolveStats};
+use puan_eval::{LbtError, DESCRIPTOR_SET};
 
 use tonic::{transport::Server, Request, Response, Status};
-use std::collections::VecDeque;
+use std::collections::HashSet;
 use std::pin::Pin;
+use std::time::Duration;
 use tokio::sync::mpsc;
-use tokio_stream::{wrappers::UnboundedReceiverStream, Stream, StreamExt};
-
-pub const DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("descriptor");
-
-// Propagates a Linear Bounded Tree (LBT) by propagating all Binary Inequality Constraints (BICs)
-// in the tree. This is done by looping through all nodes in the tree, and propagating the BICs
-// that have all their children propagated. If a BIC has a child that is not propagated, we push
-// the current node to the back of the queue and wait for the child to be propagated. If a BIC has
-// a child that is not in the tree, we cannot ever propagate the BIC, so we just ignore it.
-//
-// The algorithm is implemented as a function that takes a LinearBoundedTree and returns a new
-// LinearBoundedTree. This is done to make it easier to test the algorithm, since we can just
-// create a LinearBoundedTree and pass it to the function, and then check the result.
-//
-// # Arguments
-//
-// * `tree` - A LinearBoundedTree to propagate
-//
-// # Returns
-//
-// A new LinearBoundedTree with all BICs propagated
-fn propagate(tree: &LinearBoundedTree) -> LinearBoundedTree {
-    let mut _tree = tree.clone();
-    let mut queue: VecDeque<String> = tree.nodes.keys().cloned().collect();
-
-    while queue.len() > 0 {
-        // Extract current node by popping from the queue
-        let current = queue.pop_front().unwrap();
-        let current_node = _tree.nodes.get(&current).unwrap();
-
-        // We should not have any nodes without parts
-        // so we panic if we encounter one
-        match &current_node.part {
-            Some(part) => {
-
-                // A node could be either a Binary Inequality Constraint (BIC)
-                // or a Bound. We are actually only interested in BICs, since
-                // bounds are already propagated.
-                match part {
-                    bic_or_bound::Part::Bic(bic) => {
-
-                        // If all children are bounds, we can propagate the BIC
-                        // Otherwise, two things may happen. Either we push the
-                        // current node to the back of the queue and wait for its
-                        // children to be propagated, or we find a child that is 
-                        // not in the tree, in which case we cannot ever propagate
-                        // the BIC.
-                        let mut new_lower_bound: i64 = 0;
-                        let mut new_upper_bound: i64 = 0;
-
-                        // Since we don't really know when looping is over if we've taken all
-                        // relations into account or not, we need to count all the ones that was
-                        // taken into account.
-                        let mut relations_taken_into_account: usize = 0;
-
-                        for child in bic.relations.iter() {
-                            match _tree.nodes.get(&child.id) {
-                                Some(child_node) => {
-                                    match &child_node.part {
-                                        Some(child_part) => {
-                                            match child_part {
-                                                // Here we find a node that hasn't been propagated
-                                                // so we push the current node to the back of the queue
-                                                // and hoping for all being propagated in the future.
-                                                bic_or_bound::Part::Bic(_) => {
-                                                    queue.push_back(child.id.to_string());
-                                                    break;
-                                                },
-                                                bic_or_bound::Part::Bound(bound) => {
-                                                    // We need to flip the child's bound if the coefficient is negative
-                                                    // (because -1*(0,1) === (-1,0))
-                                                    if child.coefficient < 0 {
-                                                        new_lower_bound += bound.upper * child.coefficient;
-                                                        new_upper_bound += bound.lower * child.coefficient;
-                                                    } else {
-                                                        new_lower_bound += bound.lower * child.coefficient;
-                                                        new_upper_bound += bound.upper * child.coefficient;
-                                                    }
-
-                                                    // We've taken this relation into account
-                                                    relations_taken_into_account += 1;
-                                                },
-                                            }
-                                        },
-                                        None => panic!("Node {} has no part", child.id),
-                                    }
-                                },
-                                // Here's when the child node cannot ever be propagated
-                                // so we just don't do anything. Not calculating the new
-                                // bound, nor pushing the current node to the back of the
-                                // queue.
-                                None => break,
-                            }
-                        }
-                        
-                        if relations_taken_into_account == bic.relations.len() {
-                            // We've taken all relations into account, so we can propagate
-                            // the BIC. Update the node in the tree
-                            _tree.nodes.entry(current.clone()).and_modify(|existing_value| {
-                                *existing_value = BicOrBound{
-                                    part: Some(bic_or_bound::Part::Bound(
-                                        Bound {
-                                            lower: (new_lower_bound >= 0) as i64,
-                                            upper: (new_upper_bound >= 0) as i64,
-                                        }
-                                    )),
-                                }
-                            });
-                        }
-                    },
-                    // We're not interested in bounds, since they are already propagated
-                    bic_or_bound::Part::Bound(_) => {},
-                }
-            },
-            None => panic!("Node {} has no part", current),
-        }
+use tokio_stream::{wrappers::{ReceiverStream, UnboundedReceiverStream}, Stream, StreamExt};
+
+// The request metadata naming the pipeline of passes to run over each tree
+// before it is propagated, see `PassManager::parse`
+const PIPELINE_HEADER: &str = "x-lbt-pipeline";
+// The response metadata holding the statistics of the pipeline as JSON
+const STATS_HEADER: &str = "x-lbt-pass-stats";
+
+// Returns the pipeline requested in the metadata of a request, which is empty
+// if none was requested
+fn pipeline<T>(request: &Request<T>) -> Result<PassManager, LbtError> {
+    match request.metadata().get(PIPELINE_HEADER) {
+        Some(name) => {
+            let name = name.to_str()
+                .map_err(|_| LbtError::Malformed(format!("{} must be ascii", PIPELINE_HEADER)))?;
+            PassManager::parse(name)
+        },
+        None => Ok(PassManager::new()),
     }
+}
 
-    return _tree;
+// Runs a pipeline over a tree and propagates the result in place. Nodes the
+// pipeline merged into others are added back as copies of those, and nodes
+// it added, like the bias node of `simplify`, are removed, so that the result
+// has exactly the ids of the tree before the pipeline.
+fn propagate_pipelined(
+    pipeline: &PassManager,
+    mut lbt: LinearBoundedTree,
+) -> Result<(LinearBoundedTree, Vec<PassStats>), LbtError> {
+    if pipeline.is_empty() {
+        propagate_in_place(&mut lbt)?;
+        return Ok((lbt, Vec::new()));
+    }
+    let ids: HashSet<String> = lbt.nodes.keys().cloned().collect();
+    let (mut lbt, aliases, stats) = pipeline.run(lbt)?;
+    propagate_in_place(&mut lbt)?;
+    expand_in_place(&mut lbt, &aliases);
+    lbt.nodes.retain(|id, _| ids.contains(id));
+    Ok((lbt, stats))
+}
+
+fn statistics(stats: &SolveStats) -> SolveStatistics {
+    SolveStatistics {
+        decisions: stats.decisions,
+        conflicts: stats.conflicts,
+        propagations: stats.propagations,
+        learned: stats.learned,
+        restarts: stats.restarts,
+    }
 }
 
 #[derive(Debug)]
@@ -140,6 +89,7 @@ impl LbtEvaluationService for PuanEvaluationService {
         request: Request<tonic::Streaming<LinearBoundedTree>>,
     ) -> Result<Response<Self::PropagateLbtStreamedStream>, Status> {
         
+        let pipeline = pipeline(&request)?;
         let mut stream = request.into_inner();
 
         // Create a channel for sending results to the stream
@@ -148,11 +98,11 @@ impl LbtEvaluationService for PuanEvaluationService {
         // Spawn a task to process the stream and send results to the channel
         tokio::spawn(async move {
             while let Some(lbt) = stream.next().await {
-                let result = Ok(
-                    propagate(
-                        &lbt.unwrap(),
-                    )
-                );
+                // The tree is owned by the stream, so propagate it in place
+                // rather than building a copy
+                let result = propagate_pipelined(&pipeline, lbt.unwrap())
+                    .map(|(lbt, _)| lbt)
+                    .map_err(Status::from);
                 
                 // Send the result to the channel
                 if let Err(_) = tx.send(result) {
@@ -171,9 +121,263 @@ impl LbtEvaluationService for PuanEvaluationService {
         &self,
         request: Request<LinearBoundedTree>,
     ) -> Result<Response<LinearBoundedTree>, Status> {
-        let lbt = request.into_inner();
-        let propagated_lbt = propagate(&lbt);
-        Ok(Response::new(propagated_lbt))
+        let pipeline = pipeline(&request)?;
+        let (lbt, stats) = propagate_pipelined(&pipeline, request.into_inner())?;
+
+        let mut response = Response::new(lbt);
+        if !stats.is_empty() {
+            let stats = stats_json(&stats).to_string().parse()
+                .map_err(|_| Status::internal("pass statistics are not valid metadata"))?;
+            response.metadata_mut().insert(STATS_HEADER, stats);
+        }
+        Ok(response)
+    }
+}
+
+#[derive(Debug)]
+struct PuanAnalysisService;
+
+#[tonic::async_trait]
+impl LbtAnalysisService for PuanAnalysisService {
+
+    async fn extract_lbt(
+        &self,
+        request: Request<ExtractRequest>,
+    ) -> Result<Response<ExtractResponse>, Status> {
+        let request = request.into_inner();
+        let direction = request.direction();
+        let lbt = request.tree.unwrap_or_default();
+
+        // For ancestors we return the tree needed to propagate all of them,
+        // which is the tree reachable from the ancestors themselves
+        let mut ids: Vec<String> = match direction {
+            extract_request::Direction::Descendants => request.ids,
+            extract_request::Direction::Ancestors => ancestors(&lbt, &request.ids)?.into_iter().collect(),
+        };
+        let mut extracted = extract(&lbt, &ids)?;
+        if request.propagate {
+            propagate_in_place(&mut extracted)?;
+        }
+
+        ids.sort_unstable();
+        ids.dedup();
+        Ok(Response::new(ExtractResponse { tree: Some(extracted), ids }))
+    }
+
+    async fn evaluate_batch(
+        &self,
+        request: Request<BatchRequest>,
+    ) -> Result<Response<BatchResponse>, Status> {
+        let request = request.into_inner();
+        let lbt = request.tree.unwrap_or_default();
+
+        let batch = Batch::new(&lbt, &request.roots)?;
+        let assignments: Vec<_> = request.assignments.into_iter()
+            .map(|assignment| assignment.bounds)
+            .collect();
+        let roots: Vec<String> = batch.roots().map(|root| root.to_string()).collect();
+        let results = batch.evaluate_all(&assignments)?
+            .into_iter()
+            .map(|bounds| BatchResult {
+                roots: roots.iter()
+                    .zip(bounds)
+                    .filter_map(|(root, bound)| Some((root.to_string(), bound?)))
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Response::new(BatchResponse { roots, results }))
+    }
+
+    async fn solve(
+        &self,
+        request: Request<SolveRequest>,
+    ) -> Result<Response<SolveResponse>, Status> {
+        let request = request.into_inner();
+        let lbt = request.tree.unwrap_or_default();
+        let options = SolveOptions {
+            roots: request.roots,
+            time_limit: (request.time_limit_ms > 0).then(|| Duration::from_millis(request.time_limit_ms)),
+        };
+
+        // The search may run until the time limit, so keep it off the threads
+        // serving other requests
+        let solution = tokio::task::spawn_blocking(move || solve(&lbt, &options))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))??;
+        let status = match solution.status {
+            solve::Status::Unknown => solve_response::Status::Unknown,
+            solve::Status::Sat => solve_response::Status::Sat,
+            solve::Status::Unsat => solve_response::Status::Unsat,
+        };
+        Ok(Response::new(SolveResponse {
+            status: status as i32,
+            model: solution.model.into_iter().collect(),
+            statistics: Some(statistics(&solution.stats)),
+        }))
+    }
+
+    type EnumerateStream = Pin<Box<dyn Stream<Item = Result<EnumerateResponse, Status>> + Send + 'static>>;
+
+    async fn enumerate(
+        &self,
+        request: Request<EnumerateRequest>,
+    ) -> Result<Response<Self::EnumerateStream>, Status> {
+        let request = request.into_inner();
+        let lbt = request.tree.unwrap_or_default();
+        let options = EnumerateOptions {
+            roots: request.roots,
+            limit: (request.limit > 0).then_some(request.limit as usize),
+            projection: request.projection,
+            time_limit: (request.time_limit_ms > 0).then(|| Duration::from_millis(request.time_limit_ms)),
+        };
+        // A bounded channel keeps the search from running ahead of the
+        // client. Once the client disconnects the receiver is dropped, which
+        // closes the channel and stops the search.
+        let (tx, rx) = mpsc::channel(16);
+        let closed = tx.clone();
+        let mut solutions = enumerate(&lbt, &options)?.cancel_when(move || closed.is_closed());
+        tokio::task::spawn_blocking(move || {
+            for model in solutions.by_ref() {
+                let model = model.into_iter().collect();
+                if tx.blocking_send(Ok(EnumerateResponse { model })).is_err() {
+                    return;
+                }
+            }
+            if solutions.timed_out() {
+                let _ = tx.blocking_send(Err(Status::deadline_exceeded("time limit reached before all solutions were found")));
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx)) as Self::EnumerateStream))
+    }
+
+    async fn count(
+        &self,
+        request: Request<CountRequest>,
+    ) -> Result<Response<CountResponse>, Status> {
+        let request = request.into_inner();
+        let lbt = request.tree.unwrap_or_default();
+        let options = CountOptions {
+            roots: request.roots,
+            projection: request.projection,
+            time_limit: (request.time_limit_ms > 0).then(|| Duration::from_millis(request.time_limit_ms)),
+        };
+
+        let count = tokio::task::spawn_blocking(move || count(&lbt, &options))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))??
+            .ok_or_else(|| Status::deadline_exceeded("time limit reached before the count was complete"))?;
+        Ok(Response::new(CountResponse { count: count.to_string() }))
+    }
+
+    async fn weighted_count(
+        &self,
+        request: Request<WeightedCountRequest>,
+    ) -> Result<Response<WeightedCountResponse>, Status> {
+        let request = request.into_inner();
+        let lbt = request.tree.unwrap_or_default();
+        let options = WeightedCountOptions {
+            roots: request.roots,
+            weights: request.weights,
+            time_limit: (request.time_limit_ms > 0).then(|| Duration::from_millis(request.time_limit_ms)),
+        };
+
+        let weighted = tokio::task::spawn_blocking(move || weighted_count(&lbt, &options))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))??
+            .ok_or_else(|| Status::deadline_exceeded("time limit reached before the count was complete"))?;
+        Ok(Response::new(WeightedCountResponse {
+            count: weighted.count(),
+            log_count: weighted.log_count,
+            marginals: weighted.marginals.into_iter().collect(),
+        }))
+    }
+
+    async fn optimize(
+        &self,
+        request: Request<OptimizeRequest>,
+    ) -> Result<Response<OptimizeResponse>, Status> {
+        let request = request.into_inner();
+        let sense = match request.sense() {
+            optimize_request::Sense::Minimize => Sense::Minimize,
+            optimize_request::Sense::Maximize => Sense::Maximize,
+        };
+        let lbt = request.tree.unwrap_or_default();
+        let options = OptimizeOptions {
+            roots: request.roots,
+            objective: request.objective,
+            sense,
+            time_limit: (request.time_limit_ms > 0).then(|| Duration::from_millis(request.time_limit_ms)),
+        };
+
+        let optimum = tokio::task::spawn_blocking(move || optimize(&lbt, &options))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))??;
+        let status = match optimum.status {
+            OptimizeStatus::Unknown => optimize_response::Status::Unknown,
+            OptimizeStatus::Feasible => optimize_response::Status::Feasible,
+            OptimizeStatus::Optimal => optimize_response::Status::Optimal,
+            OptimizeStatus::Infeasible => optimize_response::Status::Infeasible,
+        };
+        Ok(Response::new(OptimizeResponse {
+            status: status as i32,
+            value: optimum.value,
+            bound: optimum.bound,
+            model: optimum.model.into_iter().collect(),
+            statistics: Some(statistics(&optimum.stats)),
+        }))
+    }
+
+    async fn range(
+        &self,
+        request: Request<RangeRequest>,
+    ) -> Result<Response<RangeResponse>, Status> {
+        let request = request.into_inner();
+        let mode = match request.mode() {
+            range_request::Mode::Exact => RangeMode::Exact,
+            range_request::Mode::Propagated => RangeMode::Propagated,
+        };
+        let lbt = request.tree.unwrap_or_default();
+        let options = RangeOptions {
+            roots: request.roots,
+            expression: request.expression,
+            assignment: request.assignment.map(|assignment| assignment.bounds).unwrap_or_default(),
+            mode,
+            time_limit: (request.time_limit_ms > 0).then(|| Duration::from_millis(request.time_limit_ms)),
+        };
+
+        let range = tokio::task::spawn_blocking(move || range(&lbt, &options))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))??;
+        Ok(Response::new(match range {
+            Some(range) => RangeResponse { feasible: true, lower: range.lower, upper: range.upper, exact: range.exact },
+            None => RangeResponse { feasible: false, lower: 0, upper: 0, exact: true },
+        }))
+    }
+
+    async fn backbone(
+        &self,
+        request: Request<BackboneRequest>,
+    ) -> Result<Response<BackboneResponse>, Status> {
+        let request = request.into_inner();
+        let lbt = request.tree.unwrap_or_default();
+        let options = BackboneOptions {
+            roots: request.roots,
+            time_limit: (request.time_limit_ms > 0).then(|| Duration::from_millis(request.time_limit_ms)),
+        };
+
+        let backbone = tokio::task::spawn_blocking(move || backbone(&lbt, &options))
+            .await
+            .map_err(|error| Status::internal(error.to_string()))??;
+        Ok(Response::new(match backbone {
+            Some(backbone) => BackboneResponse {
+                feasible: true,
+                leaves: backbone.leaves.into_iter().collect(),
+                complete: backbone.complete,
+            },
+            None => BackboneResponse { feasible: false, leaves: Default::default(), complete: true },
+        }))
     }
 }
 
@@ -190,6 +394,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 PuanEvaluationService {}
             )
         )
+        .add_service(
+            LbtAnalysisServiceServer::new(
+                PuanAnalysisService {}
+            )
+        )
         .add_service(reflection_server)
         .serve("[::1]:10000".parse().unwrap())
         .await?;
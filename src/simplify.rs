@@ -0,0 +1,227 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::error::LbtError;
+use crate::graph::topological_order;
+use crate::puan_core::{
+    bic_or_bound, BicOrBound, BinaryInequalityConstraint, Bound, CoefRelation, LinearBoundedTree,
+};
+
+// Which simplifications to apply, all of them by default
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimplifyOptions {
+    // The id of the constant node with the bound [1, 1] that folded constants
+    // relate to. It is added to the tree if any BIC needs it.
+    pub bias_id: String,
+    // Replace relations to children with a fixed bound by a single relation
+    // to the bias node
+    pub fold_constants: bool,
+    // Remove relations with a coefficient of 0
+    pub drop_zeros: bool,
+    // Sum the coefficients of all relations to the same child
+    pub merge_duplicates: bool,
+    // Divide the coefficients by their greatest common divisor
+    pub normalise_gcd: bool,
+    // Replace BICs that are 1, or 0, for any values of their children by a
+    // Bound of [1, 1], or [0, 0]
+    pub remove_trivial: bool,
+}
+
+impl Default for SimplifyOptions {
+    fn default() -> Self {
+        SimplifyOptions {
+            bias_id: "bias".to_string(),
+            fold_constants: true,
+            drop_zeros: true,
+            merge_duplicates: true,
+            normalise_gcd: true,
+            remove_trivial: true,
+        }
+    }
+}
+
+// What is known about a node after it has been simplified
+#[derive(Debug, Clone, Copy)]
+enum State {
+    // A Bound, or a BIC that was replaced by one
+    Bound(i64, i64),
+    // A BIC that can be propagated once its children are
+    Bic,
+    // A BIC that can never be propagated, since it depends on a node outside
+    // the tree
+    Dangling,
+}
+
+// Simplifies every BIC of a tree, children first, so that the BICs a BIC
+// relates to are simplified before it is. For each BIC, in order:
+//
+// 1. Relations to the same child are merged by summing their coefficients.
+// 2. Relations to children with a fixed bound, i.e. Bounds with equal lower
+//    and upper bound and BICs simplified to such a Bound, are folded into one
+//    constant, which becomes a relation to the bias node.
+// 3. Relations with a coefficient of 0 are dropped.
+// 4. If the BIC is 1, or 0, for any values of its children, it is replaced
+//    by the Bound [1, 1], or [0, 0].
+// 5. The coefficients are divided by their greatest common divisor `g`. The
+//    constant is divided by `g` rounding down, which keeps the BIC the same
+//    since the rest of the sum is always a multiple of `g`.
+//
+// Relations to nodes outside the tree are kept as they are, so that BICs
+// that cannot be propagated stay that way. All ids of the tree are kept.
+//
+// The simplified tree has the same value as the original tree for every
+// node, under every assignment of values to the leaves that are not fixed.
+// Propagating the simplified tree gives the same or tighter bounds than
+// propagating the original tree, since merging relations to the same child
+// removes a source of slack: `x - x` propagates to [-1, 1] for x in [0, 1]
+// but is always 0.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to simplify
+// * `options` - Which simplifications to apply
+//
+// # Returns
+//
+// The simplified tree, or an error if a node has no part, the relations form
+// a cycle, or the bias id is used by a node that is not the Bound [1, 1] or
+// is related to without being part of the tree
+pub fn simplify(tree: &LinearBoundedTree, options: &SimplifyOptions) -> Result<LinearBoundedTree, LbtError> {
+    match tree.nodes.get(&options.bias_id) {
+        Some(node) if node.part != Some(bic_or_bound::Part::Bound(Bound { lower: 1, upper: 1 })) => {
+            return Err(LbtError::Malformed(format!(
+                "bias node {} exists but is not the Bound [1, 1]", options.bias_id,
+            )));
+        },
+        Some(_) => {},
+        // Adding the bias node would change any BIC relating to it from
+        // dangling to propagated
+        None => {
+            let referenced = tree.nodes.values()
+                .filter_map(|node| match &node.part {
+                    Some(bic_or_bound::Part::Bic(bic)) => Some(bic),
+                    _ => None,
+                })
+                .any(|bic| bic.relations.iter().any(|relation| relation.id == options.bias_id));
+            if referenced {
+                return Err(LbtError::Malformed(format!(
+                    "bias node {} is related to but not part of the tree", options.bias_id,
+                )));
+            }
+        },
+    }
+
+    let order = topological_order(tree)?;
+    let mut states: HashMap<&str, State> = HashMap::with_capacity(tree.nodes.len());
+    let mut simplified = LinearBoundedTree::default();
+    simplified.nodes.reserve(tree.nodes.len());
+    let mut uses_bias = false;
+
+    for id in order {
+        let node = &tree.nodes[id];
+        let bic = match &node.part {
+            Some(bic_or_bound::Part::Bic(bic)) => bic,
+            Some(bic_or_bound::Part::Bound(bound)) => {
+                states.insert(id, State::Bound(bound.lower, bound.upper));
+                simplified.nodes.insert(id.to_string(), node.clone());
+                continue;
+            },
+            None => return Err(LbtError::MissingPart(id.to_string())),
+        };
+
+        let mut relations: Vec<(&str, i64)> = if options.merge_duplicates {
+            let mut sums: BTreeMap<&str, i64> = BTreeMap::new();
+            for relation in bic.relations.iter() {
+                *sums.entry(relation.id.as_str()).or_insert(0) += relation.coefficient;
+            }
+            sums.into_iter().collect()
+        } else {
+            bic.relations.iter().map(|relation| (relation.id.as_str(), relation.coefficient)).collect()
+        };
+
+        let mut constant: i64 = 0;
+        if options.fold_constants {
+            relations.retain(|(child, coefficient)| match states.get(child) {
+                Some(State::Bound(lower, upper)) if lower == upper => {
+                    constant += coefficient * lower;
+                    false
+                },
+                _ => true,
+            });
+        }
+        if options.drop_zeros {
+            // Relations to nodes outside the tree, or to BICs depending on
+            // them, are what keeps a dangling BIC from being propagated, so
+            // they are never dropped
+            relations.retain(|(child, coefficient)| {
+                *coefficient != 0 || matches!(states.get(child), Some(State::Dangling) | None)
+            });
+        }
+
+        // A BIC can only be propagated if all of its children can
+        let mut state = State::Bic;
+        let mut minimum = constant;
+        let mut maximum = constant;
+        for (child, coefficient) in relations.iter() {
+            let (lower, upper) = match states.get(child) {
+                Some(State::Bound(lower, upper)) => (*lower, *upper),
+                Some(State::Bic) => (0, 1),
+                Some(State::Dangling) | None => {
+                    state = State::Dangling;
+                    break;
+                },
+            };
+            if *coefficient < 0 {
+                minimum += upper * coefficient;
+                maximum += lower * coefficient;
+            } else {
+                minimum += lower * coefficient;
+                maximum += upper * coefficient;
+            }
+        }
+
+        if options.remove_trivial && !matches!(state, State::Dangling) && (minimum >= 0 || maximum < 0) {
+            let value = (minimum >= 0) as i64;
+            states.insert(id, State::Bound(value, value));
+            simplified.nodes.insert(id.to_string(), BicOrBound {
+                part: Some(bic_or_bound::Part::Bound(Bound { lower: value, upper: value })),
+            });
+            continue;
+        }
+
+        if options.normalise_gcd {
+            let divisor = relations.iter().fold(0, |divisor, (_, coefficient)| gcd(divisor, coefficient.unsigned_abs()));
+            if divisor > 1 && divisor <= i64::MAX as u64 {
+                let divisor = divisor as i64;
+                for (_, coefficient) in relations.iter_mut() {
+                    *coefficient /= divisor;
+                }
+                constant = constant.div_euclid(divisor);
+            }
+        }
+
+        let mut relations: Vec<CoefRelation> = relations.into_iter()
+            .map(|(child, coefficient)| CoefRelation { id: child.to_string(), coefficient })
+            .collect();
+        if constant != 0 {
+            uses_bias = true;
+            relations.push(CoefRelation { id: options.bias_id.to_string(), coefficient: constant });
+        }
+        states.insert(id, state);
+        simplified.nodes.insert(id.to_string(), BicOrBound {
+            part: Some(bic_or_bound::Part::Bic(BinaryInequalityConstraint { relations })),
+        });
+    }
+
+    if uses_bias {
+        simplified.nodes.insert(options.bias_id.to_string(), BicOrBound {
+            part: Some(bic_or_bound::Part::Bound(Bound { lower: 1, upper: 1 })),
+        });
+    }
+    Ok(simplified)
+}
+
+// Returns the greatest common divisor of two numbers, where the divisor of
+// any number and 0 is the number itself
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::error::LbtError;
+use crate::graph::topological_order;
+use crate::puan_core::{bic_or_bound, LinearBoundedTree};
+
+// Options controlling which queries are appended to an exported SMT-LIB 2
+// script. With the default options only the declarations and definitions of
+// the tree are written, which makes the script usable as a prelude for
+// hand-written queries.
+#[derive(Debug, Clone, Default)]
+pub struct SmtOptions {
+    // Node ids that are asserted to be 1
+    pub roots: Vec<String>,
+    // Whether to append a (check-sat) command
+    pub check_sat: bool,
+    // Whether to append a (get-model) command after (check-sat)
+    pub get_model: bool,
+}
+
+// Exports a LinearBoundedTree as an SMT-LIB 2 script over integer variables.
+//
+// Every Bound node becomes an integer constant constrained to its bound and
+// every BIC node becomes a function defined as `(ite (>= sum 0) 1 0)` over its
+// children. Children that are referenced but not part of the tree are
+// declared as unconstrained integers, since nothing is known about them.
+// Definitions are written in topological order, so every symbol is declared
+// before it is used.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to export
+// * `options` - Which roots to assert and which queries to append
+//
+// # Returns
+//
+// The SMT-LIB 2 script, or an error if the tree is malformed, contains a
+// cycle, or has ids that cannot be written as SMT-LIB symbols
+pub fn to_smtlib(tree: &LinearBoundedTree, options: &SmtOptions) -> Result<String, LbtError> {
+    for root in options.roots.iter() {
+        if !tree.nodes.contains_key(root) {
+            return Err(LbtError::UnknownNode(root.to_string()));
+        }
+    }
+
+    let order = topological_order(tree)?;
+    let mut script = String::new();
+
+    // Writing to a String cannot fail, hence all the unwraps below
+    writeln!(script, "; LinearBoundedTree with {} nodes", tree.nodes.len()).unwrap();
+    writeln!(script, "(set-logic QF_LIA)").unwrap();
+
+    // Declare all children that are missing from the tree up front. These are
+    // sorted to keep the output stable between runs.
+    let mut missing: Vec<&str> = tree.nodes.values()
+        .filter_map(|node| match &node.part {
+            Some(bic_or_bound::Part::Bic(bic)) => Some(bic.relations.iter()),
+            _ => None,
+        })
+        .flatten()
+        .map(|relation| relation.id.as_str())
+        .filter(|id| !tree.nodes.contains_key(*id))
+        .collect::<HashSet<&str>>()
+        .into_iter()
+        .collect();
+    missing.sort_unstable();
+    for id in missing {
+        writeln!(script, "; {} is not part of the tree", id).unwrap();
+        writeln!(script, "(declare-const {} Int)", symbol(id)?).unwrap();
+    }
+
+    for id in order {
+        let name = symbol(id)?;
+        match &tree.nodes[id].part {
+            Some(bic_or_bound::Part::Bound(bound)) => {
+                writeln!(script, "(declare-const {} Int)", name).unwrap();
+                if bound.lower == bound.upper {
+                    writeln!(script, "(assert (= {} {}))", name, numeral(bound.lower)).unwrap();
+                } else {
+                    writeln!(
+                        script,
+                        "(assert (and (>= {} {}) (<= {} {})))",
+                        name, numeral(bound.lower), name, numeral(bound.upper),
+                    ).unwrap();
+                }
+            },
+            Some(bic_or_bound::Part::Bic(bic)) => {
+                let mut terms: Vec<String> = Vec::with_capacity(bic.relations.len());
+                for relation in bic.relations.iter() {
+                    terms.push(format!("(* {} {})", numeral(relation.coefficient), symbol(&relation.id)?));
+                }
+                let sum = match terms.len() {
+                    0 => "0".to_string(),
+                    1 => terms.pop().unwrap(),
+                    _ => format!("(+ {})", terms.join(" ")),
+                };
+                writeln!(script, "(define-fun {} () Int (ite (>= {} 0) 1 0))", name, sum).unwrap();
+            },
+            // Already rejected by the topological ordering
+            None => return Err(LbtError::MissingPart(id.to_string())),
+        }
+    }
+
+    for root in options.roots.iter() {
+        writeln!(script, "(assert (= {} 1))", symbol(root)?).unwrap();
+    }
+    if options.check_sat {
+        writeln!(script, "(check-sat)").unwrap();
+        if options.get_model {
+            writeln!(script, "(get-model)").unwrap();
+        }
+    }
+
+    Ok(script)
+}
+
+// Writes an integer as an SMT-LIB numeral. Numerals cannot be negative in
+// SMT-LIB, so negative values are written as a negation.
+fn numeral(value: i64) -> String {
+    if value < 0 {
+        format!("(- {})", value.unsigned_abs())
+    } else {
+        value.to_string()
+    }
+}
+
+// Writes a node id as a quoted SMT-LIB symbol. Ids are always quoted, since
+// an id such as `ite` or `+` would otherwise clash with the predefined
+// symbols of the logic. Quoted symbols cannot contain `|` or `\`, so ids
+// with those characters are rejected.
+fn symbol(id: &str) -> Result<String, LbtError> {
+    if id.contains(['|', '\\']) {
+        Err(LbtError::InvalidId(id.to_string()))
+    } else {
+        Ok(format!("|{}|", id))
+    }
+}
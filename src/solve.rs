@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::cdcl::Solver;
+use crate::error::LbtError;
+use crate::puan_core::LinearBoundedTree;
+
+// Options for `solve`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolveOptions {
+    // The nodes that must be 1. If empty, every node that no other node
+    // relates to must be 1.
+    pub roots: Vec<String>,
+    // How long to search before giving up, or no limit if None
+    pub time_limit: Option<Duration>,
+}
+
+// Whether an assignment was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    // The time limit was reached before the search finished
+    Unknown,
+    // An assignment making all roots 1 was found
+    Sat,
+    // No assignment makes all roots 1
+    Unsat,
+}
+
+// Counters of the work done by a search
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SolveStats {
+    // The number of times a value was chosen for a node
+    pub decisions: u64,
+    // The number of times propagation found a contradiction
+    pub conflicts: u64,
+    // The number of times a constraint fixed the value of a node
+    pub propagations: u64,
+    // The number of constraints learned from conflicts
+    pub learned: u64,
+    // The number of times the search started over
+    pub restarts: u64,
+}
+
+// The result of `solve`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub status: Status,
+    // The value of every leaf that is not fixed if the status is Sat, empty otherwise
+    pub model: BTreeMap<String, i64>,
+    pub stats: SolveStats,
+}
+
+// Searches for values of the leaves of a tree that make all roots 1.
+//
+// The search is done by a `Solver`, which turns the BICs the roots depend on
+// into constraints and learns from every contradiction it runs into, see
+// `Solver` for how. Only leaves the roots depend on are searched, all other
+// leaves are set to their lower bound in the model.
+//
+// # Arguments
+//
+// * `tree` - The LinearBoundedTree to search. Leaves must either be fixed,
+//   i.e. have equal lower and upper bounds, or have the bound [0, 1].
+// * `options` - The roots and the time limit
+//
+// # Returns
+//
+// The solution, or an error if a node has no part, a root or child does not
+// exist in the tree, or a leaf is neither fixed nor [0, 1]
+pub fn solve(tree: &LinearBoundedTree, options: &SolveOptions) -> Result<Solution, LbtError> {
+    let deadline = options.time_limit.map(|limit| Instant::now() + limit);
+    let mut solver = Solver::new(tree)?;
+    let roots: Vec<u32> = if options.roots.is_empty() {
+        solver.parentless()
+    } else {
+        options.roots.iter().map(|root| solver.node(root)).collect::<Result<_, _>>()?
+    };
+    solver.require(&roots);
+
+    let status = solver.solve(&[], deadline);
+    let model = if status == Status::Sat { free_leaves(&solver) } else { BTreeMap::new() };
+    Ok(Solution { status, model, stats: solver.stats() })
+}
+
+// Returns the value of every leaf that is not fixed in the last assignment
+// found by a solver. Leaves no constraint depends on get their lower bound.
+pub(crate) fn free_leaves(solver: &Solver) -> BTreeMap<String, i64> {
+    let compiled = solver.compiled();
+    (0..compiled.len() as u32)
+        .filter(|node| !compiled.is_bic[*node as usize] && solver.constant(*node).is_none())
+        .map(|node| (compiled.id(node).to_string(), solver.value(node).unwrap_or(compiled.lower[node as usize])))
+        .collect()
+}
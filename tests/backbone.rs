@@ -0,0 +1,34 @@
+use std::collections::BTreeMap;
+
+use proptest::prelude::*;
+
+use puan_eval::backbone::{backbone, BackboneOptions};
+
+mod common;
+
+use common::{cases, free_leaves, valid};
+
+proptest! {
+    // The backbone is every leaf with the same value in all assignments that
+    // make the roots 1
+    #[test]
+    fn backbone_agrees_with_brute_force(case in cases()) {
+        let options = BackboneOptions { roots: case.roots.clone(), time_limit: None };
+        let found = backbone(&case.tree, &options).unwrap();
+
+        let valid = valid(&case);
+        if valid.is_empty() {
+            prop_assert_eq!(found, None);
+        } else {
+            let expected: BTreeMap<String, i64> = free_leaves(&case.tree).into_iter()
+                .filter_map(|leaf| {
+                    let value = valid[0].0[&leaf];
+                    valid.iter().all(|(assignment, _)| assignment[&leaf] == value).then_some((leaf, value))
+                })
+                .collect();
+            let found = found.unwrap();
+            prop_assert!(found.complete);
+            prop_assert_eq!(found.leaves, expected);
+        }
+    }
+}
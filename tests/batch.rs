@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use puan_eval::batch::Batch;
+use puan_eval::error::LbtError;
+use puan_eval::propagate::propagate;
+use puan_eval::puan_core::{bic_or_bound, Bound, LinearBoundedTree};
+
+mod common;
+
+use common::{bic, bound, cases, free_leaves, roots};
+
+// Returns the bound of each root after propagating the tree with the
+// assignment replacing the bounds of its leaves
+fn expected(tree: &LinearBoundedTree, roots: &[String], assignment: &HashMap<String, Bound>) -> Vec<Option<Bound>> {
+    let mut tree = tree.clone();
+    for (id, value) in assignment.iter() {
+        tree.nodes.insert(id.to_string(), bound(value.lower, value.upper));
+    }
+    let propagated = propagate(&tree).unwrap();
+    roots.iter()
+        .map(|root| match &propagated.nodes[root].part {
+            Some(bic_or_bound::Part::Bound(bound)) => Some(bound.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+proptest! {
+    // Every assignment evaluates to the root bounds of propagating the
+    // overridden tree, one at a time and all at once
+    #[test]
+    fn batch_agrees_with_propagate(
+        case in cases(),
+        assignments in prop::collection::vec(prop::collection::vec((any::<Index>(), -1i64..=1, 0i64..=1), 0..4), 1..20),
+    ) {
+        let leaves = free_leaves(&case.tree);
+        let assignments: Vec<HashMap<String, Bound>> = assignments.into_iter()
+            .map(|assigned| if leaves.is_empty() {
+                HashMap::new()
+            } else {
+                assigned.into_iter()
+                    .map(|(leaf, lower, width)| (leaves[leaf.index(leaves.len())].to_string(), Bound { lower, upper: lower + width }))
+                    .collect()
+            })
+            .collect();
+
+        let batch = Batch::new(&case.tree, &case.roots).unwrap();
+        let mut roots = roots(&case);
+        if case.roots.is_empty() {
+            roots.sort_unstable();
+        }
+        prop_assert_eq!(batch.roots().collect::<Vec<_>>(), roots.iter().map(String::as_str).collect::<Vec<_>>());
+
+        let all = batch.evaluate_all(&assignments).unwrap();
+        for (assignment, bounds) in assignments.iter().zip(all.iter()) {
+            let expected = expected(&case.tree, &roots, assignment);
+            prop_assert_eq!(&batch.evaluate(assignment).unwrap(), &expected);
+            prop_assert_eq!(bounds, &expected);
+        }
+    }
+}
+
+#[test]
+fn invalid_assignments_are_rejected_without_changing_later_results() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("one".to_string(), bound(1, 1));
+    tree.nodes.insert("b".to_string(), bic(&[("x", 1), ("one", -1)]));
+    let batch = Batch::new(&tree, &["b".to_string()]).unwrap();
+
+    let assigned = |id: &str, value: i64| HashMap::from([(id.to_string(), Bound { lower: value, upper: value })]);
+    assert!(matches!(batch.evaluate(&assigned("b", 1)), Err(LbtError::Malformed(_))));
+    assert_eq!(batch.evaluate(&assigned("gone", 1)), Err(LbtError::UnknownNode("gone".to_string())));
+    assert!(batch.evaluate_all(&[assigned("x", 1), assigned("gone", 1)]).is_err());
+
+    let mut invalid = assigned("x", 1);
+    invalid.insert("gone".to_string(), Bound { lower: 0, upper: 0 });
+    assert!(batch.evaluate(&invalid).is_err());
+    assert_eq!(batch.evaluate(&HashMap::new()).unwrap(), vec![Some(Bound { lower: 0, upper: 1 })]);
+    assert_eq!(Batch::new(&tree, &["gone".to_string()]).err(), Some(LbtError::UnknownNode("gone".to_string())));
+}
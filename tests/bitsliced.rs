@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use proptest::prelude::*;
+
+use puan_eval::bitsliced::{BitSliced, LANES};
+use puan_eval::puan_core::{bic_or_bound, LinearBoundedTree};
+
+mod common;
+
+use common::{bic, bound, cases, free_leaves, values};
+
+proptest! {
+    // Every lane holds the values propagate gives for its assignment, with
+    // all assignments of the free leaves packed 64 at a time. Coefficients
+    // are scaled so that sums need many bit planes and long carries.
+    #[test]
+    fn lanes_agree_with_propagate(case in cases(), scales in prop::collection::vec(prop::sample::select(vec![1i64, 3, 255, 1 << 33, (1 << 40) - 1]), 32)) {
+        let mut tree = case.tree.clone();
+        let mut scales = scales.into_iter().cycle();
+        for node in tree.nodes.values_mut() {
+            if let Some(bic_or_bound::Part::Bic(bic)) = &mut node.part {
+                for relation in bic.relations.iter_mut() {
+                    relation.coefficient *= scales.next().unwrap();
+                }
+            }
+        }
+
+        let sliced = BitSliced::new(&tree).unwrap();
+        let leaves = free_leaves(&tree);
+        let assignments: Vec<HashMap<String, bool>> = (0..1u32 << leaves.len())
+            .map(|bits| leaves.iter().enumerate().map(|(i, leaf)| (leaf.to_string(), bits >> i & 1 == 1)).collect())
+            .collect();
+        for chunk in assignments.chunks(LANES) {
+            let mut lanes = sliced.pack(chunk).unwrap();
+            sliced.evaluate(&mut lanes);
+            for (lane, assignment) in chunk.iter().enumerate() {
+                let assignment = assignment.iter().map(|(leaf, value)| (leaf.to_string(), *value as i64)).collect();
+                for (id, value) in values(&tree, &assignment) {
+                    prop_assert_eq!(sliced.value(&lanes, &id, lane), Some(value == 1), "{} in lane {}", id, lane);
+                }
+            }
+        }
+    }
+}
+
+// Sums of the largest coefficients need more than 64 bit planes
+#[test]
+fn extreme_coefficients_are_summed_exactly() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("f".to_string(), bound(1, 1));
+    tree.nodes.insert("zero".to_string(), bic(&[("x", i64::MAX), ("f", i64::MAX), ("x", -i64::MAX), ("f", -i64::MAX)]));
+    tree.nodes.insert("min".to_string(), bic(&[("x", i64::MIN), ("f", i64::MAX)]));
+    tree.nodes.insert("max".to_string(), bic(&[("x", i64::MAX), ("f", i64::MAX), ("f", i64::MIN), ("f", i64::MIN), ("f", 2)]));
+
+    let sliced = BitSliced::new(&tree).unwrap();
+    let assignments = [HashMap::from([("x".to_string(), false)]), HashMap::from([("x".to_string(), true)])];
+    let mut lanes = sliced.pack(&assignments).unwrap();
+    sliced.evaluate(&mut lanes);
+    let lane = |id: &str| (0..2).map(|lane| sliced.value(&lanes, id, lane).unwrap()).collect::<Vec<bool>>();
+    assert_eq!(lane("zero"), [true, true]);
+    assert_eq!(lane("min"), [true, false]);
+    assert_eq!(lane("max"), [false, true]);
+}
+
+#[test]
+fn invalid_assignments_are_rejected() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("f".to_string(), bound(1, 1));
+    tree.nodes.insert("b".to_string(), bic(&[("x", 1)]));
+    let sliced = BitSliced::new(&tree).unwrap();
+
+    let assignment = |values: &[(&str, bool)]| -> HashMap<String, bool> {
+        values.iter().map(|(id, value)| (id.to_string(), *value)).collect()
+    };
+    assert!(sliced.pack(&[assignment(&[("x", true), ("f", true)])]).is_ok());
+    assert!(sliced.pack(&[assignment(&[])]).is_err());
+    assert!(sliced.pack(&[assignment(&[("x", true), ("f", false)])]).is_err());
+    assert!(sliced.pack(&[assignment(&[("x", true), ("b", true)])]).is_err());
+    assert!(sliced.pack(&vec![assignment(&[("x", true)]); LANES + 1]).is_err());
+
+    tree.nodes.insert("wide".to_string(), bound(0, 2));
+    assert!(BitSliced::new(&tree).is_err());
+}
@@ -0,0 +1,145 @@
+// Small random trees and brute force over all their assignments, which the
+// searches are compared against. Each test only uses some of the helpers.
+#![allow(dead_code)]
+
+use std::collections::{BTreeMap, HashSet};
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use puan_eval::propagate::propagate;
+use puan_eval::puan_core::{
+    bic_or_bound, BicOrBound, BinaryInequalityConstraint, Bound, CoefRelation, LinearBoundedTree,
+};
+
+pub fn bound(lower: i64, upper: i64) -> BicOrBound {
+    BicOrBound { part: Some(bic_or_bound::Part::Bound(Bound { lower, upper })) }
+}
+
+pub fn bic(relations: &[(&str, i64)]) -> BicOrBound {
+    BicOrBound {
+        part: Some(bic_or_bound::Part::Bic(BinaryInequalityConstraint {
+            relations: relations.iter()
+                .map(|(id, coefficient)| CoefRelation { id: id.to_string(), coefficient: *coefficient })
+                .collect(),
+        })),
+    }
+}
+
+// A random tree with the roots to search it with
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub tree: LinearBoundedTree,
+    // Empty in about a third of the cases, to search with the nodes no other
+    // node relates to
+    pub roots: Vec<String>,
+}
+
+// Random acyclic trees of up to 7 leaves, most of them [0, 1] and some
+// fixed, and up to 8 BICs relating to leaves and BICs before them, possibly
+// more than once. The roots are mostly BICs, but may be leaves.
+pub fn cases() -> impl Strategy<Value = Case> {
+    let leaves = prop::collection::vec(prop::sample::select(vec![(0, 1), (0, 1), (0, 1), (0, 0), (1, 1)]), 1..8);
+    let bics = prop::collection::vec(prop::collection::vec((any::<Index>(), -3i64..=3), 1..5), 1..9);
+    let roots = prop::collection::vec((any::<Index>(), 0..4), 0..3);
+    (leaves, bics, roots).prop_map(|(leaves, bics, roots)| {
+        let mut tree = LinearBoundedTree::default();
+        for (i, (lower, upper)) in leaves.iter().enumerate() {
+            tree.nodes.insert(format!("leaf{}", i), bound(*lower, *upper));
+        }
+        let count = bics.len();
+        for (i, relations) in bics.into_iter().enumerate() {
+            let relations = relations.into_iter()
+                .map(|(child, coefficient)| CoefRelation { id: id(leaves.len(), child.index(leaves.len() + i)), coefficient })
+                .collect();
+            tree.nodes.insert(format!("bic{}", i), BicOrBound {
+                part: Some(bic_or_bound::Part::Bic(BinaryInequalityConstraint { relations })),
+            });
+        }
+        let roots = roots.into_iter()
+            .map(|(root, kind)| match kind {
+                0 => id(leaves.len(), root.index(leaves.len())),
+                _ => format!("bic{}", root.index(count)),
+            })
+            .collect();
+        Case { tree, roots }
+    })
+}
+
+fn id(leaves: usize, node: usize) -> String {
+    if node < leaves {
+        format!("leaf{}", node)
+    } else {
+        format!("bic{}", node - leaves)
+    }
+}
+
+// Returns the ids of the leaves that are not fixed, sorted
+pub fn free_leaves(tree: &LinearBoundedTree) -> Vec<String> {
+    let mut leaves: Vec<String> = tree.nodes.iter()
+        .filter_map(|(id, node)| match &node.part {
+            Some(bic_or_bound::Part::Bound(bound)) if bound.lower != bound.upper => Some(id.to_string()),
+            _ => None,
+        })
+        .collect();
+    leaves.sort();
+    leaves
+}
+
+// Returns the roots of a case, which are the nodes no other node relates to
+// if none are given
+pub fn roots(case: &Case) -> Vec<String> {
+    if !case.roots.is_empty() {
+        return case.roots.clone();
+    }
+    let related: HashSet<&str> = case.tree.nodes.values()
+        .filter_map(|node| match &node.part {
+            Some(bic_or_bound::Part::Bic(bic)) => Some(bic),
+            _ => None,
+        })
+        .flat_map(|bic| bic.relations.iter().map(|relation| relation.id.as_str()))
+        .collect();
+    case.tree.nodes.keys().filter(|id| !related.contains(id.as_str())).cloned().collect()
+}
+
+// Returns the value of every node of a tree with all free leaves assigned
+pub fn values(tree: &LinearBoundedTree, assignment: &BTreeMap<String, i64>) -> BTreeMap<String, i64> {
+    let mut tree = tree.clone();
+    for (id, value) in assignment.iter() {
+        tree.nodes.insert(id.to_string(), bound(*value, *value));
+    }
+    propagate(&tree).unwrap().nodes.into_iter()
+        .map(|(id, node)| match node.part {
+            Some(bic_or_bound::Part::Bound(bound)) => {
+                assert_eq!(bound.lower, bound.upper, "{} is not fixed by the assignment", id);
+                (id, bound.lower)
+            },
+            _ => panic!("{} was not propagated", id),
+        })
+        .collect()
+}
+
+// Returns true if all roots of a case are 1 under an assignment
+pub fn is_valid(case: &Case, assignment: &BTreeMap<String, i64>) -> bool {
+    let values = values(&case.tree, assignment);
+    roots(case).iter().all(|root| values[root] == 1)
+}
+
+// Returns every assignment of the free leaves that makes all roots 1, with
+// the value of every node under it
+pub fn valid(case: &Case) -> Vec<(BTreeMap<String, i64>, BTreeMap<String, i64>)> {
+    let leaves = free_leaves(&case.tree);
+    let roots = roots(case);
+    (0..1u32 << leaves.len())
+        .map(|bits| {
+            leaves.iter().enumerate()
+                .map(|(i, leaf)| (leaf.to_string(), (bits >> i & 1) as i64))
+                .collect::<BTreeMap<String, i64>>()
+        })
+        .map(|assignment| {
+            let values = values(&case.tree, &assignment);
+            (assignment, values)
+        })
+        .filter(|(_, values)| roots.iter().all(|root| values[root] == 1))
+        .collect()
+}
@@ -0,0 +1,99 @@
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use puan_eval::compiled::{compile, ParallelOptions};
+use puan_eval::propagate::propagate;
+use puan_eval::puan_core::LinearBoundedTree;
+
+mod common;
+
+use common::{bic, bound};
+
+// Random acyclic trees that are wide rather than deep, so that levels hold
+// enough BICs to be split between threads. Leaves may have any small bound,
+// and BICs may relate to a node that is not part of the tree.
+fn wide_trees() -> impl Strategy<Value = LinearBoundedTree> {
+    let leaves = prop::collection::vec((-2i64..=1, 0i64..=2), 1..8);
+    let bics = prop::collection::vec(prop::collection::vec((any::<Index>(), -3i64..=3), 0..5), 1..60);
+    (leaves, bics, any::<bool>()).prop_map(|(leaves, bics, dangling)| {
+        let mut tree = LinearBoundedTree::default();
+        for (i, (lower, width)) in leaves.iter().enumerate() {
+            tree.nodes.insert(format!("leaf{}", i), bound(*lower, lower + width));
+        }
+        for (i, relations) in bics.iter().enumerate() {
+            // Relating mostly to leaves and the first few BICs keeps levels wide
+            let available = leaves.len() + i.min(8) + dangling as usize;
+            let ids: Vec<String> = relations.iter()
+                .map(|(child, _)| match child.index(available) {
+                    child if child < leaves.len() => format!("leaf{}", child),
+                    child if child < leaves.len() + i.min(8) => format!("bic{}", child - leaves.len()),
+                    _ => "gone".to_string(),
+                })
+                .collect();
+            let relations: Vec<(&str, i64)> = ids.iter().zip(relations.iter()).map(|(id, (_, coefficient))| (id.as_str(), *coefficient)).collect();
+            tree.nodes.insert(format!("bic{}", i), bic(&relations));
+        }
+        tree
+    })
+}
+
+proptest! {
+    // Evaluating in parallel gives the same values as evaluating
+    // sequentially, whichever side of the threshold the tree is on and
+    // however the levels are split, and so does propagate
+    #[test]
+    fn parallel_evaluation_equals_sequential(tree in wide_trees(), threshold in 0usize..70, chunk_size in 0usize..4) {
+        let compiled = compile(&tree).unwrap();
+        let mut expected = compiled.initial_values();
+        compiled.evaluate(&mut expected);
+
+        let options = ParallelOptions { threshold, chunk_size };
+        for _ in 0..3 {
+            let mut actual = compiled.initial_values();
+            compiled.evaluate_parallel(&mut actual, &options);
+            prop_assert_eq!(&actual, &expected);
+        }
+        prop_assert_eq!(propagate(&tree).unwrap(), compiled.propagated(&expected, &tree));
+    }
+}
+
+// A tree large enough for the default options to evaluate in parallel
+#[test]
+fn large_tree_is_evaluated_in_parallel_like_sequentially() {
+    let mut tree = LinearBoundedTree::default();
+    let leaves = 1_000;
+    for i in 0..leaves {
+        tree.nodes.insert(format!("leaf{}", i), bound(0, (i % 3) as i64));
+    }
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    for level in 0..3 {
+        for i in 0..50_000 {
+            let relations: Vec<(String, i64)> = (0..3)
+                .map(|_| {
+                    let child = match level {
+                        0 => format!("leaf{}", next() % leaves),
+                        _ => format!("bic{}_{}", level - 1, next() % 50_000),
+                    };
+                    (child, (next() % 7) as i64 - 3)
+                })
+                .collect();
+            let relations: Vec<(&str, i64)> = relations.iter().map(|(id, coefficient)| (id.as_str(), *coefficient)).collect();
+            tree.nodes.insert(format!("bic{}_{}", level, i), bic(&relations));
+        }
+    }
+
+    let compiled = compile(&tree).unwrap();
+    let options = ParallelOptions::default();
+    assert!(compiled.order.len() >= options.threshold);
+    let mut expected = compiled.initial_values();
+    compiled.evaluate(&mut expected);
+    let mut actual = compiled.initial_values();
+    compiled.evaluate_parallel(&mut actual, &options);
+    assert_eq!(actual, expected);
+}
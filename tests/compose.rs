@@ -0,0 +1,102 @@
+use std::collections::HashSet;
+
+use puan_eval::compose::{compose, ComposeOptions, Conflict, Module};
+use puan_eval::puan_core::{BicOrBound, LinearBoundedTree};
+
+mod common;
+
+use common::{bic, bound};
+
+fn tree(nodes: &[(&str, BicOrBound)]) -> LinearBoundedTree {
+    let mut tree = LinearBoundedTree::default();
+    for (id, node) in nodes.iter() {
+        tree.nodes.insert(id.to_string(), node.clone());
+    }
+    tree
+}
+
+fn shared(ids: &[&str]) -> ComposeOptions {
+    ComposeOptions { shared: ids.iter().map(|id| id.to_string()).collect::<HashSet<String>>(), ..Default::default() }
+}
+
+#[test]
+fn defined_ids_are_namespaced_and_references_to_others_are_kept() {
+    let engine = tree(&[("power", bound(0, 1)), ("ok", bic(&[("power", 1), ("wheels.size", -1)]))]);
+    let wheels = tree(&[("size", bound(0, 1))]);
+    let root = tree(&[("root", bic(&[("engine.ok", 1)]))]);
+    let modules = [
+        Module { namespace: "engine".to_string(), tree: &engine },
+        Module { namespace: "wheels".to_string(), tree: &wheels },
+        Module { namespace: String::new(), tree: &root },
+    ];
+
+    let composed = compose(&modules, &ComposeOptions::default()).unwrap();
+    assert_eq!(composed, tree(&[
+        ("engine.power", bound(0, 1)),
+        ("engine.ok", bic(&[("engine.power", 1), ("wheels.size", -1)])),
+        ("wheels.size", bound(0, 1)),
+        ("root", bic(&[("engine.ok", 1)])),
+    ]));
+
+    let options = ComposeOptions { separator: "::".to_string(), ..Default::default() };
+    assert!(compose(&modules, &options).unwrap().nodes.contains_key("engine::power"));
+}
+
+#[test]
+fn shared_ids_are_unified_regardless_of_relation_order() {
+    let first = tree(&[("one", bound(1, 1)), ("rule", bic(&[("a", 1), ("one", -1)])), ("a", bound(0, 1))]);
+    let second = tree(&[("one", bound(1, 1)), ("rule", bic(&[("one", -1), ("a", 1)])), ("a", bound(0, 1))]);
+    let modules = [
+        Module { namespace: "first".to_string(), tree: &first },
+        Module { namespace: "second".to_string(), tree: &second },
+    ];
+
+    let composed = compose(&modules, &shared(&["one", "rule", "a"])).unwrap();
+    assert_eq!(composed.nodes.len(), 3);
+    assert_eq!(composed.nodes["rule"], bic(&[("a", 1), ("one", -1)]));
+
+    // Only the shared ids are unified, the references of the others follow
+    let composed = compose(&modules, &shared(&["one"])).unwrap();
+    let mut ids: Vec<&String> = composed.nodes.keys().collect();
+    ids.sort_unstable();
+    assert_eq!(ids, ["first.a", "first.rule", "one", "second.a", "second.rule"]);
+    assert_eq!(composed.nodes["second.rule"], bic(&[("one", -1), ("second.a", 1)]));
+}
+
+#[test]
+fn all_collisions_and_conflicting_definitions_are_reported() {
+    let first = tree(&[("x", bound(0, 1)), ("one", bound(1, 1)), ("limit", bound(0, 3))]);
+    let second = tree(&[("x", bound(0, 1)), ("one", bound(0, 1)), ("limit", bound(0, 4))]);
+    let plain = tree(&[("a.x", bound(0, 1))]);
+    let modules = [
+        Module { namespace: "a".to_string(), tree: &first },
+        Module { namespace: "a".to_string(), tree: &second },
+        Module { namespace: String::new(), tree: &plain },
+    ];
+
+    let error = compose(&modules, &shared(&["one", "limit"])).unwrap_err();
+    assert_eq!(error.conflicts, vec![
+        Conflict::Definition { id: "limit".to_string(), first: "a".to_string(), second: "a".to_string() },
+        Conflict::Definition { id: "one".to_string(), first: "a".to_string(), second: "a".to_string() },
+        Conflict::Collision { id: "a.x".to_string(), first: "a".to_string(), second: "a".to_string() },
+        Conflict::Collision { id: "a.x".to_string(), first: "a".to_string(), second: String::new() },
+    ]);
+    assert_eq!(error.to_string(), "\
+4 conflicts when composing modules
+  shared id \"limit\" is defined differently by module \"a\" and module \"a\"
+  shared id \"one\" is defined differently by module \"a\" and module \"a\"
+  id \"a.x\" is produced by both module \"a\" and module \"a\"
+  id \"a.x\" is produced by both module \"a\" and module \"\"");
+}
+
+#[test]
+fn shared_id_colliding_with_a_namespaced_id_is_a_collision() {
+    let first = tree(&[("x", bound(0, 1))]);
+    let second = tree(&[("n.x", bound(0, 1))]);
+    let modules = [
+        Module { namespace: "n".to_string(), tree: &first },
+        Module { namespace: "m".to_string(), tree: &second },
+    ];
+    let error = compose(&modules, &shared(&["n.x"])).unwrap_err();
+    assert_eq!(error.conflicts, vec![Conflict::Collision { id: "n.x".to_string(), first: "n".to_string(), second: "m".to_string() }]);
+}
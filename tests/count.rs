@@ -0,0 +1,34 @@
+use std::collections::BTreeSet;
+
+use num_bigint::BigUint;
+use proptest::prelude::*;
+
+use puan_eval::count::{count, CountOptions};
+
+mod common;
+
+use common::{cases, free_leaves, valid};
+
+proptest! {
+    // The count is the number of distinct values of the projected leaves
+    // among all assignments that make the roots 1
+    #[test]
+    fn count_agrees_with_brute_force(case in cases(), mask in any::<u8>()) {
+        let projection: Vec<String> = free_leaves(&case.tree).into_iter()
+            .enumerate()
+            .filter(|(i, _)| mask >> i & 1 == 1)
+            .map(|(_, leaf)| leaf)
+            .collect();
+        let options = CountOptions { roots: case.roots.clone(), projection: projection.clone(), time_limit: None };
+        let counted = count(&case.tree, &options).unwrap().unwrap();
+
+        let expected: BTreeSet<Vec<i64>> = valid(&case).into_iter()
+            .map(|(assignment, _)| if projection.is_empty() {
+                assignment.values().copied().collect()
+            } else {
+                projection.iter().map(|leaf| assignment[leaf]).collect()
+            })
+            .collect();
+        prop_assert_eq!(counted, BigUint::from(expected.len()));
+    }
+}
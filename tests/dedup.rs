@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use puan_eval::dedup::{dedup, Dedup};
+use puan_eval::propagate::propagate;
+use puan_eval::puan_core::{bic_or_bound, Bound, CoefRelation, LinearBoundedTree};
+
+mod common;
+
+use common::{bic, bound, cases, Case};
+
+// Returns the bound of every node, or None for BICs that are not propagated
+fn bounds(tree: &LinearBoundedTree) -> BTreeMap<String, Option<Bound>> {
+    tree.nodes.iter()
+        .map(|(id, node)| match &node.part {
+            Some(bic_or_bound::Part::Bound(bound)) => (id.to_string(), Some(bound.clone())),
+            _ => (id.to_string(), None),
+        })
+        .collect()
+}
+
+// Returns the relations of a BIC with every child resolved through the
+// aliases, sorted
+fn resolved(tree: &LinearBoundedTree, id: &str, dedup: &Dedup) -> Vec<(String, i64)> {
+    let mut relations: Vec<(String, i64)> = match &tree.nodes[id].part {
+        Some(bic_or_bound::Part::Bic(bic)) => bic.relations.iter()
+            .map(|relation| (dedup.resolve(&relation.id).to_string(), relation.coefficient))
+            .collect(),
+        _ => panic!("{} is not a BIC", id),
+    };
+    relations.sort();
+    relations
+}
+
+// A random tree with a copy of every BIC, relating to the copies of its BIC
+// children, so that every copy is merged, and a few BICs relating to nodes
+// that are not part of the tree
+fn trees() -> impl Strategy<Value = LinearBoundedTree> {
+    let open = prop::collection::vec(prop::collection::vec((0..3usize, any::<Index>(), -3i64..=3), 1..4), 0..4);
+    (cases(), open).prop_map(|(Case { mut tree, .. }, open)| {
+        let bics: Vec<String> = tree.nodes.iter()
+            .filter(|(_, node)| matches!(node.part, Some(bic_or_bound::Part::Bic(_))))
+            .map(|(id, _)| id.to_string())
+            .collect();
+        for id in bics.iter() {
+            let mut copy = tree.nodes[id].clone();
+            if let Some(bic_or_bound::Part::Bic(bic)) = &mut copy.part {
+                for relation in bic.relations.iter_mut() {
+                    if relation.id.starts_with("bic") {
+                        relation.id = relation.id.replace("bic", "copy");
+                    }
+                }
+            }
+            tree.nodes.insert(id.replace("bic", "copy"), copy);
+        }
+
+        let ids: Vec<String> = tree.nodes.keys().cloned().collect();
+        for (i, relations) in open.into_iter().enumerate() {
+            let relations: Vec<CoefRelation> = relations.into_iter()
+                .map(|(kind, node, coefficient)| CoefRelation {
+                    id: if kind == 0 { format!("missing{}", node.index(2)) } else { node.get(&ids).to_string() },
+                    coefficient,
+                })
+                .collect();
+            let mut node = bic(&[]);
+            if let Some(bic_or_bound::Part::Bic(bic)) = &mut node.part {
+                bic.relations = relations;
+            }
+            tree.nodes.insert(format!("open{}", i), node);
+        }
+        tree
+    })
+}
+
+proptest! {
+    // Propagating the deduplicated tree and adding the merged nodes back
+    // gives the bounds of propagating the tree itself
+    #[test]
+    fn expanded_propagation_equals_propagation(tree in trees()) {
+        let deduped = dedup(&tree).unwrap();
+        let expanded = deduped.expand(&propagate(&deduped.tree).unwrap());
+        let propagated = propagate(&tree).unwrap();
+        prop_assert_eq!(bounds(&expanded), bounds(&propagated));
+
+        for (id, bound) in bounds(&propagated) {
+            if bound.is_none() {
+                prop_assert_eq!(resolved(&expanded, &id, &deduped), resolved(&propagated, &id, &deduped), "{}", id);
+            }
+        }
+        for id in tree.nodes.keys().filter(|id| id.starts_with("copy")) {
+            let original = id.replace("copy", "bic");
+            prop_assert_eq!(deduped.resolve(id), deduped.resolve(&original));
+            prop_assert!(!deduped.tree.nodes.contains_key(id));
+        }
+        for (alias, id) in deduped.aliases.iter() {
+            prop_assert!(id < alias);
+            prop_assert!(deduped.tree.nodes.contains_key(id));
+            prop_assert!(!deduped.tree.nodes.contains_key(alias));
+        }
+    }
+}
+
+// BICs relating to the same missing child are merged, and to different ones
+// are not. Missing children are not added to the tree.
+#[test]
+fn missing_children_are_compared_by_id() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("a".to_string(), bic(&[("z", 1), ("x", -1)]));
+    tree.nodes.insert("b".to_string(), bic(&[("x", -1), ("z", 1)]));
+    tree.nodes.insert("c".to_string(), bic(&[("y", 1), ("x", -1)]));
+    tree.nodes.insert("d".to_string(), bic(&[("a", 1)]));
+    tree.nodes.insert("e".to_string(), bic(&[("b", 1)]));
+
+    let deduped = dedup(&tree).unwrap();
+    assert_eq!(deduped.aliases, BTreeMap::from([
+        ("b".to_string(), "a".to_string()),
+        ("e".to_string(), "d".to_string()),
+    ]));
+    let mut ids: Vec<&String> = deduped.tree.nodes.keys().collect();
+    ids.sort();
+    assert_eq!(ids, ["a", "c", "d", "x"]);
+    assert_eq!(deduped.tree.nodes["a"], bic(&[("x", -1), ("z", 1)]));
+
+    let expanded = deduped.expand(&propagate(&deduped.tree).unwrap());
+    assert_eq!(expanded.nodes["b"], bic(&[("x", -1), ("z", 1)]));
+    assert_eq!(expanded.nodes["e"], bic(&[("a", 1)]));
+    assert_eq!(bounds(&expanded), bounds(&propagate(&tree).unwrap()));
+}
+
+// Bounds with the same single value are merged, along with the BICs relating
+// to them, while free leaves are never merged
+#[test]
+fn constants_are_merged() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("f".to_string(), bound(1, 1));
+    tree.nodes.insert("g".to_string(), bound(1, 1));
+    tree.nodes.insert("h".to_string(), bound(0, 0));
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("y".to_string(), bound(0, 1));
+    tree.nodes.insert("a".to_string(), bic(&[("g", 1), ("x", -1)]));
+    tree.nodes.insert("b".to_string(), bic(&[("x", -1), ("f", 1)]));
+    tree.nodes.insert("c".to_string(), bic(&[("f", 1), ("y", -1)]));
+    tree.nodes.insert("d".to_string(), bic(&[("h", 1)]));
+
+    let deduped = dedup(&tree).unwrap();
+    assert_eq!(deduped.aliases, BTreeMap::from([
+        ("b".to_string(), "a".to_string()),
+        ("g".to_string(), "f".to_string()),
+    ]));
+    assert_eq!(deduped.resolve("g"), "f");
+    assert_eq!(deduped.resolve("x"), "x");
+    assert_eq!(deduped.tree.nodes["a"], bic(&[("f", 1), ("x", -1)]));
+    assert_eq!(deduped.tree.nodes["c"], bic(&[("f", 1), ("y", -1)]));
+    assert_eq!(deduped.tree.nodes["h"], bound(0, 0));
+
+    let expanded = deduped.expand(&propagate(&deduped.tree).unwrap());
+    assert_eq!(expanded.nodes["g"], bound(1, 1));
+    assert_eq!(bounds(&expanded), bounds(&propagate(&tree).unwrap()));
+}
+
+// Relations to the same child are kept apart rather than summed
+#[test]
+fn repeated_relations_are_not_summed() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("a".to_string(), bic(&[("x", 1), ("x", -1)]));
+    tree.nodes.insert("b".to_string(), bic(&[]));
+
+    let deduped = dedup(&tree).unwrap();
+    assert!(deduped.aliases.is_empty());
+    assert_eq!(deduped.tree.nodes.len(), 3);
+    assert_eq!(deduped.tree.nodes["a"], bic(&[("x", -1), ("x", 1)]));
+}
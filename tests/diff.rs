@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use proptest::prelude::*;
+
+use puan_eval::diff::{diff, diff_propagated, BoundChange, NodeChange, TreeDiff};
+use puan_eval::puan_core::{bic_or_bound, BicOrBound, Bound, LinearBoundedTree};
+
+mod common;
+
+use common::{bic, bound, cases};
+
+fn tree(nodes: &[(&str, BicOrBound)]) -> LinearBoundedTree {
+    let mut tree = LinearBoundedTree::default();
+    for (id, node) in nodes.iter() {
+        tree.nodes.insert(id.to_string(), node.clone());
+    }
+    tree
+}
+
+proptest! {
+    // A tree never differs from itself, nor from itself with the relations
+    // of every BIC reversed
+    #[test]
+    fn reordered_relations_are_no_change(case in cases()) {
+        let mut reversed = case.tree.clone();
+        for node in reversed.nodes.values_mut() {
+            if let Some(bic_or_bound::Part::Bic(bic)) = &mut node.part {
+                bic.relations.reverse();
+            }
+        }
+        prop_assert!(diff(&case.tree, &case.tree).is_empty());
+        prop_assert!(diff(&case.tree, &reversed).is_empty());
+        prop_assert!(diff_propagated(&case.tree, &reversed, &HashMap::new()).unwrap().is_empty());
+    }
+}
+
+#[test]
+fn added_removed_and_changed_nodes_are_sorted() {
+    let before = tree(&[("x", bound(0, 1)), ("y", bound(0, 1)), ("gone", bound(0, 0)), ("k", bound(0, 1))]);
+    let after = tree(&[("x", bound(0, 2)), ("y", bound(0, 1)), ("b", bic(&[])), ("a", bic(&[])), ("k", bic(&[("x", 1)]))]);
+    let result = diff(&before, &after);
+    assert_eq!(result, TreeDiff {
+        added: vec!["a".to_string(), "b".to_string()],
+        removed: vec!["gone".to_string()],
+        changed: vec![
+            ("k".to_string(), NodeChange::Kind { before: bound(0, 1), after: bic(&[("x", 1)]) }),
+            ("x".to_string(), NodeChange::Bound { before: Bound { lower: 0, upper: 1 }, after: Bound { lower: 0, upper: 2 } }),
+        ],
+    });
+    assert_eq!(result.to_string(), "+ a\n+ b\n- gone\n~ k: bound [0, 1] -> bic\n~ x: bound [0, 1] -> [0, 2]\n");
+}
+
+#[test]
+fn relations_are_compared_as_multisets() {
+    let before = tree(&[("b", bic(&[("x", 1), ("x", 2), ("y", 3), ("z", -1)]))]);
+
+    // Reordering is no change
+    let reordered = tree(&[("b", bic(&[("z", -1), ("x", 2), ("y", 3), ("x", 1)]))]);
+    assert!(diff(&before, &reordered).is_empty());
+
+    // Duplicates summing to the same are still a change
+    let summed = tree(&[("b", bic(&[("x", 3), ("y", 3), ("z", -1)]))]);
+    assert_eq!(diff(&before, &summed).changed, vec![("b".to_string(), NodeChange::Relations {
+        added: vec![("x".to_string(), 3)],
+        removed: vec![("x".to_string(), 1), ("x".to_string(), 2)],
+        coefficients: vec![],
+    })]);
+
+    // A single relation changed on each side is a changed coefficient, and
+    // the duplicates in common are left out
+    let changed = tree(&[("b", bic(&[("x", 1), ("x", 5), ("y", 4), ("w", 1)]))]);
+    let result = diff(&before, &changed);
+    assert_eq!(result.changed, vec![("b".to_string(), NodeChange::Relations {
+        added: vec![("w".to_string(), 1)],
+        removed: vec![("z".to_string(), -1)],
+        coefficients: vec![("x".to_string(), 2, 5), ("y".to_string(), 3, 4)],
+    })]);
+    assert_eq!(result.to_string(), "~ b:\n    + 1 * w\n    - -1 * z\n    ~ x: 2 -> 5\n    ~ y: 3 -> 4\n");
+}
+
+#[test]
+fn json_has_one_key_per_kind_of_change() {
+    let before = tree(&[("b", bic(&[("x", 1)])), ("x", bound(0, 1))]);
+    let after = tree(&[("b", bic(&[("x", 2)])), ("x", bound(1, 1)), ("y", bound(0, 1))]);
+    let json = diff(&before, &after).to_json();
+    assert_eq!(json["added"], serde_json::json!(["y"]));
+    assert_eq!(json["changed"][0]["change"], "relations");
+    assert_eq!(json["changed"][0]["coefficients"][0], serde_json::json!({"id": "x", "before": 1, "after": 2}));
+    assert_eq!(json["changed"][1]["after"], serde_json::json!({"lower": 1, "upper": 1}));
+}
+
+#[test]
+fn propagated_diff_compares_bounds_under_the_same_assignment() {
+    let before = tree(&[("x", bound(0, 1)), ("y", bound(0, 1)), ("b", bic(&[("x", 1), ("y", 1), ("one", -1)])), ("one", bound(1, 1))]);
+    let after = tree(&[("x", bound(0, 1)), ("y", bound(0, 1)), ("b", bic(&[("x", 1), ("y", 1), ("one", -2)])), ("one", bound(1, 1))]);
+
+    let assignment = HashMap::from([("x".to_string(), Bound { lower: 1, upper: 1 }), ("gone".to_string(), Bound { lower: 0, upper: 0 })]);
+    assert_eq!(diff_propagated(&before, &after, &assignment).unwrap(), vec![BoundChange {
+        id: "b".to_string(),
+        before: Some(Bound { lower: 1, upper: 1 }),
+        after: Some(Bound { lower: 0, upper: 1 }),
+    }]);
+
+    let assignment = HashMap::from([("x".to_string(), Bound { lower: 1, upper: 1 }), ("y".to_string(), Bound { lower: 1, upper: 1 })]);
+    assert!(diff_propagated(&before, &after, &assignment).unwrap().is_empty());
+}
@@ -0,0 +1,72 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use puan_eval::enumerate::{enumerate, EnumerateOptions};
+use puan_eval::puan_core::{bic_or_bound, LinearBoundedTree};
+
+mod common;
+
+use common::{bic, bound, cases, valid};
+
+proptest! {
+    // Every valid assignment is produced exactly once, projected onto the
+    // chosen leaves if any, which may be fixed
+    #[test]
+    fn enumerate_agrees_with_brute_force(case in cases(), projected in prop::collection::vec(any::<Index>(), 0..3)) {
+        let leaves: Vec<&String> = case.tree.nodes.iter()
+            .filter(|(_, node)| matches!(node.part, Some(bic_or_bound::Part::Bound(_))))
+            .map(|(id, _)| id)
+            .collect();
+        let projection: Vec<String> = projected.iter().map(|leaf| leaf.get(&leaves).to_string()).collect();
+        let expected: BTreeSet<BTreeMap<String, i64>> = valid(&case).into_iter()
+            .map(|(assignment, values)| if projection.is_empty() {
+                assignment
+            } else {
+                values.into_iter().filter(|(leaf, _)| projection.contains(leaf)).collect()
+            })
+            .collect();
+
+        let options = EnumerateOptions { roots: case.roots.clone(), projection, ..Default::default() };
+        let solutions: Vec<BTreeMap<String, i64>> = enumerate(&case.tree, &options).unwrap().collect();
+        prop_assert_eq!(solutions.len(), expected.len());
+        prop_assert_eq!(solutions.into_iter().collect::<BTreeSet<_>>(), expected.clone());
+
+        let limited = enumerate(&case.tree, &EnumerateOptions { limit: Some(2), ..options }).unwrap();
+        prop_assert_eq!(limited.count(), expected.len().min(2));
+    }
+
+    // A cancelled enumeration stops before producing anything, as if the
+    // time limit was reached, unless it finds that there is nothing to
+    // produce first
+    #[test]
+    fn cancelled_enumeration_stops(case in cases()) {
+        let options = EnumerateOptions { roots: case.roots.clone(), ..Default::default() };
+        let mut solutions = enumerate(&case.tree, &options).unwrap().cancel_when(|| true);
+        prop_assert_eq!(solutions.next(), None);
+        prop_assert!(solutions.timed_out() || valid(&case).is_empty());
+    }
+}
+
+// A projection onto fixed leaves only has a single solution
+#[test]
+fn projection_onto_fixed_leaves_ends() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("f".to_string(), bound(1, 1));
+    tree.nodes.insert("b".to_string(), bic(&[("x", 1), ("f", 1)]));
+
+    let options = EnumerateOptions { projection: vec!["f".to_string()], ..Default::default() };
+    let mut solutions = enumerate(&tree, &options).unwrap();
+    assert_eq!(solutions.next(), Some(BTreeMap::from([("f".to_string(), 1)])));
+    assert_eq!(solutions.next(), None);
+    assert!(!solutions.timed_out());
+
+    let options = EnumerateOptions { projection: vec!["f".to_string(), "x".to_string()], ..Default::default() };
+    let solutions: BTreeSet<BTreeMap<String, i64>> = enumerate(&tree, &options).unwrap().collect();
+    assert_eq!(solutions, BTreeSet::from([
+        BTreeMap::from([("f".to_string(), 1), ("x".to_string(), 0)]),
+        BTreeMap::from([("f".to_string(), 1), ("x".to_string(), 1)]),
+    ]));
+}
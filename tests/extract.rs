@@ -0,0 +1,88 @@
+use std::collections::BTreeSet;
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use puan_eval::error::LbtError;
+use puan_eval::extract::{ancestors, extract};
+use puan_eval::propagate::propagate;
+use puan_eval::puan_core::LinearBoundedTree;
+
+mod common;
+
+use common::{bic, bound, cases};
+
+fn tree() -> LinearBoundedTree {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("y".to_string(), bound(0, 1));
+    tree.nodes.insert("z".to_string(), bound(1, 1));
+    tree.nodes.insert("a".to_string(), bic(&[("x", 1), ("y", 1), ("gone", 1)]));
+    tree.nodes.insert("b".to_string(), bic(&[("a", 1), ("z", -1)]));
+    tree.nodes.insert("c".to_string(), bic(&[("y", 1)]));
+    tree
+}
+
+fn ids(tree: &LinearBoundedTree) -> BTreeSet<&str> {
+    tree.nodes.keys().map(|id| id.as_str()).collect()
+}
+
+fn strings(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+proptest! {
+    // The extracted tree holds every descendant of the roots, each node as
+    // it is in the tree, so the roots propagate the same
+    #[test]
+    fn extract_keeps_the_descendants_of_the_roots(case in cases(), picked in prop::collection::vec(any::<Index>(), 1..3)) {
+        let all: Vec<String> = case.tree.nodes.keys().cloned().collect();
+        let roots: Vec<String> = picked.iter().map(|root| all[root.index(all.len())].to_string()).collect();
+        let extracted = extract(&case.tree, &roots).unwrap();
+
+        for (id, node) in extracted.nodes.iter() {
+            prop_assert_eq!(node, &case.tree.nodes[id]);
+        }
+        let propagated = propagate(&case.tree).unwrap();
+        let extracted_propagated = propagate(&extracted).unwrap();
+        for id in extracted.nodes.keys() {
+            prop_assert_eq!(&extracted_propagated.nodes[id], &propagated.nodes[id]);
+        }
+
+        // Every node is an ancestor of the nodes it extracts, and of nothing else
+        let found = ancestors(&case.tree, &roots).unwrap();
+        let expected: BTreeSet<String> = all.iter()
+            .filter(|node| {
+                let below = extract(&case.tree, &[node.to_string()]).unwrap();
+                roots.iter().any(|root| root != *node && below.nodes.contains_key(root))
+            })
+            .cloned()
+            .collect();
+        prop_assert_eq!(found, expected);
+    }
+}
+
+#[test]
+fn extract_follows_relations_down_and_skips_missing_children() {
+    assert_eq!(ids(&extract(&tree(), &strings(&["b"])).unwrap()), BTreeSet::from(["a", "b", "x", "y", "z"]));
+    assert_eq!(ids(&extract(&tree(), &strings(&["a", "c"])).unwrap()), BTreeSet::from(["a", "c", "x", "y"]));
+    assert_eq!(ids(&extract(&tree(), &strings(&["z"])).unwrap()), BTreeSet::from(["z"]));
+    assert!(extract(&tree(), &[]).unwrap().nodes.is_empty());
+}
+
+#[test]
+fn ancestors_follow_relations_up() {
+    let found = ancestors(&tree(), &strings(&["y"])).unwrap();
+    assert_eq!(found, BTreeSet::from(["a".to_string(), "b".to_string(), "c".to_string()]));
+
+    // A given node is only part of the result if another one affects it
+    let found = ancestors(&tree(), &strings(&["a", "x"])).unwrap();
+    assert_eq!(found, BTreeSet::from(["a".to_string(), "b".to_string()]));
+    assert!(ancestors(&tree(), &strings(&["b"])).unwrap().is_empty());
+}
+
+#[test]
+fn unknown_ids_are_rejected() {
+    assert_eq!(extract(&tree(), &strings(&["b", "gone"])), Err(LbtError::UnknownNode("gone".to_string())));
+    assert_eq!(ancestors(&tree(), &strings(&["gone"])), Err(LbtError::UnknownNode("gone".to_string())));
+}
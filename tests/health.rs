@@ -0,0 +1,60 @@
+use std::collections::BTreeMap;
+
+use proptest::prelude::*;
+
+use puan_eval::health::{health, HealthOptions};
+use puan_eval::puan_core::{bic_or_bound, LinearBoundedTree};
+
+mod common;
+
+use common::{bic, bound, cases, free_leaves, roots, valid};
+
+// A root leaf can be what makes another node dead, so it has to be assumed
+// when explaining it
+#[test]
+fn node_dead_because_of_root_leaf_is_explained() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("y".to_string(), bound(0, 1));
+    tree.nodes.insert("one".to_string(), bound(1, 1));
+    tree.nodes.insert("b".to_string(), bic(&[("x", -1), ("y", -1), ("one", 1)]));
+
+    let options = HealthOptions { roots: vec!["x".to_string(), "b".to_string()], time_limit: None };
+    let health = health(&tree, &options).unwrap().unwrap();
+    assert!(health.complete);
+    assert_eq!(health.dead, BTreeMap::from([("y".to_string(), Some(vec!["b".to_string(), "x".to_string()]))]));
+    assert!(health.mandatory.is_empty());
+}
+
+proptest! {
+    // The dead and mandatory nodes are the BICs and free leaves, other than
+    // the roots, that are 0 or 1 in all assignments that make the roots 1,
+    // and each of them is explained
+    #[test]
+    fn health_agrees_with_brute_force(case in cases()) {
+        let options = HealthOptions { roots: case.roots.clone(), time_limit: None };
+        let found = health(&case.tree, &options).unwrap();
+
+        let valid = valid(&case);
+        if valid.is_empty() {
+            prop_assert_eq!(found, None);
+            return Ok(());
+        }
+        let found = found.unwrap();
+        prop_assert!(found.complete);
+        prop_assert!(found.dead.values().chain(found.mandatory.values()).all(|responsible| responsible.is_some()));
+
+        let roots = roots(&case);
+        let leaves = free_leaves(&case.tree);
+        let decided: Vec<(String, i64)> = valid[0].1.iter()
+            .filter(|(id, _)| !roots.contains(id))
+            .filter(|(id, _)| leaves.contains(id) || matches!(case.tree.nodes[*id].part, Some(bic_or_bound::Part::Bic(_))))
+            .filter(|(id, value)| valid.iter().all(|(_, values)| values[*id] == **value))
+            .map(|(id, value)| (id.to_string(), *value))
+            .collect();
+        let dead: Vec<&String> = decided.iter().filter(|(_, value)| *value == 0).map(|(id, _)| id).collect();
+        let mandatory: Vec<&String> = decided.iter().filter(|(_, value)| *value == 1).map(|(id, _)| id).collect();
+        prop_assert_eq!(found.dead.keys().collect::<Vec<_>>(), dead);
+        prop_assert_eq!(found.mandatory.keys().collect::<Vec<_>>(), mandatory);
+    }
+}
@@ -0,0 +1,126 @@
+use std::io::{self, Cursor};
+
+use prost::Message;
+use proptest::prelude::*;
+use tonic::codec::{Codec, ProstCodec, Streaming};
+use tonic::transport::Body;
+
+use puan_eval::io::{from_text_format, read_file, to_text_format, write_delimited, write_file, DelimitedReader, Framing};
+use puan_eval::puan_core::LinearBoundedTree;
+
+mod common;
+
+use common::{bic, bound, cases};
+
+// A tree with ids that need escaping in the text format
+fn escaped() -> LinearBoundedTree {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("å \"quoted\"".to_string(), bound(0, 1));
+    tree.nodes.insert("back\\slash\ttab".to_string(), bound(-3, 3));
+    tree.nodes.insert("multi\nline\r\n".to_string(), bic(&[("å \"quoted\"", -2), ("back\\slash\ttab", 1), ("gone ✓", 1)]));
+    tree
+}
+
+fn read_all(bytes: &[u8], framing: Framing) -> Vec<io::Result<LinearBoundedTree>> {
+    DelimitedReader::new(Cursor::new(bytes.to_vec()), framing).collect()
+}
+
+proptest! {
+    #[test]
+    fn delimited_streams_round_trip(trees in prop::collection::vec(cases(), 0..4)) {
+        let trees: Vec<LinearBoundedTree> = trees.into_iter().map(|case| case.tree).collect();
+        for framing in [Framing::Varint, Framing::Grpc] {
+            let mut bytes: Vec<u8> = Vec::new();
+            write_delimited(&mut bytes, trees.iter(), framing).unwrap();
+            let read: Vec<LinearBoundedTree> = read_all(&bytes, framing).into_iter().map(|tree| tree.unwrap()).collect();
+            prop_assert_eq!(&read, &trees);
+        }
+    }
+
+    #[test]
+    fn text_format_round_trips(case in cases()) {
+        prop_assert_eq!(from_text_format(&to_text_format(&case.tree)).unwrap(), case.tree);
+    }
+}
+
+#[test]
+fn varint_framing_matches_prost() {
+    let tree = escaped();
+    let mut bytes: Vec<u8> = Vec::new();
+    write_delimited(&mut bytes, [&tree, &tree], Framing::Varint).unwrap();
+    assert_eq!(bytes, [tree.encode_length_delimited_to_vec(), tree.encode_length_delimited_to_vec()].concat());
+}
+
+// The stream written with the gRPC framing is read back by the decoder the
+// PropagateLbtStreamed handler receives its requests through
+#[tokio::test]
+async fn grpc_framing_is_read_by_tonic() {
+    let trees = vec![escaped(), LinearBoundedTree::default(), escaped()];
+    let mut bytes: Vec<u8> = Vec::new();
+    write_delimited(&mut bytes, trees.iter(), Framing::Grpc).unwrap();
+
+    let decoder = ProstCodec::<LinearBoundedTree, LinearBoundedTree>::default().decoder();
+    let mut stream = Streaming::new_request(decoder, Body::from(bytes), None, None);
+    let mut read: Vec<LinearBoundedTree> = Vec::new();
+    while let Some(tree) = stream.message().await.unwrap() {
+        read.push(tree);
+    }
+    assert_eq!(read, trees);
+}
+
+#[test]
+fn truncated_streams_fail_once() {
+    let tree = escaped();
+    for framing in [Framing::Varint, Framing::Grpc] {
+        let mut bytes: Vec<u8> = Vec::new();
+        write_delimited(&mut bytes, [&tree, &tree], framing).unwrap();
+        let whole = bytes.len() / 2;
+        // Cut inside the second length prefix and inside the second message
+        for end in [whole + 1, bytes.len() - 1] {
+            let read = read_all(&bytes[..end], framing);
+            assert_eq!(read.len(), 2, "{:?} cut at {}", framing, end);
+            assert_eq!(read[0].as_ref().unwrap(), &tree);
+            assert_eq!(read[1].as_ref().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        }
+        assert!(read_all(&[], framing).is_empty());
+    }
+}
+
+#[test]
+fn compressed_grpc_messages_are_rejected() {
+    let mut bytes: Vec<u8> = Vec::new();
+    write_delimited(&mut bytes, [&escaped()], Framing::Grpc).unwrap();
+    bytes[0] = 1;
+    let read = read_all(&bytes, Framing::Grpc);
+    assert_eq!(read.len(), 1);
+    assert_eq!(read[0].as_ref().unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn overlong_varint_is_rejected() {
+    let read = read_all(&[0xff; 11], Framing::Varint);
+    assert_eq!(read.len(), 1);
+    assert_eq!(read[0].as_ref().unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn escaped_ids_round_trip_through_text_format() {
+    let tree = escaped();
+    let text = to_text_format(&tree);
+    assert!(text.is_ascii());
+    assert_eq!(from_text_format(&text).unwrap(), tree);
+}
+
+#[test]
+fn files_are_read_in_the_format_of_their_extension() {
+    let tree = escaped();
+    for extension in ["txtpb", "textproto", "pbtxt", "pb"] {
+        let path = std::env::temp_dir().join(format!("puan-eval-io-{}.{}", std::process::id(), extension));
+        write_file(&path, &tree).unwrap();
+        let text = std::fs::read(&path).unwrap().starts_with(b"nodes {");
+        let read = read_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.unwrap(), tree);
+        assert_eq!(text, extension != "pb");
+    }
+}
@@ -0,0 +1,112 @@
+use std::path::PathBuf;
+
+use puan_eval::compiled::compile;
+use puan_eval::model::{to_bytes, MappedModel, HEADER_SIZE};
+use puan_eval::puan_core::{
+    bic_or_bound, BicOrBound, BinaryInequalityConstraint, Bound, CoefRelation, LinearBoundedTree,
+};
+
+fn bound(lower: i64, upper: i64) -> BicOrBound {
+    BicOrBound { part: Some(bic_or_bound::Part::Bound(Bound { lower, upper })) }
+}
+
+fn bic(relations: &[(&str, i64)]) -> BicOrBound {
+    BicOrBound {
+        part: Some(bic_or_bound::Part::Bic(BinaryInequalityConstraint {
+            relations: relations.iter()
+                .map(|(id, coefficient)| CoefRelation { id: id.to_string(), coefficient: *coefficient })
+                .collect(),
+        })),
+    }
+}
+
+// A small tree with ids that are more than one byte long in utf-8
+fn tree() -> LinearBoundedTree {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("å".to_string(), bound(0, 1));
+    tree.nodes.insert("ö".to_string(), bound(1, 1));
+    tree.nodes.insert("and".to_string(), bic(&[("å", 1), ("ö", 1), ("one", -2)]));
+    tree.nodes.insert("one".to_string(), bound(1, 1));
+    tree.nodes.insert("root".to_string(), bic(&[("and", 1), ("ö", -1)]));
+    tree
+}
+
+// Where each array of a valid model starts, found from where its slices are
+// in the mapped file
+struct Positions {
+    name_offsets: usize,
+    offsets: usize,
+    children: usize,
+    order: usize,
+}
+
+fn path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("puan-eval-{}-{}.lbtm", std::process::id(), name))
+}
+
+fn open(name: &str, bytes: &[u8]) -> Result<MappedModel, puan_eval::error::LbtError> {
+    let path = path(name);
+    std::fs::write(&path, bytes).unwrap();
+    let model = MappedModel::open(&path);
+    std::fs::remove_file(&path).unwrap();
+    model
+}
+
+fn positions(bytes: &[u8]) -> Positions {
+    let model = open("positions", bytes).unwrap();
+    let view = model.view();
+    let start = view.lower.as_ptr() as usize - HEADER_SIZE;
+    Positions {
+        name_offsets: view.name_offsets.as_ptr() as usize - start,
+        offsets: view.offsets.as_ptr() as usize - start,
+        children: view.children.as_ptr() as usize - start,
+        order: view.order.as_ptr() as usize - start,
+    }
+}
+
+// Writes a u32 into a model and recomputes both checksums, so that only the
+// checks of the contents can find the change
+fn corrupt(bytes: &[u8], at: usize, value: u32) -> Vec<u8> {
+    let mut bytes = bytes.to_vec();
+    bytes[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    let payload = crc32fast::hash(&bytes[HEADER_SIZE..]);
+    bytes[56..60].copy_from_slice(&payload.to_le_bytes());
+    let header = crc32fast::hash(&bytes[..60]);
+    bytes[60..64].copy_from_slice(&header.to_le_bytes());
+    bytes
+}
+
+#[test]
+fn valid_model_evaluates_like_compiled_tree() {
+    let compiled = compile(&tree()).unwrap();
+    let model = open("valid", &to_bytes(&compiled)).unwrap();
+    let view = model.view();
+    assert_eq!(view.to_compiled(), compiled);
+
+    let mut expected = compiled.initial_values();
+    compiled.evaluate(&mut expected);
+    let mut actual = view.initial_values();
+    view.evaluate(&mut actual);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn checksummed_model_with_bad_contents_is_rejected() {
+    let compiled = compile(&tree()).unwrap();
+    let bytes = to_bytes(&compiled);
+    let positions = positions(&bytes);
+    let nodes = compiled.len() as u32;
+    let first = compiled.name_offsets[..compiled.len()].iter()
+        .position(|offset| compiled.names[*offset as usize..].starts_with('å'))
+        .unwrap();
+
+    let corrupted = [
+        ("child", corrupt(&bytes, positions.children, nodes)),
+        ("order", corrupt(&bytes, positions.order, u32::MAX)),
+        ("offsets", corrupt(&bytes, positions.offsets, u32::MAX)),
+        ("name_offsets", corrupt(&bytes, positions.name_offsets + 4 * first, compiled.name_offsets[first] + 1)),
+    ];
+    for (name, bytes) in corrupted.iter() {
+        assert!(open(name, bytes).is_err(), "{} was not rejected", name);
+    }
+}
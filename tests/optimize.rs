@@ -0,0 +1,74 @@
+use std::collections::{BTreeMap, HashMap};
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use puan_eval::error::LbtError;
+use puan_eval::optimize::{optimize, OptimizeOptions, OptimizeStatus, Sense};
+use puan_eval::puan_core::LinearBoundedTree;
+
+mod common;
+
+use common::{bound, cases, is_valid, valid, values};
+
+proptest! {
+    // The optimum is the best objective over all assignments that make the
+    // roots 1, and the assignment found has it
+    #[test]
+    fn optimize_agrees_with_brute_force(
+        case in cases(),
+        objective in prop::collection::vec((any::<Index>(), -5i64..=5), 0..6),
+        maximize in any::<bool>(),
+    ) {
+        let ids: Vec<String> = case.tree.nodes.keys().cloned().collect();
+        let mut coefficients: HashMap<String, i64> = HashMap::new();
+        for (node, coefficient) in objective {
+            *coefficients.entry(ids[node.index(ids.len())].to_string()).or_default() += coefficient;
+        }
+        let sense = if maximize { Sense::Maximize } else { Sense::Minimize };
+        let options = OptimizeOptions { roots: case.roots.clone(), objective: coefficients.clone(), sense, time_limit: None };
+        let optimum = optimize(&case.tree, &options).unwrap();
+
+        let value = |values: &BTreeMap<String, i64>| -> i64 {
+            coefficients.iter().map(|(id, coefficient)| coefficient * values[id]).sum()
+        };
+        let objectives = valid(&case).into_iter().map(|(_, values)| value(&values));
+        let expected = if maximize { objectives.max() } else { objectives.min() };
+        match expected {
+            None => {
+                prop_assert_eq!(optimum.status, OptimizeStatus::Infeasible);
+                prop_assert_eq!(optimum.value, None);
+            },
+            Some(expected) => {
+                prop_assert_eq!(optimum.status, OptimizeStatus::Optimal);
+                prop_assert_eq!(optimum.value, Some(expected));
+                prop_assert_eq!(optimum.bound, expected);
+                prop_assert!(is_valid(&case, &optimum.model), "{:?}", optimum.model);
+                prop_assert_eq!(value(&values(&case.tree, &optimum.model)), expected);
+            },
+        }
+    }
+}
+
+#[test]
+fn objective_that_overflows_is_rejected() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("a".to_string(), bound(0, 1));
+    tree.nodes.insert("b".to_string(), bound(0, 1));
+    tree.nodes.insert("c".to_string(), bound(2, 2));
+
+    let objectives = [
+        vec![("a", i64::MAX), ("b", i64::MAX)],
+        vec![("a", i64::MIN)],
+        vec![("c", i64::MAX)],
+    ];
+    for (objective, sense) in objectives.iter().flat_map(|objective| [(objective, Sense::Minimize), (objective, Sense::Maximize)]) {
+        let options = OptimizeOptions {
+            roots: vec!["a".to_string()],
+            objective: objective.iter().map(|(id, coefficient)| (id.to_string(), *coefficient)).collect(),
+            sense,
+            time_limit: None,
+        };
+        assert!(matches!(optimize(&tree, &options), Err(LbtError::Malformed(_))), "{:?} {:?}", objective, sense);
+    }
+}
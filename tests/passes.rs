@@ -0,0 +1,130 @@
+use std::collections::{BTreeMap, HashSet};
+
+use proptest::prelude::*;
+
+use puan_eval::dedup::expand_in_place;
+use puan_eval::error::LbtError;
+use puan_eval::passes::{Aliases, PassManager};
+use puan_eval::propagate::propagate;
+use puan_eval::puan_core::{bic_or_bound, Bound, LinearBoundedTree};
+
+mod common;
+
+use common::{bic, bound, cases, free_leaves, values};
+
+// Runs a pipeline and propagates the result the way the server does: merged
+// nodes are added back and nodes the pipeline added are removed
+fn pipelined(pipeline: &PassManager, tree: &LinearBoundedTree) -> (LinearBoundedTree, Aliases) {
+    let ids: HashSet<&String> = tree.nodes.keys().collect();
+    let (transformed, aliases, stats) = pipeline.run(tree.clone()).unwrap();
+    assert_eq!(stats.iter().map(|stats| stats.name.as_str()).collect::<Vec<&str>>(), pipeline.names());
+    let mut propagated = propagate(&transformed).unwrap();
+    expand_in_place(&mut propagated, &aliases);
+    propagated.nodes.retain(|id, _| ids.contains(id));
+    (propagated, aliases)
+}
+
+// Returns the bound of every node, or None for BICs that are not propagated
+fn bounds(tree: &LinearBoundedTree) -> BTreeMap<String, Option<Bound>> {
+    tree.nodes.iter()
+        .map(|(id, node)| match &node.part {
+            Some(bic_or_bound::Part::Bound(bound)) => (id.to_string(), Some(bound.clone())),
+            _ => (id.to_string(), None),
+        })
+        .collect()
+}
+
+proptest! {
+    // Propagating through a pipeline keeps the ids of the tree. Without
+    // simplify the bounds are those of propagating without a pipeline. With
+    // it they may be tighter, since simplify sums relations to the same
+    // child, but they still hold the value of every node under every
+    // assignment.
+    #[test]
+    fn pipelines_keep_ids_and_bounds(case in cases(), pipeline in prop::sample::select(vec!["none", "compact", "strict", "validate,dedup", "dedup,propagate,dedup", "propagate,simplify"])) {
+        let manager = PassManager::parse(pipeline).unwrap();
+        let (propagated, _) = pipelined(&manager, &case.tree);
+        let expected = bounds(&propagate(&case.tree).unwrap());
+        let bounds = bounds(&propagated);
+        prop_assert_eq!(bounds.keys().collect::<Vec<&String>>(), expected.keys().collect::<Vec<&String>>());
+        if !manager.names().contains(&"simplify") {
+            prop_assert_eq!(&bounds, &expected);
+        }
+
+        for (id, bound) in bounds.iter() {
+            let (bound, expected) = (bound.as_ref().unwrap(), expected[id].as_ref().unwrap());
+            prop_assert!(expected.lower <= bound.lower && bound.upper <= expected.upper, "{}: {:?} is not within {:?}", id, bound, expected);
+        }
+        let leaves = free_leaves(&case.tree);
+        for bits in 0..1u32 << leaves.len() {
+            let assignment = leaves.iter().enumerate().map(|(i, leaf)| (leaf.to_string(), (bits >> i & 1) as i64)).collect();
+            for (id, value) in values(&case.tree, &assignment) {
+                let bound = bounds[&id].as_ref().unwrap();
+                prop_assert!(bound.lower <= value && value <= bound.upper, "{} is {} outside {:?}", id, value, bound);
+            }
+        }
+    }
+}
+
+#[test]
+fn pipelines_are_parsed() {
+    assert!(PassManager::parse("none").unwrap().is_empty());
+    assert!(PassManager::parse("").unwrap().is_empty());
+    assert_eq!(PassManager::parse("compact").unwrap().names(), ["simplify", "dedup"]);
+    assert_eq!(PassManager::parse("strict").unwrap().names(), ["validate", "simplify", "dedup"]);
+    assert_eq!(PassManager::parse(" validate , propagate,").unwrap().names(), ["validate", "propagate"]);
+}
+
+#[test]
+fn unknown_passes_are_rejected() {
+    for (name, pass) in [("validate,fold", "fold"), ("extract", "extract"), ("Compact", "Compact"), ("dedup;propagate", "dedup;propagate")] {
+        match PassManager::parse(name) {
+            Err(LbtError::Malformed(message)) => assert_eq!(message, format!("unknown pass {}", pass)),
+            other => panic!("{} was parsed: {:?}", name, other.map(|manager| manager.names().join(","))),
+        }
+    }
+}
+
+// A node merged by the first dedup is merged again by the second, through
+// the node it was merged into
+#[test]
+fn aliases_follow_later_merges() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("f".to_string(), bound(1, 1));
+    tree.nodes.insert("a".to_string(), bic(&[("f", 2)]));
+    tree.nodes.insert("b".to_string(), bic(&[("f", 1)]));
+    tree.nodes.insert("c".to_string(), bic(&[("f", 1)]));
+
+    let pipeline = PassManager::parse("dedup,propagate,dedup").unwrap();
+    let (propagated, aliases) = pipelined(&pipeline, &tree);
+    assert_eq!(aliases, Aliases::from([
+        ("b".to_string(), "a".to_string()),
+        ("c".to_string(), "a".to_string()),
+        ("f".to_string(), "a".to_string()),
+    ]));
+    assert_eq!(bounds(&propagated), bounds(&propagate(&tree).unwrap()));
+    assert_eq!(propagated.nodes["c"], bound(1, 1));
+}
+
+// The bias node simplify adds is not part of the result, even if the tree
+// already uses the bias id
+#[test]
+fn added_nodes_are_removed() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("f".to_string(), bound(1, 1));
+    tree.nodes.insert("bias".to_string(), bic(&[("x", 1), ("f", -1)]));
+    tree.nodes.insert("a".to_string(), bic(&[("bias", 1), ("f", 1)]));
+
+    let (propagated, _) = pipelined(&PassManager::parse("strict").unwrap(), &tree);
+    assert_eq!(bounds(&propagated), bounds(&propagate(&tree).unwrap()));
+}
+
+#[test]
+fn validate_rejects_unknown_children() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("a".to_string(), bic(&[("z", 1)]));
+
+    assert!(matches!(PassManager::parse("strict").unwrap().run(tree.clone()), Err(LbtError::UnknownNode(id)) if id == "z"));
+    assert!(PassManager::parse("compact").unwrap().run(tree).is_ok());
+}
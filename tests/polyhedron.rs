@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+use proptest::prelude::*;
+
+use puan_eval::error::LbtError;
+use puan_eval::polyhedron::{from_polyhedron, to_polyhedron, CsrMatrix, Polyhedron};
+use puan_eval::puan_core::{bic_or_bound, Bound, LinearBoundedTree};
+
+mod common;
+
+use common::{bic, bound, cases};
+
+// What the polyhedron keeps of a node: the bound of a Bound, or the summed
+// non-zero coefficients of a BIC
+#[derive(Debug, PartialEq, Eq)]
+enum Part {
+    Bound(i64, i64),
+    Bic(BTreeMap<String, i64>),
+}
+
+fn normalised(tree: &LinearBoundedTree) -> BTreeMap<String, Part> {
+    tree.nodes.iter()
+        .map(|(id, node)| {
+            let part = match &node.part {
+                Some(bic_or_bound::Part::Bound(bound)) => Part::Bound(bound.lower, bound.upper),
+                Some(bic_or_bound::Part::Bic(bic)) => {
+                    let mut sums: BTreeMap<String, i64> = BTreeMap::new();
+                    for relation in bic.relations.iter() {
+                        *sums.entry(relation.id.to_string()).or_default() += relation.coefficient;
+                    }
+                    sums.retain(|_, coefficient| *coefficient != 0);
+                    Part::Bic(sums)
+                },
+                None => panic!("{} has no part", id),
+            };
+            (id.to_string(), part)
+        })
+        .collect()
+}
+
+proptest! {
+    // Converting a tree to a polyhedron and back keeps every bound and the
+    // summed coefficients of every BIC, and converting it again gives the
+    // same polyhedron
+    #[test]
+    fn polyhedron_round_trips(case in cases()) {
+        let polyhedron = to_polyhedron(&case.tree).unwrap();
+        prop_assert!(polyhedron.b.iter().all(|b| *b == 0));
+        let tree = from_polyhedron(&polyhedron, "bias").unwrap();
+        prop_assert_eq!(normalised(&tree), normalised(&case.tree));
+        prop_assert_eq!(to_polyhedron(&tree).unwrap(), polyhedron);
+    }
+}
+
+#[test]
+fn non_zero_bias_is_related_to_a_constant_node() {
+    let polyhedron = Polyhedron {
+        a: CsrMatrix { nrows: 1, ncols: 3, indptr: vec![0, 2], indices: vec![0, 1], data: vec![1, 1] },
+        b: vec![2],
+        variables: vec!["x".to_string(), "y".to_string(), "and".to_string()],
+        bounds: vec![Bound { lower: 0, upper: 1 }, Bound { lower: 0, upper: 1 }, Bound { lower: 0, upper: 1 }],
+        rows: vec!["and".to_string()],
+    };
+    assert_eq!(polyhedron.to_dense(), vec![vec![2, 1, 1, 0]]);
+
+    let tree = from_polyhedron(&polyhedron, "bias").unwrap();
+    let mut expected = LinearBoundedTree::default();
+    expected.nodes.insert("x".to_string(), bound(0, 1));
+    expected.nodes.insert("y".to_string(), bound(0, 1));
+    expected.nodes.insert("and".to_string(), bic(&[("x", 1), ("y", 1), ("bias", -2)]));
+    expected.nodes.insert("bias".to_string(), bound(1, 1));
+    assert_eq!(tree, expected);
+
+    assert_eq!(from_polyhedron(&polyhedron, "x"), Err(LbtError::InvalidId("x".to_string())));
+}
+
+#[test]
+fn malformed_polyhedron_is_rejected() {
+    let polyhedron = Polyhedron {
+        a: CsrMatrix { nrows: 1, ncols: 2, indptr: vec![0, 1], indices: vec![2], data: vec![1] },
+        b: vec![0],
+        variables: vec!["x".to_string(), "and".to_string()],
+        bounds: vec![Bound { lower: 0, upper: 1 }, Bound { lower: 0, upper: 1 }],
+        rows: vec!["and".to_string()],
+    };
+    assert!(matches!(from_polyhedron(&polyhedron, "bias"), Err(LbtError::Malformed(_))));
+}
+
+#[test]
+fn relations_that_overflow_when_summed_are_rejected() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("b".to_string(), bic(&[("x", i64::MAX), ("x", 1)]));
+    assert!(matches!(to_polyhedron(&tree), Err(LbtError::Malformed(_))));
+
+    tree.nodes.insert("b".to_string(), bic(&[("x", i64::MAX), ("x", -1)]));
+    assert_eq!(to_polyhedron(&tree).unwrap().a.data, vec![i64::MAX - 1]);
+}
+
+#[test]
+fn relation_to_missing_node_is_rejected() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("b".to_string(), bic(&[("gone", 1)]));
+    assert_eq!(to_polyhedron(&tree), Err(LbtError::UnknownNode("gone".to_string())));
+}
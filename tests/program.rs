@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use proptest::prelude::*;
+
+use puan_eval::error::LbtError;
+use puan_eval::program::{self, Instruction, Program};
+use puan_eval::propagate::propagate;
+use puan_eval::puan_core::{bic_or_bound, Bound, LinearBoundedTree};
+
+mod common;
+
+use common::{bic, bound, cases};
+
+// Returns the bound of every node propagate leaves as a Bound
+fn expected(tree: &LinearBoundedTree) -> HashMap<String, Bound> {
+    propagate(tree).unwrap().nodes.into_iter()
+        .filter_map(|(id, node)| match node.part {
+            Some(bic_or_bound::Part::Bound(bound)) => Some((id, bound)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn bounds(program: &Program) -> HashMap<String, Bound> {
+    program.bounds(&program.run()).into_iter().map(|(id, bound)| (id.to_string(), bound)).collect()
+}
+
+proptest! {
+    // Running the program gives the bounds propagate gives, also for leaves
+    // with any bound
+    #[test]
+    fn run_agrees_with_propagate(case in cases(), ranges in prop::collection::vec((-4i64..=4, 0i64..=4), 8)) {
+        let mut tree = case.tree.clone();
+        let mut ranges = ranges.into_iter();
+        for node in tree.nodes.values_mut() {
+            if let Some(bic_or_bound::Part::Bound(bound)) = &mut node.part {
+                let (lower, width) = ranges.next().unwrap();
+                *bound = Bound { lower, upper: lower + width };
+            }
+        }
+
+        let program = program::compile(&tree).unwrap();
+        prop_assert_eq!(bounds(&program), expected(&tree));
+    }
+
+    #[test]
+    fn bytes_round_trip(case in cases()) {
+        let program = program::compile(&case.tree).unwrap();
+        let read = Program::from_bytes(&program.to_bytes()).unwrap();
+        prop_assert_eq!(&read, &program);
+        prop_assert_eq!(bounds(&read), expected(&case.tree));
+    }
+}
+
+// The true sum is 0, so the BIC is satisfied, even though the partial sums
+// do not fit in 64 bits
+#[test]
+fn sums_past_64_bits_are_exact() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("f".to_string(), bound(1, 1));
+    tree.nodes.insert("b".to_string(), bic(&[("f", i64::MAX), ("f", i64::MAX), ("f", -i64::MAX), ("f", -i64::MAX)]));
+    tree.nodes.insert("c".to_string(), bic(&[("f", i64::MIN), ("f", i64::MIN), ("f", i64::MAX), ("f", i64::MAX), ("f", 1)]));
+
+    let program = program::compile(&tree).unwrap();
+    let bounds = bounds(&program);
+    assert_eq!(bounds["b"], Bound { lower: 1, upper: 1 });
+    assert_eq!(bounds["c"], Bound { lower: 0, upper: 0 });
+}
+
+// A stored sum that was not thresholded is clamped to 64 bits
+#[test]
+fn store_clamps_to_64_bits() {
+    let program = Program {
+        ids: vec!["f".to_string(), "sum".to_string()],
+        instructions: vec![
+            Instruction::Load { slot: 0, lower: 1, upper: 1 },
+            Instruction::MulAcc { slot: 0, coefficient: i64::MAX },
+            Instruction::MulAcc { slot: 0, coefficient: i64::MAX },
+            Instruction::Store { slot: 1 },
+        ],
+        entry: 1,
+    };
+    let memory = program.run();
+    assert_eq!((memory.lower[1], memory.upper[1]), (i64::MAX, i64::MAX));
+}
+
+// The bytes of a program with one Load, one MulAcc, a Threshold and a Store
+fn small() -> Vec<u8> {
+    let program = Program {
+        ids: vec!["x".to_string(), "b".to_string()],
+        instructions: vec![
+            Instruction::Load { slot: 0, lower: 0, upper: 1 },
+            Instruction::MulAcc { slot: 0, coefficient: 1 },
+            Instruction::Threshold,
+            Instruction::Store { slot: 1 },
+        ],
+        entry: 1,
+    };
+    program.to_bytes()
+}
+
+// Offsets into the bytes of `small`
+const VERSION: usize = 4;
+const FIRST_ID: usize = 16;
+const ENTRY: usize = 26;
+const LOAD_SLOT: usize = 31;
+const MULACC: usize = 51;
+
+fn message(bytes: &[u8]) -> String {
+    match Program::from_bytes(bytes) {
+        Err(LbtError::Malformed(message)) => message,
+        other => panic!("expected a malformed program, got {:?}", other),
+    }
+}
+
+#[test]
+fn small_program_is_read() {
+    let bytes = small();
+    assert_eq!(bytes.len(), 70);
+    assert_eq!(&bytes[FIRST_ID..FIRST_ID + 1], b"x");
+    assert_eq!(bytes[MULACC], 1);
+    let program = Program::from_bytes(&bytes).unwrap();
+    assert_eq!(program.entry, 1);
+    assert_eq!(program.bounds(&program.run())["b"], Bound { lower: 1, upper: 1 });
+}
+
+#[test]
+fn bad_magic_is_rejected() {
+    let mut bytes = small();
+    bytes[0] = b'X';
+    assert_eq!(message(&bytes), "not a program");
+    assert_eq!(message(b"LB"), "program is truncated");
+}
+
+#[test]
+fn other_versions_are_rejected() {
+    let mut bytes = small();
+    bytes[VERSION..VERSION + 4].copy_from_slice(&2u32.to_le_bytes());
+    assert_eq!(message(&bytes), "unsupported program version 2");
+}
+
+#[test]
+fn truncated_programs_are_rejected() {
+    let bytes = small();
+    for length in 0..bytes.len() {
+        assert!(matches!(Program::from_bytes(&bytes[..length]), Err(LbtError::Malformed(_))), "{} bytes", length);
+    }
+    assert_eq!(message(&bytes[..bytes.len() - 1]), "program is truncated");
+}
+
+#[test]
+fn trailing_bytes_are_rejected() {
+    let mut bytes = small();
+    bytes.push(0);
+    assert_eq!(message(&bytes), "trailing bytes after program");
+}
+
+#[test]
+fn invalid_utf8_ids_are_rejected() {
+    let mut bytes = small();
+    bytes[FIRST_ID] = 0xff;
+    assert_eq!(message(&bytes), "id is not valid utf-8");
+}
+
+#[test]
+fn entry_past_the_end_is_rejected() {
+    let mut bytes = small();
+    bytes[ENTRY..ENTRY + 4].copy_from_slice(&5u32.to_le_bytes());
+    assert_eq!(message(&bytes), "entry 5 is past the last instruction");
+}
+
+#[test]
+fn slots_out_of_range_are_rejected() {
+    let mut bytes = small();
+    bytes[LOAD_SLOT..LOAD_SLOT + 4].copy_from_slice(&2u32.to_le_bytes());
+    assert_eq!(message(&bytes), "slot 2 is out of range");
+}
+
+#[test]
+fn unknown_opcodes_are_rejected() {
+    let mut bytes = small();
+    bytes[MULACC] = 7;
+    assert_eq!(message(&bytes), "unknown opcode 7");
+}
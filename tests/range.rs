@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use puan_eval::error::LbtError;
+use puan_eval::puan_core::{Bound, LinearBoundedTree};
+use puan_eval::range::{range, Range, RangeMode, RangeOptions};
+
+mod common;
+
+use common::{bic, bound, cases, free_leaves, valid};
+
+proptest! {
+    // The exact range is the smallest and the largest value of the
+    // expression over all assignments that make the roots 1, and the
+    // propagated range contains it
+    #[test]
+    fn range_agrees_with_brute_force(
+        case in cases(),
+        expression in prop::collection::vec((any::<Index>(), -5i64..=5), 0..6),
+        assigned in prop::collection::vec((any::<Index>(), 0i64..=1), 0..3),
+    ) {
+        let ids: Vec<String> = case.tree.nodes.keys().cloned().collect();
+        let mut coefficients: HashMap<String, i64> = HashMap::new();
+        for (node, coefficient) in expression {
+            *coefficients.entry(ids[node.index(ids.len())].to_string()).or_default() += coefficient;
+        }
+        let leaves = free_leaves(&case.tree);
+        let assignment: HashMap<String, Bound> = if leaves.is_empty() {
+            HashMap::new()
+        } else {
+            assigned.into_iter()
+                .map(|(leaf, value)| (leaves[leaf.index(leaves.len())].to_string(), Bound { lower: value, upper: value }))
+                .collect()
+        };
+
+        let mut assigned = case.clone();
+        for (id, value) in assignment.iter() {
+            assigned.tree.nodes.insert(id.to_string(), bound(value.lower, value.upper));
+        }
+        let values: Vec<i64> = valid(&assigned).into_iter()
+            .map(|(_, values)| coefficients.iter().map(|(id, coefficient)| coefficient * values[id]).sum())
+            .collect();
+
+        let options = RangeOptions {
+            roots: case.roots.clone(),
+            expression: coefficients,
+            assignment,
+            mode: RangeMode::Exact,
+            time_limit: None,
+        };
+        let exact = range(&case.tree, &options).unwrap();
+        let propagated = range(&case.tree, &RangeOptions { mode: RangeMode::Propagated, ..options }).unwrap();
+        match (values.iter().min(), values.iter().max()) {
+            (Some(min), Some(max)) => {
+                let exact = exact.unwrap();
+                prop_assert_eq!((exact.lower, exact.upper, exact.exact), (*min, *max, true));
+                let propagated = propagated.unwrap();
+                prop_assert!(propagated.lower <= *min && *max <= propagated.upper, "{:?} does not contain [{}, {}]", propagated, min, max);
+            },
+            _ => prop_assert_eq!(exact, None),
+        }
+    }
+}
+
+// Ends that do not fit in 64 bits are an error in both modes, rather than
+// wrapping around or panicking
+#[test]
+fn expression_that_overflows_is_rejected() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("y".to_string(), bound(0, 1));
+    tree.nodes.insert("f".to_string(), bound(2, 2));
+    tree.nodes.insert("always".to_string(), bic(&[("x", 0)]));
+    let overflowing = [
+        HashMap::from([("x".to_string(), i64::MAX), ("y".to_string(), i64::MAX)]),
+        HashMap::from([("x".to_string(), i64::MIN), ("y".to_string(), -1)]),
+        HashMap::from([("f".to_string(), i64::MAX)]),
+    ];
+    for expression in overflowing {
+        for mode in [RangeMode::Exact, RangeMode::Propagated] {
+            let options = RangeOptions { roots: vec!["x".to_string()], expression: expression.clone(), mode, ..Default::default() };
+            match range(&tree, &options) {
+                Err(LbtError::Malformed(message)) => assert_eq!(message, "the objective does not fit in 64 bits"),
+                other => panic!("{:?} in {:?} gave {:?}", expression, mode, other),
+            }
+        }
+    }
+
+    let expression = HashMap::from([("x".to_string(), i64::MAX), ("y".to_string(), i64::MIN)]);
+    let options = RangeOptions { roots: vec!["always".to_string()], expression, mode: RangeMode::Propagated, ..Default::default() };
+    assert_eq!(range(&tree, &options).unwrap(), Some(Range { lower: i64::MIN, upper: i64::MAX, exact: false }));
+}
@@ -0,0 +1,140 @@
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use puan_eval::propagate::propagate;
+use puan_eval::puan_core::{
+    bic_or_bound, BicOrBound, BinaryInequalityConstraint, Bound, CoefRelation, LinearBoundedTree,
+};
+use puan_eval::simplify::{simplify, SimplifyOptions};
+
+fn bound(lower: i64, upper: i64) -> BicOrBound {
+    BicOrBound { part: Some(bic_or_bound::Part::Bound(Bound { lower, upper })) }
+}
+
+fn bound_of(tree: &LinearBoundedTree, id: &str) -> Option<(i64, i64)> {
+    match &tree.nodes[id].part {
+        Some(bic_or_bound::Part::Bound(bound)) => Some((bound.lower, bound.upper)),
+        _ => None,
+    }
+}
+
+// Random acyclic trees of a few leaves and BICs. Every BIC relates to leaves
+// and BICs before it, possibly more than once, and possibly to a node that is
+// not part of the tree.
+fn trees() -> impl Strategy<Value = LinearBoundedTree> {
+    let leaves = prop::collection::vec(prop::sample::select(vec![(0, 0), (1, 1), (0, 1), (0, 2), (-1, 1)]), 1..5);
+    let bics = prop::collection::vec(prop::collection::vec((any::<Index>(), -4i64..=4), 0..6), 1..8);
+    (leaves, bics, any::<bool>()).prop_map(|(leaves, bics, dangling)| {
+        let mut tree = LinearBoundedTree::default();
+        for (i, (lower, upper)) in leaves.iter().enumerate() {
+            tree.nodes.insert(format!("leaf{}", i), bound(*lower, *upper));
+        }
+        for (i, relations) in bics.into_iter().enumerate() {
+            let available = leaves.len() + i + dangling as usize;
+            let relations = relations.into_iter()
+                .map(|(child, coefficient)| {
+                    let child = child.index(available);
+                    let id = if child < leaves.len() {
+                        format!("leaf{}", child)
+                    } else if child < leaves.len() + i {
+                        format!("bic{}", child - leaves.len())
+                    } else {
+                        "missing".to_string()
+                    };
+                    CoefRelation { id, coefficient }
+                })
+                .collect();
+            tree.nodes.insert(format!("bic{}", i), BicOrBound {
+                part: Some(bic_or_bound::Part::Bic(BinaryInequalityConstraint { relations })),
+            });
+        }
+        tree
+    })
+}
+
+fn options() -> impl Strategy<Value = SimplifyOptions> {
+    prop::array::uniform5(any::<bool>()).prop_map(|[fold, zeros, merge, gcd, trivial]| SimplifyOptions {
+        fold_constants: fold,
+        drop_zeros: zeros,
+        merge_duplicates: merge,
+        normalise_gcd: gcd,
+        remove_trivial: trivial,
+        ..Default::default()
+    })
+}
+
+// Returns every way to fix the leaves of a tree to a single value within
+// their bound
+fn assignments(tree: &LinearBoundedTree) -> Vec<Vec<(String, i64)>> {
+    let mut leaves: Vec<(&String, i64, i64)> = tree.nodes.iter()
+        .filter_map(|(id, node)| match &node.part {
+            Some(bic_or_bound::Part::Bound(bound)) => Some((id, bound.lower, bound.upper)),
+            _ => None,
+        })
+        .collect();
+    leaves.sort();
+
+    let mut assignments: Vec<Vec<(String, i64)>> = vec![Vec::new()];
+    for (id, lower, upper) in leaves {
+        assignments = assignments.into_iter()
+            .flat_map(|assignment| (lower..=upper).map(move |value| {
+                let mut assignment = assignment.clone();
+                assignment.push((id.to_string(), value));
+                assignment
+            }))
+            .collect();
+    }
+    assignments
+}
+
+fn assign(tree: &LinearBoundedTree, assignment: &[(String, i64)]) -> LinearBoundedTree {
+    let mut tree = tree.clone();
+    for (id, value) in assignment {
+        tree.nodes.insert(id.to_string(), bound(*value, *value));
+    }
+    tree
+}
+
+proptest! {
+    // Under every assignment of all leaves, every node of the original tree
+    // propagates to the same value in the simplified tree
+    #[test]
+    fn simplify_keeps_values(tree in trees(), options in options()) {
+        let simplified = simplify(&tree, &options).unwrap();
+        for assignment in assignments(&tree) {
+            let expected = propagate(&assign(&tree, &assignment)).unwrap();
+            let actual = propagate(&assign(&simplified, &assignment)).unwrap();
+            for id in tree.nodes.keys() {
+                prop_assert_eq!(bound_of(&expected, id), bound_of(&actual, id), "{} under {:?}", id, assignment);
+            }
+        }
+    }
+
+    // Without any assignment, the simplified tree propagates to the same or
+    // tighter bounds, and BICs are propagated in both trees or in neither
+    #[test]
+    fn simplify_never_widens_bounds(tree in trees(), options in options()) {
+        let expected = propagate(&tree).unwrap();
+        let actual = propagate(&simplify(&tree, &options).unwrap()).unwrap();
+        for id in tree.nodes.keys() {
+            match (bound_of(&expected, id), bound_of(&actual, id)) {
+                (Some((lower, upper)), Some((simplified_lower, simplified_upper))) => {
+                    prop_assert!(lower <= simplified_lower && simplified_upper <= upper, "{}", id);
+                },
+                (expected, actual) => prop_assert_eq!(expected, actual, "{}", id),
+            }
+        }
+    }
+
+    // Merging relations is the only simplification that may tighten bounds,
+    // so without it the simplified tree propagates to exactly the same bounds
+    #[test]
+    fn simplify_without_merging_keeps_bounds(tree in trees(), options in options()) {
+        let options = SimplifyOptions { merge_duplicates: false, ..options };
+        let expected = propagate(&tree).unwrap();
+        let actual = propagate(&simplify(&tree, &options).unwrap()).unwrap();
+        for id in tree.nodes.keys() {
+            prop_assert_eq!(bound_of(&expected, id), bound_of(&actual, id), "{}", id);
+        }
+    }
+}
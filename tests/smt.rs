@@ -0,0 +1,78 @@
+use puan_eval::error::LbtError;
+use puan_eval::puan_core::LinearBoundedTree;
+use puan_eval::smt::{to_smtlib, SmtOptions};
+
+mod common;
+
+use common::{bic, bound};
+
+// A tree with a BIC named like an SMT-LIB function, an id with a space and a
+// child that is not part of the tree
+fn tree() -> LinearBoundedTree {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("a".to_string(), bound(0, 1));
+    tree.nodes.insert("b c".to_string(), bound(-2, 3));
+    tree.nodes.insert("one".to_string(), bound(1, 1));
+    tree.nodes.insert("+".to_string(), bic(&[("a", 1), ("b c", -2), ("gone", 3)]));
+    tree.nodes.insert("root".to_string(), bic(&[("+", 1), ("one", -1)]));
+    tree
+}
+
+#[test]
+fn script_matches_golden_output() {
+    let options = SmtOptions { roots: vec!["root".to_string()], check_sat: true, get_model: true };
+    let expected = "\
+; LinearBoundedTree with 5 nodes
+(set-logic QF_LIA)
+; gone is not part of the tree
+(declare-const |gone| Int)
+(declare-const |a| Int)
+(assert (and (>= |a| 0) (<= |a| 1)))
+(declare-const |b c| Int)
+(assert (and (>= |b c| (- 2)) (<= |b c| 3)))
+(define-fun |+| () Int (ite (>= (+ (* 1 |a|) (* (- 2) |b c|) (* 3 |gone|)) 0) 1 0))
+(declare-const |one| Int)
+(assert (= |one| 1))
+(define-fun |root| () Int (ite (>= (+ (* 1 |+|) (* (- 1) |one|)) 0) 1 0))
+(assert (= |root| 1))
+(check-sat)
+(get-model)
+";
+    assert_eq!(to_smtlib(&tree(), &options).unwrap(), expected);
+}
+
+#[test]
+fn default_options_only_define_the_tree() {
+    let script = to_smtlib(&tree(), &SmtOptions::default()).unwrap();
+    assert!(script.ends_with("(define-fun |root| () Int (ite (>= (+ (* 1 |+|) (* (- 1) |one|)) 0) 1 0))\n"));
+    assert!(!script.contains("check-sat"));
+}
+
+#[test]
+fn bics_without_relations_and_single_relations_are_written_without_sum() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("empty".to_string(), bic(&[]));
+    tree.nodes.insert("single".to_string(), bic(&[("x", -1)]));
+    let script = to_smtlib(&tree, &SmtOptions::default()).unwrap();
+    assert!(script.contains("(define-fun |empty| () Int (ite (>= 0 0) 1 0))\n"));
+    assert!(script.contains("(define-fun |single| () Int (ite (>= (* (- 1) |x|) 0) 1 0))\n"));
+}
+
+#[test]
+fn invalid_trees_are_rejected() {
+    let mut quoted = tree();
+    quoted.nodes.insert("a|b".to_string(), bound(0, 1));
+    assert_eq!(to_smtlib(&quoted, &SmtOptions::default()), Err(LbtError::InvalidId("a|b".to_string())));
+
+    let mut escaped = tree();
+    escaped.nodes.insert("root".to_string(), bic(&[("back\\slash", 1)]));
+    assert_eq!(to_smtlib(&escaped, &SmtOptions::default()), Err(LbtError::InvalidId("back\\slash".to_string())));
+
+    let options = SmtOptions { roots: vec!["nowhere".to_string()], ..Default::default() };
+    assert_eq!(to_smtlib(&tree(), &options), Err(LbtError::UnknownNode("nowhere".to_string())));
+
+    let mut cyclic = tree();
+    cyclic.nodes.insert("a".to_string(), bic(&[("root", 1)]));
+    assert!(matches!(to_smtlib(&cyclic, &SmtOptions::default()), Err(LbtError::Cycle(_))));
+}
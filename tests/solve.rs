@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+
+use num_bigint::BigUint;
+use proptest::prelude::*;
+
+use puan_eval::cdcl::Solver;
+use puan_eval::count::{count, CountOptions};
+use puan_eval::error::LbtError;
+use puan_eval::puan_core::LinearBoundedTree;
+use puan_eval::solve::{solve, SolveOptions, Status};
+
+mod common;
+
+use common::{bic, bound, cases, is_valid, valid};
+
+proptest! {
+    // The search finds an assignment making all roots 1 if and only if one
+    // exists, and the assignment found does
+    #[test]
+    fn solve_agrees_with_brute_force(case in cases()) {
+        let solution = solve(&case.tree, &SolveOptions { roots: case.roots.clone(), time_limit: None }).unwrap();
+        if valid(&case).is_empty() {
+            prop_assert_eq!(solution.status, Status::Unsat);
+        } else {
+            prop_assert_eq!(solution.status, Status::Sat);
+            prop_assert!(is_valid(&case, &solution.model), "{:?}", solution.model);
+        }
+    }
+}
+
+fn overflowing() -> LinearBoundedTree {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("y".to_string(), bound(0, 1));
+    tree.nodes.insert("b".to_string(), bic(&[("x", 1 << 62), ("y", 1 << 62), ("x", -1)]));
+    tree
+}
+
+// The constraint making b 0 needs a coefficient of 2^63 for b
+#[test]
+fn constraints_that_overflow_are_rejected() {
+    let message = "the relations of b do not fit in 64 bits".to_string();
+    assert!(matches!(Solver::new(&overflowing()), Err(LbtError::Malformed(error)) if error == message));
+    assert!(matches!(solve(&overflowing(), &SolveOptions::default()), Err(LbtError::Malformed(error)) if error == message));
+    assert!(matches!(count(&overflowing(), &CountOptions::default()), Err(LbtError::Malformed(error)) if error == message));
+
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("f".to_string(), bound(1, 1));
+    tree.nodes.insert("b".to_string(), bic(&[("f", i64::MAX), ("f", i64::MAX)]));
+    assert!(matches!(Solver::new(&tree), Err(LbtError::Malformed(_))));
+}
+
+// Relations that cancel out are summed exactly, however large they are
+#[test]
+fn large_relations_that_cancel_out_are_searched() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("y".to_string(), bound(0, 1));
+    tree.nodes.insert("f".to_string(), bound(1, 1));
+    // f + f - f - f + x - 1 >= 0 with f scaled to the ends of i64, i.e. x
+    tree.nodes.insert("a".to_string(), bic(&[("f", i64::MAX), ("f", i64::MAX), ("f", i64::MIN), ("f", -i64::MAX), ("f", 1), ("x", 1), ("f", -1)]));
+    // y - x >= 0 with x scaled away
+    tree.nodes.insert("b".to_string(), bic(&[("x", i64::MAX), ("x", i64::MIN), ("y", 1)]));
+
+    let solution = solve(&tree, &SolveOptions { roots: vec!["a".to_string(), "b".to_string()], time_limit: None }).unwrap();
+    assert_eq!(solution.status, Status::Sat);
+    assert_eq!(solution.model, BTreeMap::from([("x".to_string(), 1), ("y".to_string(), 1)]));
+    assert_eq!(count(&tree, &CountOptions { roots: vec!["a".to_string(), "b".to_string()], ..CountOptions::default() }).unwrap(), Some(BigUint::from(1u32)));
+}
+
+// A linear constraint that does not fit is not added, and the solver can
+// still be used
+#[test]
+fn linear_constraints_that_overflow_are_rejected() {
+    let mut tree = LinearBoundedTree::default();
+    tree.nodes.insert("x".to_string(), bound(0, 1));
+    tree.nodes.insert("y".to_string(), bound(0, 1));
+    let mut solver = Solver::new(&tree).unwrap();
+    let (x, y) = (solver.node("x").unwrap(), solver.node("y").unwrap());
+
+    assert!(matches!(solver.add_linear(&[(x, i64::MIN), (y, i64::MIN)], 0), Err(LbtError::Malformed(_))));
+    assert!(matches!(solver.add_linear(&[(x, i64::MAX), (y, i64::MAX)], i64::MAX), Err(LbtError::Malformed(_))));
+    solver.add_linear(&[(x, i64::MAX), (x, i64::MAX), (x, i64::MIN), (x, i64::MIN)], -2).unwrap();
+    solver.add_linear(&[(x, i64::MAX - 1), (y, -1)], i64::MAX - 1).unwrap();
+    assert_eq!(solver.solve(&[], None), Status::Sat);
+    assert_eq!((solver.value(x), solver.value(y)), (Some(1), Some(0)));
+}
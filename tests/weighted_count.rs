@@ -0,0 +1,59 @@
+use std::collections::{BTreeMap, HashMap};
+
+use proptest::prelude::*;
+
+use puan_eval::count::{weighted_count, WeightedCountOptions};
+use puan_eval::puan_core::bic_or_bound;
+
+mod common;
+
+use common::{cases, free_leaves, valid};
+
+fn close(actual: f64, expected: f64) -> bool {
+    (actual - expected).abs() <= 1e-9 * expected.abs().max(1.0)
+}
+
+proptest! {
+    // The weighted count is the sum of the weights of the assignments that
+    // make the roots 1, and the marginal of a node the share of that weight
+    // in which it is 1
+    #[test]
+    fn weighted_count_agrees_with_brute_force(case in cases(), rates in prop::collection::vec(prop::option::of(0.0..=1.0f64), 8)) {
+        let weights: HashMap<String, f64> = free_leaves(&case.tree).into_iter()
+            .zip(rates)
+            .filter_map(|(leaf, rate)| rate.map(|rate| (leaf, rate)))
+            .collect();
+        let options = WeightedCountOptions { roots: case.roots.clone(), weights: weights.clone(), time_limit: None };
+        let weighted = weighted_count(&case.tree, &options).unwrap().unwrap();
+
+        let mut total = 0.0;
+        let mut ones: BTreeMap<String, f64> = BTreeMap::new();
+        for (assignment, values) in valid(&case) {
+            let weight: f64 = assignment.iter()
+                .map(|(leaf, value)| match (weights.get(leaf), value) {
+                    (Some(rate), 1) => *rate,
+                    (Some(rate), _) => 1.0 - rate,
+                    (None, _) => 1.0,
+                })
+                .product();
+            total += weight;
+            for (id, value) in values.iter() {
+                let free = assignment.contains_key(id)
+                    || matches!(case.tree.nodes[id].part, Some(bic_or_bound::Part::Bic(_)));
+                if free {
+                    *ones.entry(id.to_string()).or_default() += weight * *value as f64;
+                }
+            }
+        }
+
+        prop_assert!(close(weighted.count(), total), "{} != {}", weighted.count(), total);
+        if total == 0.0 {
+            prop_assert!(weighted.marginals.is_empty());
+        } else {
+            prop_assert_eq!(weighted.marginals.keys().collect::<Vec<_>>(), ones.keys().collect::<Vec<_>>());
+            for (id, one) in ones.iter() {
+                prop_assert!(close(weighted.marginals[id], one / total), "{}: {} != {}", id, weighted.marginals[id], one / total);
+            }
+        }
+    }
+}